@@ -0,0 +1,98 @@
+//! `git-recent install-alias`: `git recent` (and optionally a shortcut such as
+//! `git rb`) as aliases in the user's global git config, pointing at this binary.
+
+use std::env;
+use std::error::Error;
+
+use crate::cli::NAME;
+use crate::git::{self, shell_quote};
+use crate::i18n;
+use crate::refname;
+use crate::trace;
+
+/// The alias every install adds.
+pub const ALIAS: &str = "recent";
+/// The shortcut `--shortcut` adds when not given a name.
+pub const DEFAULT_SHORTCUT: &str = "rb";
+
+/// Add `alias.recent`, and `alias.<shortcut>` if asked for, to the global git config.
+/// Aliases already running git-recent are updated; any other alias of the same name
+/// is left alone and reported as an error. Returns the aliases written.
+pub fn install(shortcut: Option<&str>) -> Result<Vec<String>, Box<dyn Error>> {
+    let value = alias_value()?;
+    let existing = global_aliases()?;
+    let mut names = vec![ALIAS];
+    names.extend(shortcut);
+    for name in &names {
+        if let Some((_, current)) = existing.iter().find(|(n, _)| n == name)
+            && !runs_git_recent(current)
+        {
+            return Err(
+                i18n::message("alias.taken", &[("name", name), ("current", current)]).into(),
+            );
+        }
+    }
+    for name in &names {
+        let key = format!("alias.{name}");
+        git::run_git(
+            &i18n::message("progress.adding_alias", &[("name", name)]),
+            &["config", "--global", &key, &value],
+        )?;
+    }
+    Ok(names.into_iter().map(String::from).collect())
+}
+
+/// Remove every global alias that runs git-recent, whatever its name. Returns the
+/// aliases removed.
+pub fn uninstall() -> Result<Vec<String>, Box<dyn Error>> {
+    let mut removed = Vec::new();
+    for (name, value) in global_aliases()? {
+        if !runs_git_recent(&value) {
+            continue;
+        }
+        let key = format!("alias.{name}");
+        git::run_git(
+            &i18n::message("progress.removing_alias", &[("name", &name)]),
+            &["config", "--global", "--unset", &key],
+        )?;
+        removed.push(name);
+    }
+    Ok(removed)
+}
+
+/// `!<path of this binary>`, so the alias works even where git-recent is not on the PATH.
+fn alias_value() -> Result<String, Box<dyn Error>> {
+    let exe = env::current_exe()?;
+    let path = refname::decode(exe.as_os_str().as_encoded_bytes());
+    Ok(format!("!{}", shell_quote(&path)))
+}
+
+/// Whether an alias's `value` is a shell alias running git-recent.
+fn runs_git_recent(value: &str) -> bool {
+    value.starts_with('!') && value.contains(NAME)
+}
+
+/// The `alias.*` entries of the global git config, as (name, value) pairs.
+fn global_aliases() -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let output = trace::output(git::command().args([
+        "config",
+        "--global",
+        "--null",
+        "--get-regexp",
+        r"^alias\.",
+    ]))?;
+    // Exit status 1 only means there are no aliases yet.
+    if !output.status.success() && output.status.code() != Some(1) {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(git::git_failed("config", stderr.trim()).into());
+    }
+    // With --null each entry is "<key>\n<value>\0".
+    Ok(refname::decode(&output.stdout)
+        .split('\0')
+        .filter_map(|entry| entry.split_once('\n'))
+        .filter_map(|(key, value)| {
+            let name = key.strip_prefix("alias.")?;
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect())
+}
@@ -0,0 +1,136 @@
+//! `annotate`: a user command run for each branch the picker shows, such as a CI
+//! status script, whose first line of output becomes an extra column. Commands run
+//! on worker threads, several at a time and each for a few seconds at most, and the
+//! column fills in as they finish.
+
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::git;
+use crate::refname;
+use crate::trace;
+
+/// How many annotation commands run at once.
+const PARALLEL: usize = 8;
+/// How long an annotation command may take before it is killed and its branch left
+/// without an annotation.
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// The longest annotation shown, in characters; the rest is cut off with `…`.
+const MAX_WIDTH: usize = 40;
+
+/// Run the `annotate` command `template` for `branch` with `sh -c`, the branch filling
+/// in `{}` and `GIT_RECENT_BRANCH`. Returns the first line of its output, cleaned of
+/// control characters; `None` if it fails, prints nothing or takes longer than
+/// `TIMEOUT`. `shorten` cuts it to the width shown.
+pub fn run(template: &str, branch: &str) -> Option<String> {
+    let command = git::branch_command(template, branch);
+    let output = trace::output_timeout(
+        Command::new("sh")
+            .arg("-c")
+            .arg(refname::to_os(&command))
+            .env("GIT_RECENT_BRANCH", refname::to_os(branch)),
+        TIMEOUT,
+    )
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = refname::decode(&output.stdout);
+    let line = text.lines().next().unwrap_or_default();
+    // Escape sequences would garble the row around the annotation.
+    let line: String = line.chars().filter(|c| !c.is_control()).collect();
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    Some(line.to_string())
+}
+
+/// `annotation` cut to `MAX_WIDTH` characters, with `ellipsis` in place of the rest.
+pub fn shorten(annotation: &str, ellipsis: &str) -> String {
+    match annotation.char_indices().nth(MAX_WIDTH) {
+        Some((end, _)) => format!("{}{ellipsis}", &annotation[..end]),
+        None => annotation.to_string(),
+    }
+}
+
+/// One branch to annotate, for the generation it was asked for in.
+struct Job {
+    generation: u64,
+    branch: String,
+}
+
+/// The annotation of one branch, tagged with the generation it was asked for in.
+pub struct Annotated {
+    pub generation: u64,
+    pub branch: String,
+    pub annotation: Option<String>,
+}
+
+/// Worker threads running annotation commands. They exit once the loader is dropped.
+pub struct AnnotationLoader {
+    jobs: Sender<Job>,
+    results: Receiver<Annotated>,
+}
+
+impl AnnotationLoader {
+    /// Start `PARALLEL` workers that annotate branches with `annotate` (such as `run`
+    /// with the configured template). Each takes the next branch asked for as soon as
+    /// it is done with its last, so a slow command holds up only its own worker, and
+    /// every branch is answered as soon as its own command finishes.
+    pub fn spawn<F>(annotate: F) -> AnnotationLoader
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        let (jobs, worker_jobs) = mpsc::channel::<Job>();
+        let (worker_results, results) = mpsc::channel();
+        let worker_jobs = Arc::new(Mutex::new(worker_jobs));
+        let annotate = Arc::new(annotate);
+        for _ in 0..PARALLEL {
+            let (jobs, results, annotate) = (
+                Arc::clone(&worker_jobs),
+                worker_results.clone(),
+                Arc::clone(&annotate),
+            );
+            thread::spawn(move || {
+                loop {
+                    // The lock is only held while waiting for a job, not while running it.
+                    let job = jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                    let Ok(job) = job else {
+                        break;
+                    };
+                    let annotated = Annotated {
+                        generation: job.generation,
+                        annotation: annotate(&job.branch),
+                        branch: job.branch,
+                    };
+                    if results.send(annotated).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        AnnotationLoader { jobs, results }
+    }
+
+    /// Ask for the annotations of `branches`; the answers come from `receive`.
+    pub fn request(&self, generation: u64, branches: Vec<String>) {
+        for branch in branches {
+            // Only fails if the workers are gone, and then there is nobody to ask.
+            let _ = self.jobs.send(Job { generation, branch });
+        }
+    }
+
+    /// The annotations that have arrived, waiting up to `wait` for the first one.
+    pub fn receive(&self, wait: Duration) -> Vec<Annotated> {
+        let Ok(first) = self.results.recv_timeout(wait) else {
+            return Vec::new();
+        };
+        let mut annotated = vec![first];
+        annotated.extend(self.results.try_iter());
+        annotated
+    }
+}
@@ -0,0 +1,1561 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::annotate::{self, AnnotationLoader};
+use crate::backend::{GitBackend, SystemGit};
+use crate::confirm::ConfirmDialog;
+use crate::cursor::Cursor;
+use crate::error::AppError;
+use crate::filter;
+use crate::git::{self, BranchStatus, LoadOptions, Scope, SwitchCommand};
+use crate::hooks::Hooks;
+use crate::i18n;
+use crate::keymap::{Action, Keymap};
+use crate::keys::{Key, KeyReader};
+use crate::loader::StatusLoader;
+use crate::menu::Menu;
+use crate::notify;
+use crate::pulls::{PullLoader, PullRequest};
+use crate::queries::QueryHistory;
+use crate::refname;
+use crate::screen::Screen;
+use crate::terminal::{self, RawModeGuard};
+use crate::text_input::TextInput;
+use crate::theme::Theme;
+use crate::trace;
+
+/// Default number of branches shown at once in the picker (`height`).
+pub const NO_OF_VISIBLE_BRANCHES: usize = 5;
+
+/// Rows of the diff preview shown at once.
+const PREVIEW_HEIGHT: usize = 15;
+/// The most lines of a diff the preview loads.
+const PREVIEW_LINES: usize = 400;
+
+/// How often the picker checks for statuses from a `StatusLoader` while it waits for keys.
+const STATUS_POLL: Duration = Duration::from_millis(20);
+
+pub const CLEAR_SCREEN: &str = "\x1b[H\x1b[J";
+const CURSOR_TO_LEFT: &str = "\x1b[G";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const RESET: &str = "\x1b[0m";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// What the action menu offers, in order, with the message ids of their labels.
+const MENU_ACTIONS: &[(Action, &str)] = &[
+    (Action::Select, "action.check_out"),
+    (Action::Create, "action.create"),
+    (Action::Rename, "action.rename"),
+    (Action::Delete, "action.delete"),
+    (Action::Diff, "action.diff"),
+    (Action::Log, "action.log"),
+    (Action::Push, "action.push"),
+    (Action::PullRequest, "action.pull_request"),
+    (Action::Yank, "action.yank"),
+];
+
+/// Action waiting on a confirmation dialog.
+enum PendingAction {
+    Delete(Vec<String>),
+    ForceDelete(Vec<String>),
+    /// Close the picker with the highlighted branch (`confirm.checkout`).
+    Checkout,
+}
+
+/// What confirming a branch in the picker does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Pick one branch and return it (the default).
+    Pick,
+    /// Mark branches with Space and delete them with Enter; the picker stays open.
+    Delete,
+}
+
+/// The diff preview pane below the list.
+struct Preview {
+    /// The branch `lines` are the diff of; `None` until loaded, and again once stale.
+    branch: Option<String>,
+    lines: Vec<String>,
+    /// What is shown instead of an empty diff: why there is none.
+    note: Option<String>,
+    /// The first line shown.
+    scroll: usize,
+}
+
+/// What the text in the active input line is for.
+enum InputPurpose {
+    Filter,
+    Create { base: String },
+    Rename { from: String },
+}
+
+/// The branch picker: the list, its selection and filter, and whatever prompt or
+/// dialog is open. Settings are public fields, to be adjusted after `App::new`.
+pub struct App {
+    branches: Vec<String>,
+    current_branch: String,
+    /// The abbreviated commit HEAD is detached at, shown in the header.
+    pub detached: Option<String>,
+    /// Branches checked out in other worktrees, marked `+`, with the worktree's path.
+    pub other_worktrees: HashMap<String, PathBuf>,
+    /// Indexes into `branches` that match the current filter.
+    visible: Vec<usize>,
+    filter: String,
+    selected: usize,
+    offset: usize,
+    confirm: Option<(ConfirmDialog, PendingAction)>,
+    input: Option<(TextInput, InputPurpose)>,
+    /// The open action menu and the action behind each of its items.
+    menu: Option<(Menu, Vec<Action>)>,
+    message: Option<String>,
+    /// What Enter does; `Mode::Pick` unless changed.
+    pub mode: Mode,
+    /// Number of branches shown at once (`height`); at least 1.
+    pub height: usize,
+    /// Announce changes as plain lines instead of drawing the full-screen picker
+    /// (`accessible`), for screen readers.
+    pub accessible: bool,
+    /// Draw with ASCII characters only, marking the selection with `>` (`ascii`); pair
+    /// with `Theme::ascii`.
+    pub ascii: bool,
+    /// Draw no colors at all, not even the diff preview's (`NO_COLOR`); pair with
+    /// `Theme::ascii`.
+    pub no_color: bool,
+    /// The lines accessible mode last announced.
+    announced: Vec<String>,
+    /// Branches marked for deletion in `Mode::Delete`.
+    marked: Vec<String>,
+    /// How branches were loaded, reused when reloading.
+    load: LoadOptions,
+    keys: KeyReader,
+    /// Key bindings (`keys.*`).
+    pub keymap: Keymap,
+    /// Colors (`colors.*`).
+    pub theme: Theme,
+    /// Whether deleting asks first (`confirm.delete`).
+    pub confirm_delete: bool,
+    /// How checkouts are run (`switch`, `switch_args`).
+    pub switch: SwitchCommand,
+    /// Commands run around checkouts (`hooks.*`).
+    pub hooks: Hooks,
+    /// Glob patterns of branches that cannot be deleted or renamed (`protected`).
+    pub protected: Vec<String>,
+    /// Whether choosing a branch to check out asks first (`confirm.checkout`).
+    pub confirm_checkout: bool,
+    /// Whether copying also goes through the terminal's OSC 52 (`clipboard.osc52`).
+    pub osc52: bool,
+    /// Earlier filter queries, recalled with Up/Down while filtering.
+    pub queries: QueryHistory,
+    /// Where git commands go; `SystemGit` unless replaced, e.g. by a `MockGit`.
+    pub git: Box<dyn GitBackend>,
+    /// What ahead/behind is counted against instead of each branch's upstream
+    /// (`--default-branch`).
+    pub counts_against: Option<String>,
+    /// What merged status is checked against; none is shown without it.
+    pub merged_into: Option<String>,
+    /// Ahead/behind and merged status of the branches shown so far, by name.
+    status: HashMap<String, BranchStatus>,
+    /// Loads statuses on a worker thread; without one they are loaded before drawing.
+    pub status_loader: Option<StatusLoader>,
+    /// Branches whose status was asked of `status_loader` and has not arrived yet.
+    status_pending: HashSet<String>,
+    /// Bumped whenever the list is reloaded, so answers about the old list are dropped.
+    status_generation: u64,
+    /// Output of the `annotate` command by branch name; `None` when it had none.
+    annotations: HashMap<String, Option<String>>,
+    /// Runs the `annotate` command for the shown branches; no column without one.
+    pub annotation_loader: Option<AnnotationLoader>,
+    /// Branches whose annotation was asked for and has not arrived yet.
+    annotation_pending: HashSet<String>,
+    /// Pull requests by branch name (`pull_requests`).
+    pub pulls: HashMap<String, PullRequest>,
+    /// Loads `pulls` on a worker thread; dropped once they have arrived.
+    pub pull_loader: Option<PullLoader>,
+    /// The diff preview, while it is open.
+    preview: Option<Preview>,
+    screen: Screen,
+}
+
+impl App {
+    /// A picker over `branches` as loaded with `load` (which is reused for reloads),
+    /// marking `current_branch`, with the first branch selected.
+    pub fn new(branches: Vec<String>, current_branch: String, load: LoadOptions) -> Self {
+        let mut app = App {
+            branches,
+            current_branch,
+            detached: None,
+            other_worktrees: HashMap::new(),
+            visible: Vec::new(),
+            filter: String::new(),
+            offset: 0,
+            selected: 0,
+            confirm: None,
+            input: None,
+            menu: None,
+            message: None,
+            mode: Mode::Pick,
+            height: NO_OF_VISIBLE_BRANCHES,
+            accessible: false,
+            ascii: false,
+            no_color: false,
+            announced: Vec::new(),
+            marked: Vec::new(),
+            load,
+            keys: KeyReader::new(),
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            confirm_delete: true,
+            confirm_checkout: false,
+            osc52: true,
+            protected: Vec::new(),
+            switch: SwitchCommand::default(),
+            hooks: Hooks::default(),
+            queries: QueryHistory::default(),
+            git: Box::new(SystemGit),
+            counts_against: None,
+            merged_into: None,
+            status: HashMap::new(),
+            status_loader: None,
+            status_pending: HashSet::new(),
+            status_generation: 0,
+            annotations: HashMap::new(),
+            annotation_loader: None,
+            annotation_pending: HashSet::new(),
+            pulls: HashMap::new(),
+            pull_loader: None,
+            preview: None,
+            screen: Screen::new(),
+        };
+        app.refilter();
+        app
+    }
+
+    /// Recompute the visible branches after the filter or branch list changed. Without
+    /// a filter only the first `limit` branches are listed; a filter searches them all.
+    fn refilter(&mut self) {
+        let limit = if self.filter.is_empty() {
+            self.load.limit.unwrap_or(usize::MAX)
+        } else {
+            usize::MAX
+        };
+        let query = filter::Query::new(&self.filter, self.load.case);
+        self.visible = self
+            .branches
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| query.matches(b))
+            .map(|(i, _)| i)
+            .take(limit)
+            .collect();
+        if self.selected >= self.visible.len() {
+            self.selected = self.visible.len().saturating_sub(1);
+        }
+        if self.offset > self.selected {
+            self.offset = self.selected;
+        }
+        // Keep the window full when the list shrinks near its end.
+        self.offset = self
+            .offset
+            .min(self.visible.len().saturating_sub(self.height));
+    }
+
+    /// Replace the filter query, resetting the selection when it actually changed.
+    pub fn set_filter(&mut self, filter: String) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.selected = 0;
+            self.offset = 0;
+            self.refilter();
+        }
+    }
+
+    /// Select `cursor.branch` at the same row of the window, if it is still listed.
+    pub fn restore(&mut self, cursor: &Cursor) {
+        let Some(index) = self
+            .visible
+            .iter()
+            .position(|&i| self.branches[i] == cursor.branch)
+        else {
+            return;
+        };
+        self.selected = index;
+        let row = cursor.row.min(self.height - 1);
+        self.offset = index.saturating_sub(row);
+        self.refilter();
+    }
+
+    /// The selected branch and its row in the window, for `restore` in a later run.
+    pub fn cursor(&self) -> Option<Cursor> {
+        Some(Cursor {
+            branch: self.selected_branch()?.clone(),
+            row: self.selected - self.offset,
+        })
+    }
+
+    /// Show `message` below the list until the next key.
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    /// Select `branch` if it is visible. Returns whether it was.
+    pub fn select_branch(&mut self, branch: &str) -> bool {
+        match self
+            .visible
+            .iter()
+            .position(|&i| self.branches[i] == branch)
+        {
+            Some(index) => {
+                self.select(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn selected_branch(&self) -> Option<&String> {
+        self.visible.get(self.selected).map(|&i| &self.branches[i])
+    }
+
+    /// Every branch, in list order, whatever the filter and limit.
+    pub fn branches(&self) -> &[String] {
+        &self.branches
+    }
+
+    pub fn visible_branches(&self) -> impl ExactSizeIterator<Item = &String> {
+        self.visible.iter().map(|&i| &self.branches[i])
+    }
+
+    pub fn current_branch(&self) -> &str {
+        &self.current_branch
+    }
+
+    pub fn load_options(&self) -> &LoadOptions {
+        &self.load
+    }
+
+    fn is_protected(&self, branch: &str) -> bool {
+        self.protected.iter().any(|p| filter::glob_match(p, branch))
+    }
+
+    /// The first line of the picker: what it is for, with notes on scope and filter.
+    fn title(&self) -> String {
+        let mut notes = Vec::new();
+        if !self.load.scope.label().is_empty() {
+            notes.push(self.load.scope.label().to_string());
+        }
+        if let Some(commit) = &self.detached {
+            notes.push(self.format_message("picker.detached", &[("commit", commit)]));
+        }
+        if !self.filter.is_empty() {
+            notes.push(self.format_message("picker.filter", &[("filter", &self.filter)]));
+        } else if self.visible.len() < self.branches.len() {
+            notes.push(self.format_message(
+                "picker.limited",
+                &[
+                    ("shown", &self.visible.len()),
+                    ("total", &self.branches.len()),
+                ],
+            ));
+        }
+        let title = match self.mode {
+            Mode::Pick if self.load.scope == Scope::Tags => self.text("picker.tag_title"),
+            Mode::Pick => self.text("picker.title"),
+            Mode::Delete => self.text("picker.delete_title"),
+        };
+        if notes.is_empty() {
+            format!("{title}:")
+        } else {
+            format!("{title} ({}):", notes.join(", "))
+        }
+    }
+
+    /// Text `id` from the catalog as the picker draws it, spelled with ASCII only in
+    /// ASCII mode.
+    fn text(&self, id: &'static str) -> Cow<'static, str> {
+        let text = i18n::text(id);
+        if self.ascii {
+            i18n::to_ascii(text)
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Like `i18n::message`, spelled as `text` does; the values filled in are not.
+    fn format_message(&self, id: &'static str, args: &[(&str, &dyn Display)]) -> String {
+        i18n::fill(&self.text(id), args)
+    }
+
+    /// Build the lines of the menu; `Screen` decides which of them need repainting.
+    pub fn frame(&self) -> Vec<String> {
+        let mut lines = vec![self.title()];
+        let less = self.text("picker.less");
+        if self.offset > 0 {
+            lines.push(format!("  {}{less}{RESET}", self.theme.pagination));
+        } else {
+            lines.push(format!("  {}{less}{RESET}", self.theme.pagination_inactive));
+        }
+        if self.visible.is_empty() {
+            lines.push(format!("   {}", self.text("picker.no_matches")));
+        }
+        for (i, b) in self
+            .visible
+            .iter()
+            .map(|&i| &self.branches[i])
+            .skip(self.offset)
+            .take(self.height)
+            .enumerate()
+        {
+            let current_mark = if b == &self.current_branch {
+                "*"
+            } else if self.marked.contains(b) {
+                "x"
+            } else if self.other_worktrees.contains_key(b) {
+                "+"
+            } else {
+                " "
+            };
+            let lock = match (self.is_protected(b), self.ascii) {
+                (false, _) => String::new(),
+                (true, false) => " 🔒".to_string(),
+                (true, true) => format!(" {}", self.text("picker.protected")),
+            };
+            let mut status = self
+                .status
+                .get(b)
+                .map(|status| status_columns(status, self.ascii))
+                .unwrap_or_default();
+            if let Some(pull) = self.pull(b) {
+                status.push_str(&format!(" {pull}"));
+            }
+            if let Some(Some(annotation)) = self.annotations.get(b) {
+                let ellipsis = if self.ascii { "..." } else { "…" };
+                status.push_str(&format!(" {}", annotate::shorten(annotation, ellipsis)));
+            }
+            if i == self.selected - self.offset {
+                // Highlight selection: blue background, black text
+                let pointer = if self.ascii { ">" } else { " " };
+                lines.push(format!(
+                    "{pointer}{}{current_mark} {b}{lock}{status}{RESET}",
+                    self.theme.highlight
+                ));
+            } else {
+                lines.push(format!(" {current_mark} {b}{lock}{status}"));
+            }
+        }
+        let more = self.text("picker.more");
+        if self.offset + self.height < self.visible.len() {
+            lines.push(format!("  {}{more}{RESET}", self.theme.pagination));
+        } else {
+            lines.push(format!("  {}{more}{RESET}", self.theme.pagination_inactive));
+        }
+        if let Some((dialog, _)) = &self.confirm {
+            lines.push(dialog.render(&self.theme.highlight));
+        } else if let Some((menu, _)) = &self.menu {
+            lines.extend(menu.render(&self.theme.highlight));
+        } else if let Some((input, _)) = &self.input {
+            lines.push(input.render());
+        } else if let Some(message) = &self.message {
+            lines.push(message.clone());
+        }
+        if let Some(preview) = &self.preview
+            && let Some(branch) = &preview.branch
+        {
+            lines.extend(self.preview_lines(branch, preview));
+        }
+        // Branch names anywhere in the frame may hold bytes that are not UTF-8.
+        lines
+            .iter()
+            .map(|line| refname::display(line).into_owned())
+            .collect()
+    }
+
+    /// The preview pane for `branch`: a rule with what it shows, then its window of
+    /// the diff.
+    fn preview_lines(&self, branch: &str, preview: &Preview) -> Vec<String> {
+        let mut title = self.format_message("preview.title", &[("branch", &branch)]);
+        if preview.lines.len() > PREVIEW_HEIGHT {
+            let last = (preview.scroll + PREVIEW_HEIGHT).min(preview.lines.len());
+            title.push(' ');
+            title.push_str(&self.format_message(
+                "preview.position",
+                &[
+                    ("first", &(preview.scroll + 1)),
+                    ("last", &last),
+                    ("total", &preview.lines.len()),
+                ],
+            ));
+        }
+        let rule = if self.ascii { "--" } else { "──" };
+        let mut lines = vec![format!(
+            "{}{rule} {title} {rule}{RESET}",
+            self.theme.pagination_inactive
+        )];
+        if let Some(note) = &preview.note {
+            lines.push(format!("   {note}"));
+        }
+        lines.extend(
+            preview
+                .lines
+                .iter()
+                .skip(preview.scroll)
+                .take(PREVIEW_HEIGHT)
+                .map(|line| match self.ascii || self.no_color {
+                    false => format!("{line}{RESET}"),
+                    true => without_colors(line),
+                }),
+        );
+        lines
+    }
+
+    /// Load the diff of the highlighted branch into the open preview, unless it holds
+    /// that branch's already. Only the first `PREVIEW_LINES` lines are loaded.
+    fn load_preview(&mut self) {
+        let branch = self.selected_branch().cloned();
+        let Some(preview) = &mut self.preview else {
+            return;
+        };
+        if preview.branch.is_some() && preview.branch == branch {
+            return;
+        }
+        preview.scroll = 0;
+        preview.note = None;
+        preview.lines = Vec::new();
+        preview.branch = branch.clone();
+        let Some(branch) = branch else {
+            return;
+        };
+        let (note, lines) = self.preview_of(&branch);
+        if let Some(preview) = &mut self.preview {
+            preview.note = note;
+            preview.lines = lines;
+        }
+    }
+
+    /// The first lines of the diff the preview shows for `branch`, or a note saying
+    /// why there are none.
+    fn preview_of(&mut self, branch: &str) -> (Option<String>, Vec<String>) {
+        if branch == self.current_branch {
+            let note = self.format_message("diff.current", &[("branch", &branch)]);
+            return (Some(note), Vec::new());
+        }
+        let range = self.diff_range(branch);
+        match self.git.diff_preview(&range, PREVIEW_LINES + 1) {
+            Ok(mut lines) => {
+                if lines.len() > PREVIEW_LINES {
+                    lines.truncate(PREVIEW_LINES);
+                    lines.push(format!(
+                        "{}{}",
+                        self.theme.pagination_inactive,
+                        self.format_message("preview.truncated", &[("count", &PREVIEW_LINES)])
+                    ));
+                }
+                let note = lines
+                    .is_empty()
+                    .then(|| self.format_message("preview.empty", &[("branch", &branch)]));
+                (note, lines)
+            }
+            Err(e) => (
+                Some(e.lines().next().unwrap_or_default().to_string()),
+                Vec::new(),
+            ),
+        }
+    }
+
+    /// Scroll the preview by half its height, towards the end when `down`.
+    fn scroll_preview(&mut self, down: bool) {
+        let Some(preview) = &mut self.preview else {
+            return;
+        };
+        let step = PREVIEW_HEIGHT.div_ceil(2);
+        preview.scroll = if down {
+            (preview.scroll + step).min(preview.lines.len().saturating_sub(PREVIEW_HEIGHT))
+        } else {
+            preview.scroll.saturating_sub(step)
+        };
+    }
+
+    /// Load the status of the shown branches and those a page further down, unless
+    /// already known, so that scrolling a page usually finds it ready. With a
+    /// `status_loader` this only asks for it (see `receive_status`). Failures are
+    /// remembered as "no status" rather than retried.
+    pub fn load_status(&mut self) {
+        let missing: Vec<String> = self
+            .visible_branches()
+            .skip(self.offset)
+            .take(2 * self.height)
+            .filter(|b| !self.status.contains_key(*b) && !self.status_pending.contains(*b))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        if let Some(loader) = &self.status_loader {
+            self.status_pending.extend(missing.iter().cloned());
+            loader.request(
+                self.status_generation,
+                missing,
+                self.load.scope,
+                self.counts_against.clone(),
+                self.merged_into.clone(),
+            );
+            return;
+        }
+        let statuses = self
+            .git
+            .branch_status(
+                &missing,
+                self.load.scope,
+                self.counts_against.as_deref(),
+                self.merged_into.as_deref(),
+            )
+            .unwrap_or_default();
+        let mut statuses = statuses.into_iter();
+        for branch in missing {
+            let status = statuses.next().unwrap_or_default();
+            self.store_status(branch, status);
+        }
+    }
+
+    /// Take the statuses `status_loader` has loaded, waiting up to `wait` for the
+    /// first. Returns whether any arrived.
+    pub fn receive_status(&mut self, wait: Duration) -> bool {
+        let Some(loader) = &self.status_loader else {
+            return false;
+        };
+        let mut received = false;
+        for loaded in loader.receive(wait) {
+            if loaded.generation != self.status_generation {
+                continue;
+            }
+            for (branch, status) in loaded.statuses {
+                self.status_pending.remove(&branch);
+                self.store_status(branch, status);
+                received = true;
+            }
+        }
+        received
+    }
+
+    fn store_status(&mut self, branch: String, mut status: BranchStatus) {
+        // Every branch is merged into itself (or its remote counterpart, such as
+        // `main` into `origin/main`); not worth a mark.
+        status.merged &= self.merged_into.as_deref().is_none_or(|target| {
+            target != branch && target.split_once('/').map(|(_, b)| b) != Some(&branch)
+        });
+        self.status.insert(branch, status);
+    }
+
+    /// Ask `annotation_loader` for the annotations of the same rows `load_status`
+    /// covers, unless already known or on their way.
+    pub fn load_annotations(&mut self) {
+        let Some(loader) = &self.annotation_loader else {
+            return;
+        };
+        let missing: Vec<String> = self
+            .visible
+            .iter()
+            .map(|&i| &self.branches[i])
+            .skip(self.offset)
+            .take(2 * self.height)
+            .filter(|b| !self.annotations.contains_key(*b) && !self.annotation_pending.contains(*b))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        self.annotation_pending.extend(missing.iter().cloned());
+        loader.request(self.status_generation, missing);
+    }
+
+    /// Take the annotations that have arrived, waiting up to `wait` for the first.
+    /// Returns whether any did.
+    pub fn receive_annotations(&mut self, wait: Duration) -> bool {
+        let Some(loader) = &self.annotation_loader else {
+            return false;
+        };
+        let mut received = false;
+        for annotated in loader.receive(wait) {
+            if annotated.generation != self.status_generation {
+                continue;
+            }
+            self.annotation_pending.remove(&annotated.branch);
+            self.annotations
+                .insert(annotated.branch, annotated.annotation);
+            received = true;
+        }
+        received
+    }
+
+    /// The pull request of `branch`; remote branches have their name on the remote's.
+    fn pull(&self, branch: &str) -> Option<&PullRequest> {
+        self.pulls.get(&self.load.scope.checkout_name(branch))
+    }
+
+    /// Take the pull requests once `pull_loader` has them. Returns whether they arrived.
+    fn receive_pulls(&mut self) -> bool {
+        let Some(pulls) = self.pull_loader.as_ref().and_then(PullLoader::receive) else {
+            return false;
+        };
+        self.pulls = pulls;
+        self.pull_loader = None;
+        true
+    }
+
+    /// Drop all statuses, annotations and the preview's diff, loaded or on their way,
+    /// after the list was reloaded.
+    fn forget_status(&mut self) {
+        if let Some(preview) = &mut self.preview {
+            preview.branch = None;
+        }
+        self.status.clear();
+        self.status_pending.clear();
+        self.annotations.clear();
+        self.annotation_pending.clear();
+        self.status_generation += 1;
+    }
+
+    fn render(&mut self) -> io::Result<()> {
+        self.receive_status(Duration::ZERO);
+        self.receive_pulls();
+        self.receive_annotations(Duration::ZERO);
+        self.load_status();
+        self.load_annotations();
+        self.load_preview();
+        if self.accessible {
+            let text = self.announcement_update();
+            let mut ui = terminal::ui();
+            ui.write_all(text.as_bytes())?;
+            return ui.flush();
+        }
+        let frame = self.frame();
+        self.screen.draw(&frame)
+    }
+
+    /// Start over with the next draw: repaint the whole frame, or announce everything
+    /// again in accessible mode.
+    fn invalidate(&mut self) {
+        self.screen.invalidate();
+        self.announced.clear();
+    }
+
+    /// What accessible mode says about the picker: the title, a status line for the
+    /// highlighted branch ("selected: main (3 of 12)") and any open prompt, menu or
+    /// message. Plain text, without colors or positioning.
+    pub fn announcement(&self) -> Vec<String> {
+        let mut lines = vec![self.title()];
+        lines.push(match self.selected_branch() {
+            Some(branch) => self.describe(branch),
+            None => self.text("picker.no_matches").to_string(),
+        });
+        if let Some((dialog, _)) = &self.confirm {
+            lines.push(dialog.announcement());
+        } else if let Some((menu, _)) = &self.menu {
+            lines.push(menu.announcement());
+        } else if let Some((input, _)) = &self.input {
+            lines.push(input.announcement());
+        } else if let Some(message) = &self.message {
+            lines.push(message.clone());
+        }
+        lines
+            .iter()
+            .map(|line| refname::display(line).into_owned())
+            .collect()
+    }
+
+    /// The status line for the highlighted `branch`: its position in the list, then
+    /// what the marks and columns of the full-screen picker say about it, in words.
+    fn describe(&self, branch: &str) -> String {
+        let mut line = self.format_message(
+            "accessible.selected",
+            &[
+                ("branch", &branch),
+                ("index", &(self.selected + 1)),
+                ("total", &self.visible.len()),
+            ],
+        );
+        let mut details = Vec::new();
+        if branch == self.current_branch {
+            details.push(self.text("accessible.current").to_string());
+        } else if self.other_worktrees.contains_key(branch) {
+            details.push(self.text("accessible.other_worktree").to_string());
+        }
+        if self.marked.iter().any(|b| b == branch) {
+            details.push(self.text("accessible.marked").to_string());
+        }
+        if self.is_protected(branch) {
+            details.push(self.text("accessible.protected").to_string());
+        }
+        if let Some(status) = self.status.get(branch) {
+            if let Some(ahead) = status.ahead.filter(|&n| n > 0) {
+                details.push(self.format_message("accessible.ahead", &[("count", &ahead)]));
+            }
+            if let Some(behind) = status.behind.filter(|&n| n > 0) {
+                details.push(self.format_message("accessible.behind", &[("count", &behind)]));
+            }
+            if status.merged {
+                details.push(self.text("accessible.merged").to_string());
+            }
+        }
+        if let Some(pull) = self.pull(branch) {
+            details.push(self.format_message("accessible.pull", &[("pull", pull)]));
+        }
+        if let Some(Some(annotation)) = self.annotations.get(branch) {
+            details.push(annotation.clone());
+        }
+        for detail in details {
+            line.push_str(", ");
+            line.push_str(&detail);
+        }
+        line
+    }
+
+    /// The output that brings accessible mode up to date: each line of the
+    /// announcement that changed since the last one, on a line of its own, so the
+    /// terminal only ever scrolls and a screen reader reads just what is new.
+    pub fn announcement_update(&mut self) -> String {
+        let lines = self.announcement();
+        let mut text = String::new();
+        for (row, line) in lines.iter().enumerate() {
+            if self.announced.get(row) != Some(line) {
+                // Raw mode leaves carriage returns to us.
+                text.push_str(line);
+                text.push_str("\r\n");
+            }
+        }
+        self.announced = lines;
+        text
+    }
+
+    /// The sequence that clears the screen, or nothing in accessible mode, which
+    /// keeps the output so far for the screen reader to go back to.
+    pub fn clear_screen(&self) -> &'static str {
+        if self.accessible { "" } else { CLEAR_SCREEN }
+    }
+
+    fn handle_up(&mut self) {
+        if self.selected > 0 {
+            self.selected -= 1;
+        }
+        if self.offset > self.selected {
+            self.offset -= 1;
+        }
+    }
+
+    fn handle_down(&mut self) {
+        if self.selected + 1 < self.visible.len() {
+            self.selected += 1;
+        }
+        if self.offset + self.height - 1 < self.selected {
+            self.offset += 1;
+        }
+    }
+
+    /// Move the selection to `index` (clamped), scrolling just enough to keep it visible.
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.visible.len().saturating_sub(1));
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.height {
+            self.offset = self.selected + 1 - self.height;
+        }
+    }
+
+    /// Read a single key (or escape sequence) from the terminal and handle it.
+    fn handle_input(&mut self) -> io::Result<Option<bool>> {
+        let Some(key) = self.keys.read_key()? else {
+            return Ok(None);
+        };
+        // Raw mode swallows SIGINT, so Ctrl-C arrives as a key and interrupts from anywhere.
+        if key == Key::Ctrl('c') {
+            terminal::interrupt();
+        }
+        Ok(self.handle_key(key))
+    }
+
+    /// Update the picker for one keypress. Returns `Some(true)` when the selected
+    /// branch is confirmed (Enter), `Some(false)` when the picker is cancelled and
+    /// `None` while it stays open.
+    pub fn handle_key(&mut self, key: Key) -> Option<bool> {
+        // An open dialog takes all keys until it is answered.
+        if let Some((dialog, _)) = &mut self.confirm {
+            if let Some(answer) = dialog.handle_key(key)
+                && let Some((_, action)) = self.confirm.take()
+                && answer
+            {
+                if let PendingAction::Checkout = action {
+                    return Some(true);
+                }
+                self.perform(action);
+            }
+            return None;
+        }
+
+        if self.input.is_some() {
+            self.handle_input_key(key);
+            return None;
+        }
+
+        if let Some((menu, _)) = &mut self.menu {
+            if let Some(choice) = menu.handle_key(key)
+                && let Some((_, actions)) = self.menu.take()
+                && let Some(&action) = choice.and_then(|i| actions.get(i))
+            {
+                return self.apply(action);
+            }
+            return None;
+        }
+
+        self.message = None;
+        // Pasted text is never treated as keystrokes; it becomes the filter query.
+        if let Key::Paste(text) = key {
+            let mut input = TextInput::with_value(self.text("prompt.filter"), &self.filter);
+            input.handle_key(Key::Paste(text));
+            self.queries.reset();
+            self.set_filter(input.value());
+            self.input = Some((input, InputPurpose::Filter));
+            return None;
+        }
+        // ESC clears an active filter before it cancels.
+        if key == Key::Esc && !self.filter.is_empty() {
+            self.filter.clear();
+            self.refilter();
+            return None;
+        }
+        let action = if self.mode == Mode::Delete && self.keymap.is_bound(Action::Mark, &key) {
+            Some(Action::Mark)
+        } else {
+            self.keymap.action(&key)
+        };
+        self.apply(action?)
+    }
+
+    /// Carry out `action`. Returns the picker's outcome, as for `handle_key`.
+    fn apply(&mut self, action: Action) -> Option<bool> {
+        if self.load.scope == Scope::Tags && !applies_to_tags(action) {
+            self.message = Some(self.text("tags.not_a_branch").to_string());
+            return None;
+        }
+        match action {
+            Action::Up => self.handle_up(),
+            Action::Down => self.handle_down(),
+            Action::First => self.select(0),
+            Action::Last => self.select(usize::MAX),
+            Action::PageUp => self.select(self.selected.saturating_sub(self.height)),
+            Action::PageDown => self.select(self.selected + self.height),
+            Action::Mark => {
+                if self.mode == Mode::Delete {
+                    self.toggle_mark();
+                    self.handle_down();
+                }
+            }
+            Action::Select if self.mode == Mode::Delete => self.request_delete_marked(),
+            Action::Select if self.confirm_checkout => self.request_checkout(),
+            Action::Select => return self.selected_branch().map(|_| true),
+            Action::Cancel => return Some(false),
+            Action::Filter => {
+                self.queries.reset();
+                let input = TextInput::with_value(self.text("prompt.filter"), &self.filter);
+                self.input = Some((input, InputPurpose::Filter));
+            }
+            Action::Create => {
+                if let Some(base) = self.selected_branch().cloned() {
+                    let input = TextInput::new(
+                        self.format_message("prompt.new_branch", &[("base", &base)]),
+                    );
+                    self.input = Some((input, InputPurpose::Create { base }));
+                }
+            }
+            Action::Rename => {
+                if let Some(from) = self.selected_branch().cloned() {
+                    if self.load.scope.is_remote(&from) {
+                        self.message =
+                            Some(self.format_message("rename.remote", &[("branch", &from)]));
+                        return None;
+                    }
+                    if self.is_protected(&from) {
+                        self.message =
+                            Some(self.format_message("rename.protected", &[("branch", &from)]));
+                        return None;
+                    }
+                    let input = TextInput::with_value(
+                        self.format_message("prompt.rename", &[("branch", &from)]),
+                        &from,
+                    );
+                    self.input = Some((input, InputPurpose::Rename { from }));
+                }
+            }
+            Action::Delete => self.request_delete(),
+            Action::Reload => self.refresh(),
+            Action::ToggleScope => self.toggle_scope(),
+            // Repaint from scratch if something else scribbled on the screen.
+            Action::Repaint => self.invalidate(),
+            Action::Menu => self.open_menu(),
+            Action::Diff => self.diff(),
+            Action::Log => self.log(),
+            Action::Push => self.push(),
+            Action::PullRequest => self.pull_request(),
+            Action::Yank => self.yank(),
+            Action::Preview => {
+                self.preview = match self.preview {
+                    Some(_) => None,
+                    None => Some(Preview {
+                        branch: None,
+                        lines: Vec::new(),
+                        note: None,
+                        scroll: 0,
+                    }),
+                };
+            }
+            Action::PreviewUp => self.scroll_preview(false),
+            Action::PreviewDown => self.scroll_preview(true),
+        }
+        self.load_preview();
+        None
+    }
+
+    /// Offer the actions that make sense for the highlighted branch.
+    fn open_menu(&mut self) {
+        let Some(branch) = self.selected_branch() else {
+            return;
+        };
+        let remote = self.load.scope.is_remote(branch);
+        let actions: Vec<(Action, &str)> = MENU_ACTIONS
+            .iter()
+            .copied()
+            .filter(|&(action, _)| match action {
+                Action::Select => self.mode == Mode::Pick,
+                _ if self.load.scope == Scope::Tags => applies_to_tags(action),
+                Action::Rename | Action::Delete => !remote && !self.is_protected(branch),
+                Action::Push => !remote,
+                Action::Diff => *branch != self.current_branch,
+                _ => true,
+            })
+            .collect();
+        let labels = actions
+            .iter()
+            .map(|&(_, label)| self.text(label).to_string())
+            .collect();
+        let menu = Menu::new(
+            self.format_message("menu.title", &[("branch", branch)]),
+            labels,
+        );
+        self.menu = Some((menu, actions.into_iter().map(|(a, _)| a).collect()));
+    }
+
+    /// Page through the changes the highlighted branch makes since it forked from the
+    /// current branch (`git diff current...branch`).
+    fn diff(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        if branch == self.current_branch {
+            self.message = Some(self.format_message("diff.current", &[("branch", &branch)]));
+            return;
+        }
+        let range = self.diff_range(&branch);
+        if let Err(e) = terminal::suspend(|| git::diff(&range)) {
+            self.message = Some(e.to_string());
+        }
+        self.invalidate();
+    }
+
+    /// The changes `branch` makes since it forked from the current branch, as a range
+    /// for `git diff`.
+    fn diff_range(&self, branch: &str) -> String {
+        // A detached HEAD has no branch name.
+        let base = match self.current_branch.as_str() {
+            "" => "HEAD",
+            name => name,
+        };
+        format!("{base}...{branch}")
+    }
+
+    /// Page through the highlighted branch's history (`git log branch`).
+    fn log(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        if let Err(e) = terminal::suspend(|| git::log(&branch)) {
+            self.message = Some(e.to_string());
+        }
+        self.invalidate();
+    }
+
+    fn push(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        if self.load.scope.is_remote(&branch) {
+            self.message = Some(self.format_message("push.remote", &[("branch", &branch)]));
+            return;
+        }
+        self.message = Some(match self.git.push(&branch) {
+            Ok(()) => self.format_message("push.done", &[("branch", &branch)]),
+            Err(e) => e,
+        });
+    }
+
+    /// Copy the highlighted branch's name to the clipboard.
+    fn yank(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        self.message = Some(match self.git.copy_to_clipboard(&branch, self.osc52) {
+            Ok(()) => self.format_message("yank.done", &[("branch", &branch)]),
+            Err(e) => e,
+        });
+    }
+
+    /// Open the highlighted branch's pull request in the browser, or start one. The
+    /// tool may ask questions (such as where to push), so it gets the terminal.
+    fn pull_request(&mut self) {
+        let Some(branch) = self.selected_branch() else {
+            return;
+        };
+        let branch = self.load.scope.checkout_name(branch);
+        let git = &mut self.git;
+        let result = terminal::suspend(|| git.open_pull_request(&branch));
+        self.invalidate();
+        self.message = Some(match result {
+            Ok(true) => self.format_message("pull.opened", &[("branch", &branch)]),
+            Ok(false) => self.format_message("pull.started", &[("branch", &branch)]),
+            Err(e) => e,
+        });
+    }
+
+    /// Route a key to the active text input, applying its purpose on submit.
+    fn handle_input_key(&mut self, key: Key) {
+        let prompt = self.text("prompt.filter");
+        let Some((input, purpose)) = &mut self.input else {
+            return;
+        };
+
+        if let InputPurpose::Filter = purpose {
+            // Up/Down recall earlier queries, as in a shell.
+            let recalled = match key {
+                Key::Up => self.queries.older(&input.value()),
+                Key::Down => self.queries.newer(),
+                _ => None,
+            };
+            if let Some(query) = recalled {
+                let query = query.to_string();
+                *input = TextInput::with_value(prompt, &query);
+                return self.set_filter(query);
+            }
+            // Keep navigation available while typing a filter.
+            match key {
+                Key::Up | Key::Down => return,
+                Key::Ctrl('p') => return self.handle_up(),
+                Key::Ctrl('n') => return self.handle_down(),
+                Key::PageUp => {
+                    return self.select(self.selected.saturating_sub(self.height));
+                }
+                Key::PageDown => return self.select(self.selected + self.height),
+                _ => {}
+            }
+        }
+
+        let answer = input.handle_key(key);
+        if let InputPurpose::Filter = purpose {
+            let filter = match answer {
+                Some(false) => String::new(),
+                _ => input.value(),
+            };
+            self.set_filter(filter);
+        }
+
+        let Some(submitted) = answer else {
+            return;
+        };
+        let Some((input, purpose)) = self.input.take() else {
+            return;
+        };
+        let name = input.value().trim().to_string();
+        if !submitted || name.is_empty() {
+            return;
+        }
+        match purpose {
+            InputPurpose::Filter => {
+                // Only a convenience for later filters; not worth failing over.
+                let _ = self.queries.add(&name);
+            }
+            InputPurpose::Create { base } => match self.git.create_branch(&name, &base) {
+                Ok(()) => {
+                    // The new branch has no place in a list of tags.
+                    if self.load.scope != Scope::Tags {
+                        self.branches.insert(0, name.clone());
+                        self.selected = 0;
+                        self.offset = 0;
+                        self.refilter();
+                    }
+                    self.message = Some(
+                        self.format_message("create.done", &[("name", &name), ("base", &base)]),
+                    );
+                }
+                Err(e) => self.message = Some(e),
+            },
+            InputPurpose::Rename { from } => {
+                if name == from {
+                    return;
+                }
+                match self.git.rename_branch(&from, &name) {
+                    Ok(()) => {
+                        for b in self.branches.iter_mut().filter(|b| **b == from) {
+                            *b = name.clone();
+                        }
+                        if self.current_branch == from {
+                            self.current_branch = name.clone();
+                        }
+                        self.refilter();
+                        self.message = Some(
+                            self.format_message("rename.done", &[("from", &from), ("to", &name)]),
+                        );
+                    }
+                    Err(e) => self.message = Some(e),
+                }
+            }
+        }
+    }
+
+    /// Re-run branch discovery, keeping the highlighted branch selected if it still exists.
+    fn refresh(&mut self) {
+        match self.git.load_recent(&self.load) {
+            Ok((current_branch, branches)) => {
+                let selected = self.selected_branch().cloned();
+                self.current_branch = current_branch;
+                self.branches = branches;
+                self.forget_status();
+                self.detached = self.git.detached_head().unwrap_or_default();
+                self.other_worktrees = self.git.other_worktrees().unwrap_or_default();
+                self.refilter();
+                let index = selected
+                    .and_then(|name| self.visible.iter().position(|&i| self.branches[i] == name));
+                self.select(index.unwrap_or(0));
+                self.message =
+                    Some(self.format_message("reload.done", &[("count", &self.branches.len())]));
+            }
+            Err(e) => self.message = Some(e.to_string()),
+        }
+    }
+
+    /// Switch between local, remote and all branches, skipping a scope with no branches.
+    fn toggle_scope(&mut self) {
+        let previous = self.load.scope;
+        if previous.next() == previous {
+            return;
+        }
+        self.load.scope = previous.next();
+        match self.git.load_recent(&self.load) {
+            Ok((_, branches)) if branches.is_empty() => {
+                self.message = Some(
+                    self.format_message("scope.empty", &[("scope", &self.load.scope.label())]),
+                );
+                self.load.scope = previous;
+            }
+            Ok((current_branch, branches)) => {
+                self.current_branch = current_branch;
+                self.branches = branches;
+                self.forget_status();
+                self.selected = 0;
+                self.offset = 0;
+                self.refilter();
+            }
+            Err(e) => {
+                self.message = Some(e.to_string());
+                self.load.scope = previous;
+            }
+        }
+    }
+
+    fn request_delete(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        if self.load.scope.is_remote(&branch) {
+            self.message = Some(self.format_message("delete.remote", &[("branch", &branch)]));
+            return;
+        }
+        if branch == self.current_branch {
+            self.message = Some(self.format_message("delete.current", &[("branch", &branch)]));
+            return;
+        }
+        if self.is_protected(&branch) {
+            self.message = Some(self.format_message("delete.protected", &[("branch", &branch)]));
+            return;
+        }
+        if !self.confirm_delete {
+            return self.perform(PendingAction::Delete(vec![branch]));
+        }
+        let dialog =
+            ConfirmDialog::new(self.format_message("delete.confirm", &[("branch", &branch)]));
+        self.confirm = Some((dialog, PendingAction::Delete(vec![branch])));
+    }
+
+    /// Ask before checking out the highlighted branch, saying whether there are
+    /// uncommitted changes that would be carried over (or block the switch).
+    fn request_checkout(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        let status = match self.git.uncommitted_changes() {
+            Ok(0) => self.text("checkout.clean").to_string(),
+            Ok(1) => self.text("checkout.one_change").to_string(),
+            Ok(n) => self.format_message("checkout.changes", &[("count", &n)]),
+            Err(e) => e.to_string(),
+        };
+        let dialog = ConfirmDialog::new(self.format_message(
+            "checkout.confirm",
+            &[("branch", &branch), ("status", &status)],
+        ));
+        self.confirm = Some((dialog, PendingAction::Checkout));
+    }
+
+    /// Mark or unmark the highlighted branch for deletion.
+    fn toggle_mark(&mut self) {
+        let Some(branch) = self.selected_branch().cloned() else {
+            return;
+        };
+        if let Some(i) = self.marked.iter().position(|b| *b == branch) {
+            self.marked.remove(i);
+        } else if branch == self.current_branch {
+            self.message = Some(self.format_message("delete.current", &[("branch", &branch)]));
+        } else if self.load.scope.is_remote(&branch) {
+            self.message = Some(self.format_message("delete.remote", &[("branch", &branch)]));
+        } else if self.is_protected(&branch) {
+            self.message = Some(self.format_message("delete.protected", &[("branch", &branch)]));
+        } else {
+            self.marked.push(branch);
+        }
+    }
+
+    /// Ask before deleting the marked branches, or just the highlighted one if none are marked.
+    fn request_delete_marked(&mut self) {
+        if self.marked.is_empty() {
+            return self.request_delete();
+        }
+        let branches = self.marked.clone();
+        if !self.confirm_delete {
+            return self.perform(PendingAction::Delete(branches));
+        }
+        let dialog = ConfirmDialog::new(
+            self.format_message("delete.confirm_marked", &[("count", &branches.len())]),
+        );
+        self.confirm = Some((dialog, PendingAction::Delete(branches)));
+    }
+
+    fn perform(&mut self, action: PendingAction) {
+        let (branches, force) = match action {
+            PendingAction::Delete(branches) => (branches, false),
+            PendingAction::ForceDelete(branches) => (branches, true),
+            PendingAction::Checkout => return,
+        };
+        let mut deleted = Vec::new();
+        let mut unmerged = Vec::new();
+        let mut error = None;
+        for branch in branches {
+            match self.git.delete_branch(&branch, force) {
+                Ok(()) => deleted.push(branch),
+                // Decided by the commits: git words its refusal in the user's language.
+                Err(_) if !force && !self.git.is_merged(&branch).unwrap_or(true) => {
+                    unmerged.push(branch)
+                }
+                Err(e) => error = Some(e),
+            }
+        }
+        self.branches.retain(|b| !deleted.contains(b));
+        self.marked.retain(|b| !deleted.contains(b));
+        self.refilter();
+        self.message = match deleted.as_slice() {
+            [] => None,
+            [branch] => Some(self.format_message("delete.done", &[("branch", branch)])),
+            _ => Some(self.format_message("delete.done_many", &[("count", &deleted.len())])),
+        };
+
+        // Any other failure (a worktree holding the branch, a lock) is reported as is.
+        if error.is_some() {
+            self.message = error;
+        }
+        if unmerged.is_empty() {
+            return;
+        }
+        // Only unmerged commits are worth offering the force variant for.
+        let prompt = match unmerged.as_slice() {
+            [branch] => self.format_message("delete.unmerged", &[("branch", branch)]),
+            _ => self.format_message("delete.unmerged_many", &[("count", &unmerged.len())]),
+        };
+        self.confirm = Some((
+            ConfirmDialog::new(prompt),
+            PendingAction::ForceDelete(unmerged),
+        ));
+    }
+
+    /// Check out `chosen` as listed (remote branches by their local name) and move it
+    /// to the front of the list.
+    pub fn checkout(&mut self, chosen: &str, quiet: bool) -> Result<(), Box<dyn Error>> {
+        let mut ui = terminal::ui();
+        let clear = self.clear_screen();
+        if quiet {
+            write!(ui, "{clear}")?;
+        } else {
+            writeln!(ui, "{clear}")?;
+            writeln!(
+                ui,
+                "\n{}",
+                self.format_message("checkout.running", &[("branch", &chosen)])
+            )?;
+            write!(ui, "{CURSOR_TO_LEFT}")?;
+        }
+        ui.flush()?;
+
+        let branch = self.load.scope.checkout_name(chosen);
+        switch_to(self.git.as_mut(), &branch, quiet, &self.switch, &self.hooks)?;
+        // Move chosen branch to the front of the list
+        self.branches.retain(|b| b != chosen);
+        self.branches.insert(0, chosen.to_string());
+        Ok(())
+    }
+
+    /// Run the interactive picker. Returns the chosen branch, or `None` if cancelled.
+    pub fn pick(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        let setup_started = Instant::now();
+        // Create RAII guard to restore terminal state on panic/exit.
+        let raw_guard = RawModeGuard::new();
+        if cfg!(unix) && !raw_guard.is_enabled() {
+            return Err(
+                AppError::TerminalSetupFailed(self.text("terminal.raw_mode").to_string()).into(),
+            );
+        }
+        // Unambiguous key events where the terminal supports them; legacy sequences otherwise.
+        if self.keys.detect_kitty_protocol()? {
+            terminal::enable_kitty_keyboard()?;
+        }
+
+        let mut ui = terminal::ui();
+        // Screen readers follow the cursor; only the full-screen picker hides it.
+        if !self.accessible {
+            write!(ui, "{HIDE_CURSOR}")?;
+        }
+        ui.flush()?;
+        trace::phase("terminal setup", setup_started);
+
+        let confirmed = loop {
+            if self.branches.is_empty() {
+                break false;
+            }
+            let render_started = Instant::now();
+            self.render()?;
+            trace::phase("first render", render_started);
+            trace::startup_done();
+            // While statuses, pull requests or annotations are on their way, wake up
+            // now and then to draw them.
+            if (!self.status_pending.is_empty()
+                || self.pull_loader.is_some()
+                || !self.annotation_pending.is_empty())
+                && !self.keys.has_buffered_input()
+                && !terminal::poll_input(Some(STATUS_POLL))?
+            {
+                self.receive_status(Duration::ZERO);
+                continue;
+            }
+            if let Some(confirmed) = self.handle_input()? {
+                break confirmed;
+            }
+        };
+
+        drop(raw_guard);
+        write!(ui, "{SHOW_CURSOR}")?;
+        ui.flush()?;
+
+        Ok(if confirmed {
+            self.selected_branch().cloned()
+        } else {
+            None
+        })
+    }
+}
+
+/// Check out `branch`, running the checkout hooks around it.
+pub fn switch_to(
+    git: &mut dyn GitBackend,
+    branch: &str,
+    quiet: bool,
+    command: &SwitchCommand,
+    hooks: &Hooks,
+) -> Result<(), Box<dyn Error>> {
+    let started = Instant::now();
+    let previous = git.current_branch()?;
+    hooks
+        .pre_checkout(branch, &previous)
+        .map_err(AppError::HookFailed)?;
+    if let Err(e) = git.checkout(branch, quiet, command) {
+        // Someone at a terminal can wait for another git command to finish.
+        let Some(AppError::RepositoryLocked(message)) = e.downcast_ref::<AppError>() else {
+            return Err(e);
+        };
+        if !io::stdin().is_terminal() {
+            return Err(e);
+        }
+        let mut ui = terminal::ui();
+        writeln!(ui, "{message}")?;
+        ui.flush()?;
+        git::wait_for_unlock();
+        git.checkout(branch, quiet, command)?;
+    }
+    hooks
+        .post_checkout(branch, &previous)
+        .map_err(AppError::HookFailed)?;
+    notify::if_slow(
+        hooks.notify_after,
+        started,
+        &i18n::message("notify.checked_out", &[("branch", &branch)]),
+    );
+    Ok(())
+}
+
+/// Whether `action` makes sense for a tag, which can be looked at, checked out and
+/// branched from, but not renamed, deleted, pushed or given a pull request here.
+fn applies_to_tags(action: Action) -> bool {
+    !matches!(
+        action,
+        Action::Rename | Action::Delete | Action::Push | Action::PullRequest
+    )
+}
+
+/// The status columns after a branch name: commits ahead/behind its upstream (`↑2 ↓5`,
+/// or `+2 -5` in ASCII) and whether it is merged.
+fn status_columns(status: &BranchStatus, ascii: bool) -> String {
+    let (up, down) = if ascii { ("+", "-") } else { ("↑", "↓") };
+    let mut columns = String::new();
+    if let Some(ahead) = status.ahead.filter(|&n| n > 0) {
+        columns.push_str(&format!(" {up}{ahead}"));
+    }
+    if let Some(behind) = status.behind.filter(|&n| n > 0) {
+        columns.push_str(&format!(" {down}{behind}"));
+    }
+    if status.merged {
+        columns.push_str(&format!(" {}", i18n::text("picker.merged")));
+    }
+    columns
+}
+
+/// `line` without its SGR escape sequences (`\x1b[...m`), such as git's colors.
+fn without_colors(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("\x1b[") {
+        plain.push_str(&rest[..start]);
+        let sequence = &rest[start + 2..];
+        match sequence.find(|c: char| !c.is_ascii_digit() && c != ';') {
+            Some(end) if sequence[end..].starts_with('m') => rest = &sequence[end + 1..],
+            // Not a color; the escape character goes, the rest stays.
+            _ => rest = sequence,
+        }
+    }
+    plain.push_str(rest);
+    plain
+}
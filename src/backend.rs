@@ -0,0 +1,341 @@
+use std::error::Error;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::clipboard;
+use crate::git::{self, BranchStatus, LoadOptions, Scope, SwitchCommand, run_git};
+use crate::history::History;
+use crate::i18n;
+use crate::pulls::{self, Forge};
+use crate::refname;
+
+/// Everything the picker asks of git. `SystemGit` runs the `git` command;
+/// `MockGit` keeps an in-memory repository, so `App` can be driven without one.
+pub trait GitBackend {
+    /// The current branch and every branch to list, as `git::load_recent`; the
+    /// picker applies `options.limit` itself.
+    fn load_recent(
+        &mut self,
+        options: &LoadOptions,
+    ) -> Result<(String, Vec<String>), Box<dyn Error>>;
+    /// The checked-out branch; empty when HEAD is detached.
+    fn current_branch(&mut self) -> Result<String, Box<dyn Error>>;
+    /// The abbreviated commit of a detached HEAD; `None` when on a branch.
+    fn detached_head(&mut self) -> Result<Option<String>, Box<dyn Error>>;
+    /// Branches checked out in other worktrees, as `git::other_worktree_branches`.
+    fn other_worktrees(&mut self) -> Result<HashMap<String, PathBuf>, Box<dyn Error>>;
+    fn checkout(
+        &mut self,
+        branch: &str,
+        quiet: bool,
+        command: &SwitchCommand,
+    ) -> Result<(), Box<dyn Error>>;
+    fn create_branch(&mut self, name: &str, base: &str) -> Result<(), String>;
+    fn rename_branch(&mut self, from: &str, to: &str) -> Result<(), String>;
+    /// Delete `branch`; without `force`, only if it is fully merged.
+    fn delete_branch(&mut self, branch: &str, force: bool) -> Result<(), String>;
+    /// Whether `branch` is merged far enough for a delete without `force`.
+    fn is_merged(&mut self, branch: &str) -> Result<bool, Box<dyn Error>>;
+    fn push(&mut self, branch: &str) -> Result<(), String>;
+    /// Open the pull request of `branch` in the browser or start creating one, as
+    /// `pulls::open`. Returns whether one existed.
+    fn open_pull_request(&mut self, branch: &str) -> Result<bool, String>;
+    /// Put `text` on the clipboard, as `clipboard::copy`.
+    fn copy_to_clipboard(&mut self, text: &str, osc52: bool) -> Result<(), String>;
+    /// The first `max_lines` lines of the diff of `range`, as `git::diff_preview`.
+    fn diff_preview(&mut self, range: &str, max_lines: usize) -> Result<Vec<String>, String>;
+    /// Number of paths with uncommitted changes.
+    fn uncommitted_changes(&mut self) -> Result<usize, Box<dyn Error>>;
+    /// The status of each of `branches`, in order, as `git::branch_status`.
+    fn branch_status(
+        &mut self,
+        branches: &[String],
+        scope: Scope,
+        counts_against: Option<&str>,
+        merged_into: Option<&str>,
+    ) -> Result<Vec<BranchStatus>, Box<dyn Error>>;
+}
+
+/// The real repository in the current directory, through the `git` command.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemGit;
+
+impl GitBackend for SystemGit {
+    fn load_recent(
+        &mut self,
+        options: &LoadOptions,
+    ) -> Result<(String, Vec<String>), Box<dyn Error>> {
+        git::load_recent(options)
+    }
+
+    fn current_branch(&mut self) -> Result<String, Box<dyn Error>> {
+        git::current_branch_name()
+    }
+
+    fn detached_head(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        git::detached_head()
+    }
+
+    fn other_worktrees(&mut self) -> Result<HashMap<String, PathBuf>, Box<dyn Error>> {
+        git::other_worktree_branches()
+    }
+
+    /// Check out `branch` and remember it in the repository's checkout history.
+    fn checkout(
+        &mut self,
+        branch: &str,
+        quiet: bool,
+        command: &SwitchCommand,
+    ) -> Result<(), Box<dyn Error>> {
+        git::checkout(branch, quiet, command)?;
+        // Tags have no place in the checkout history, which orders branches.
+        if !git::is_dry_run()
+            && git::tag_name(branch).is_none()
+            && let Some(mut history) = History::open()
+        {
+            // The history only improves ordering; failing to save it must not fail the checkout.
+            let _ = history.record(branch);
+        }
+        Ok(())
+    }
+
+    fn create_branch(&mut self, name: &str, base: &str) -> Result<(), String> {
+        run_git(
+            &i18n::message("progress.creating", &[("branch", &refname::display(name))]),
+            &["branch", name, base],
+        )
+    }
+
+    fn rename_branch(&mut self, from: &str, to: &str) -> Result<(), String> {
+        run_git(
+            &i18n::message("progress.renaming", &[("branch", &refname::display(from))]),
+            &["branch", "-m", from, to],
+        )
+    }
+
+    fn delete_branch(&mut self, branch: &str, force: bool) -> Result<(), String> {
+        let flag = if force { "-D" } else { "-d" };
+        run_git(
+            &i18n::message(
+                "progress.deleting",
+                &[("branch", &refname::display(branch))],
+            ),
+            &["branch", flag, branch],
+        )
+    }
+
+    fn is_merged(&mut self, branch: &str) -> Result<bool, Box<dyn Error>> {
+        git::is_merged(branch)
+    }
+
+    fn push(&mut self, branch: &str) -> Result<(), String> {
+        git::push(branch)
+    }
+
+    fn open_pull_request(&mut self, branch: &str) -> Result<bool, String> {
+        pulls::open(Forge::detect(), branch).map_err(|e| e.to_string())
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str, osc52: bool) -> Result<(), String> {
+        clipboard::copy(text, osc52)
+    }
+
+    fn diff_preview(&mut self, range: &str, max_lines: usize) -> Result<Vec<String>, String> {
+        git::diff_preview(range, max_lines)
+    }
+
+    fn uncommitted_changes(&mut self) -> Result<usize, Box<dyn Error>> {
+        git::uncommitted_changes()
+    }
+
+    fn branch_status(
+        &mut self,
+        branches: &[String],
+        scope: Scope,
+        counts_against: Option<&str>,
+        merged_into: Option<&str>,
+    ) -> Result<Vec<BranchStatus>, Box<dyn Error>> {
+        git::branch_status(branches, scope, counts_against, merged_into)
+    }
+}
+
+/// An in-memory repository for tests. Branches are listed in the order of
+/// `branches`; checkouts move a branch to the front, like the reflog order would.
+#[derive(Clone, Debug, Default)]
+pub struct MockGit {
+    pub branches: Vec<String>,
+    /// Empty when HEAD is detached at `detached`.
+    pub current: String,
+    pub detached: Option<String>,
+    /// Branches that need a force delete.
+    pub unmerged: Vec<String>,
+    /// Branches that have been pushed.
+    pub pushed: Vec<String>,
+    /// Branches with a pull request.
+    pub pull_requests: Vec<String>,
+    /// Branches whose pull request was opened or started.
+    pub opened_pull_requests: Vec<String>,
+    /// What was last copied to the clipboard.
+    pub clipboard: Option<String>,
+    pub uncommitted_changes: usize,
+    /// The diff of each range (`base...branch`), line by line; others are empty.
+    pub diffs: HashMap<String, Vec<String>>,
+    /// Status per branch; others have the default (no upstream, not merged).
+    pub status: HashMap<String, BranchStatus>,
+    /// Branches checked out in other worktrees, with the worktree's path.
+    pub worktrees: HashMap<String, PathBuf>,
+}
+
+impl MockGit {
+    /// A repository with `branches` (most recent first), the first one checked out.
+    pub fn new(branches: &[&str]) -> Self {
+        MockGit {
+            branches: branches.iter().map(|b| b.to_string()).collect(),
+            current: branches.first().map_or_else(String::new, |b| b.to_string()),
+            ..MockGit::default()
+        }
+    }
+
+    fn find(&self, branch: &str) -> Result<usize, String> {
+        self.branches
+            .iter()
+            .position(|b| b == branch)
+            .ok_or_else(|| format!("error: branch '{branch}' not found"))
+    }
+}
+
+impl GitBackend for MockGit {
+    fn load_recent(
+        &mut self,
+        options: &LoadOptions,
+    ) -> Result<(String, Vec<String>), Box<dyn Error>> {
+        let mut branches = self.branches.clone();
+        branches.retain(|b| {
+            !options
+                .exclude
+                .iter()
+                .any(|p| crate::filter::glob_match(p, b))
+                && options
+                    .query
+                    .as_ref()
+                    .is_none_or(|q| crate::filter::matches(b, q, options.case))
+        });
+        Ok((self.current.clone(), branches))
+    }
+
+    fn current_branch(&mut self) -> Result<String, Box<dyn Error>> {
+        Ok(self.current.clone())
+    }
+
+    fn detached_head(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        Ok(self.detached.clone())
+    }
+
+    fn other_worktrees(&mut self) -> Result<HashMap<String, PathBuf>, Box<dyn Error>> {
+        Ok(self.worktrees.clone())
+    }
+
+    fn checkout(
+        &mut self,
+        branch: &str,
+        _quiet: bool,
+        _command: &SwitchCommand,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(path) = self.worktrees.get(branch) {
+            return Err(format!(
+                "fatal: '{branch}' is already checked out at '{}'",
+                path.display()
+            )
+            .into());
+        }
+        let index = self.find(branch)?;
+        let branch = self.branches.remove(index);
+        self.branches.insert(0, branch.clone());
+        self.current = branch;
+        self.detached = None;
+        Ok(())
+    }
+
+    fn create_branch(&mut self, name: &str, base: &str) -> Result<(), String> {
+        self.find(base)?;
+        if self.branches.iter().any(|b| b == name) {
+            return Err(format!("fatal: a branch named '{name}' already exists"));
+        }
+        self.branches.insert(0, name.to_string());
+        Ok(())
+    }
+
+    fn rename_branch(&mut self, from: &str, to: &str) -> Result<(), String> {
+        let index = self.find(from)?;
+        self.branches[index] = to.to_string();
+        if self.current == from {
+            self.current = to.to_string();
+        }
+        Ok(())
+    }
+
+    fn delete_branch(&mut self, branch: &str, force: bool) -> Result<(), String> {
+        let index = self.find(branch)?;
+        if branch == self.current {
+            return Err(format!(
+                "error: cannot delete branch '{branch}' used by worktree"
+            ));
+        }
+        if let Some(path) = self.worktrees.get(branch) {
+            return Err(format!(
+                "error: cannot delete branch '{branch}' used by worktree at '{}'",
+                path.display()
+            ));
+        }
+        if !force && self.unmerged.iter().any(|b| b == branch) {
+            return Err(format!("error: the branch '{branch}' is not fully merged"));
+        }
+        self.branches.remove(index);
+        Ok(())
+    }
+
+    fn is_merged(&mut self, branch: &str) -> Result<bool, Box<dyn Error>> {
+        self.find(branch)?;
+        Ok(!self.unmerged.iter().any(|b| b == branch))
+    }
+
+    fn push(&mut self, branch: &str) -> Result<(), String> {
+        self.find(branch)?;
+        self.pushed.push(branch.to_string());
+        Ok(())
+    }
+
+    fn open_pull_request(&mut self, branch: &str) -> Result<bool, String> {
+        self.find(branch)?;
+        self.opened_pull_requests.push(branch.to_string());
+        Ok(self.pull_requests.iter().any(|b| b == branch))
+    }
+
+    fn copy_to_clipboard(&mut self, text: &str, _osc52: bool) -> Result<(), String> {
+        self.clipboard = Some(text.to_string());
+        Ok(())
+    }
+
+    fn diff_preview(&mut self, range: &str, max_lines: usize) -> Result<Vec<String>, String> {
+        let lines = self.diffs.get(range).map_or(&[][..], Vec::as_slice);
+        Ok(lines.iter().take(max_lines).cloned().collect())
+    }
+
+    fn uncommitted_changes(&mut self) -> Result<usize, Box<dyn Error>> {
+        Ok(self.uncommitted_changes)
+    }
+
+    fn branch_status(
+        &mut self,
+        branches: &[String],
+        _scope: Scope,
+        _counts_against: Option<&str>,
+        _merged_into: Option<&str>,
+    ) -> Result<Vec<BranchStatus>, Box<dyn Error>> {
+        Ok(branches
+            .iter()
+            .map(|b| self.status.get(b).cloned().unwrap_or_default())
+            .collect())
+    }
+}
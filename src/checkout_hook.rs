@@ -0,0 +1,101 @@
+//! `git-recent install-hook`: a `post-checkout` git hook that records every branch
+//! checkout in git-recent's history, so `--sort checkout` also follows switches made
+//! with plain `git switch` or `git checkout`. The hook runs `git-recent
+//! record-checkout`.
+
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use crate::git::{self, shell_quote};
+use crate::history::History;
+use crate::refname;
+use crate::trace;
+
+/// Set on the checkouts git-recent makes itself, which it records on its own; the hook
+/// leaves those alone so they are not counted twice.
+pub const RECORDING_VAR: &str = "GIT_RECENT_RECORDING";
+
+/// The line that marks a hook as installed by git-recent.
+const MARKER: &str = "# Installed by 'git-recent install-hook'";
+
+/// The hook's command: record the checkout when it was one of a branch (the third
+/// argument is 0 for checking out files). A failure must not fail git's checkout.
+fn hook_line() -> Result<String, Box<dyn Error>> {
+    let exe = env::current_exe()?;
+    let exe = shell_quote(&refname::decode(exe.as_os_str().as_encoded_bytes()));
+    Ok(format!(
+        "[ \"$3\" = 1 ] && {exe} record-checkout >/dev/null 2>&1"
+    ))
+}
+
+/// The repository's `post-checkout` hook, honoring `core.hooksPath`.
+fn hook_path() -> Result<PathBuf, Box<dyn Error>> {
+    let output = trace::output(git::command().args(["rev-parse", "--git-path", "hooks"]))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git rev-parse failed: {}", stderr.trim()).into());
+    }
+    let dir = refname::decode(&output.stdout);
+    Ok(PathBuf::from(refname::to_os(dir.trim_end_matches('\n'))).join("post-checkout"))
+}
+
+/// Write the hook, replacing an earlier one of git-recent's. Another `post-checkout`
+/// hook is left alone and reported, with the line to add to it. Returns the hook's path.
+pub fn install() -> Result<PathBuf, Box<dyn Error>> {
+    let path = hook_path()?;
+    let line = hook_line()?;
+    if let Ok(existing) = fs::read_to_string(&path)
+        && !existing.contains(MARKER)
+    {
+        return Err(format!(
+            "'{}' already exists; to record checkouts, add this line to it:\n  {line}",
+            path.display()
+        )
+        .into());
+    }
+    if git::skip_for_dry_run(format!("write {}", path.display())) {
+        return Ok(path);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let script = format!(
+        "#!/bin/sh\n{MARKER}; remove with 'git-recent install-hook --uninstall'.\n\
+         # Records branch checkouts for git-recent's checkout order.\n{line}\nexit 0\n"
+    );
+    fs::write(&path, script)?;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+    Ok(path)
+}
+
+/// Remove the hook if git-recent installed it. Returns its path if it was removed.
+pub fn uninstall() -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let path = hook_path()?;
+    match fs::read_to_string(&path) {
+        Ok(existing) if existing.contains(MARKER) => {}
+        _ => return Ok(None),
+    }
+    if !git::skip_for_dry_run(format!("rm {}", path.display())) {
+        fs::remove_file(&path)?;
+    }
+    Ok(Some(path))
+}
+
+/// `git-recent record-checkout`: add the checked-out branch to the history, unless
+/// git-recent made the checkout (and so records it itself) or HEAD is detached.
+pub fn record() -> Result<(), Box<dyn Error>> {
+    if env::var_os(RECORDING_VAR).is_some() {
+        return Ok(());
+    }
+    let branch = git::current_branch_name()?;
+    if branch.is_empty() {
+        return Ok(());
+    }
+    if let Some(mut history) = History::open() {
+        history.record(&branch)?;
+    }
+    Ok(())
+}
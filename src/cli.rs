@@ -0,0 +1,753 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::completions::Shell;
+use crate::export::ExportFormat;
+use crate::format::Template;
+use crate::fzf::Picker;
+use crate::git::{Scope, Sort};
+use crate::init;
+use crate::refname;
+
+pub const NAME: &str = env!("CARGO_PKG_NAME");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const ABOUT: &str = "Interactively pick a recently committed branch and check it out.";
+const USAGE: &str = "git-recent [OPTIONS] [COMMAND]";
+
+/// Subcommands, named by the one positional argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Print the effective settings and where each one comes from.
+    Config,
+    /// Check git, the repository, the terminal, config and state files.
+    Doctor,
+    /// Print a completion script for the shell in `Args::shell`.
+    Completions,
+    /// Add (or with `--uninstall`, remove) `git recent` in the global git config.
+    InstallAlias,
+    /// Print the shell function for the shell in `Args::shell`.
+    Init,
+    /// Print the few most recent branches, for shell prompts.
+    Top,
+    /// Print every branch with its metadata in `Args::export`'s format.
+    Export,
+    /// Add (or with `--uninstall`, remove) the post-checkout hook recording checkouts.
+    InstallHook,
+    /// Record the current branch in the checkout history; run by that hook.
+    RecordCheckout,
+    /// Pick a recent tag instead of a branch (`Scope::Tags`).
+    Tags,
+}
+
+pub const COMMANDS: &[(&str, Command, &str)] = &[
+    (
+        "config",
+        Command::Config,
+        "Show the effective settings and where each one comes from",
+    ),
+    (
+        "doctor",
+        Command::Doctor,
+        "Check git, the repository, the terminal, config and state files, and suggest fixes",
+    ),
+    (
+        "completions",
+        Command::Completions,
+        "Print a completion script for SHELL (bash, zsh, fish or powershell)",
+    ),
+    (
+        "install-alias",
+        Command::InstallAlias,
+        "Add 'git recent' to your global git config (see --shortcut, --uninstall)",
+    ),
+    (
+        "init",
+        Command::Init,
+        "Print a shell function for SHELL (bash, zsh or fish) that follows worktree choices with cd",
+    ),
+    (
+        "top",
+        Command::Top,
+        "Print the 3 most recent branches (or --limit N) quickly, for shell prompts",
+    ),
+    (
+        "export",
+        Command::Export,
+        "Print every branch with its metadata and checkout count (--format json or csv)",
+    ),
+    (
+        "install-hook",
+        Command::InstallHook,
+        "Add a post-checkout hook so checkouts made with plain git count for --sort checkout",
+    ),
+    (
+        "record-checkout",
+        Command::RecordCheckout,
+        "Record the current branch as checked out (run by the install-hook hook)",
+    ),
+    (
+        "tags",
+        Command::Tags,
+        "Pick from the most recently created tags; checking one out detaches HEAD",
+    ),
+];
+
+/// One command-line option. This table is the single source of truth for
+/// parsing and for the generated `--help` text.
+pub struct OptSpec {
+    pub short: Option<char>,
+    pub long: &'static str,
+    /// Placeholder name of the option's value, or `None` for a plain flag. A
+    /// placeholder in brackets (`"[REF]"`) marks an optional value, which must be
+    /// attached (`--merged=main`, `-Mmain`) so a following argument is never taken.
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+impl OptSpec {
+    pub fn optional_value(&self) -> bool {
+        self.value.is_some_and(|v| v.starts_with('['))
+    }
+}
+
+pub const OPTIONS: &[OptSpec] = &[
+    OptSpec {
+        short: Some('C'),
+        long: "repo",
+        value: Some("PATH"),
+        help: "Run as if started in PATH (like git -C); may be repeated",
+    },
+    OptSpec {
+        short: None,
+        long: "profile",
+        value: Some("NAME"),
+        help: "Use the settings of the [profile.NAME] section of the config file",
+    },
+    OptSpec {
+        short: Some('l'),
+        long: "list",
+        value: None,
+        help: "Print recent branches, one per line, instead of opening the picker",
+    },
+    OptSpec {
+        short: Some('n'),
+        long: "limit",
+        value: Some("N"),
+        help: "List at most N branches (default 200; 0 or 'all' for no limit)",
+    },
+    OptSpec {
+        short: None,
+        long: "height",
+        value: Some("N"),
+        help: "Show N branches at once in the picker (default 5)",
+    },
+    OptSpec {
+        short: None,
+        long: "sort",
+        value: Some("KEY"),
+        help: "Order by committerdate (default), authordate, name or checkout",
+    },
+    OptSpec {
+        short: None,
+        long: "contains",
+        value: Some("COMMIT"),
+        help: "Only list branches that contain COMMIT",
+    },
+    OptSpec {
+        short: None,
+        long: "merged",
+        value: Some("[REF]"),
+        help: "Only list branches merged into REF (default: the default branch)",
+    },
+    OptSpec {
+        short: None,
+        long: "no-merged",
+        value: Some("[REF]"),
+        help: "Only list branches not merged into REF (default: the default branch)",
+    },
+    OptSpec {
+        short: None,
+        long: "default-branch",
+        value: Some("BRANCH"),
+        help: "Compare against BRANCH instead of the detected default branch (origin/HEAD)",
+    },
+    OptSpec {
+        short: None,
+        long: "stale",
+        value: Some("DAYS"),
+        help: "Only list branches with no commits in the last DAYS days",
+    },
+    OptSpec {
+        short: Some('f'),
+        long: "fetch",
+        value: None,
+        help: "Run 'git fetch --all --prune' before listing branches",
+    },
+    OptSpec {
+        short: Some('r'),
+        long: "remote",
+        value: None,
+        help: "List remote-tracking branches instead of local ones",
+    },
+    OptSpec {
+        short: Some('a'),
+        long: "all",
+        value: None,
+        help: "List both local and remote-tracking branches",
+    },
+    OptSpec {
+        short: Some('c'),
+        long: "checkout",
+        value: Some("BRANCH"),
+        help: "Check out BRANCH, or the Nth most recent branch, without opening the picker",
+    },
+    OptSpec {
+        short: None,
+        long: "last",
+        value: None,
+        help: "Switch back to the previously checked-out branch (like 'cd -')",
+    },
+    OptSpec {
+        short: Some('d'),
+        long: "delete",
+        value: None,
+        help: "Open the picker in cleanup mode to mark and delete branches",
+    },
+    OptSpec {
+        short: Some('p'),
+        long: "print",
+        value: None,
+        help: "Pick interactively but print the chosen branch instead of checking it out",
+    },
+    OptSpec {
+        short: Some('x'),
+        long: "exec",
+        value: Some("COMMAND"),
+        help: "Run COMMAND on the chosen branch instead of checking it out; {} is the branch",
+    },
+    OptSpec {
+        short: None,
+        long: "cd-file",
+        value: Some("PATH"),
+        help: "Write a cd command for the chosen worktree to PATH instead of printing its path",
+    },
+    OptSpec {
+        short: None,
+        long: "simple",
+        value: None,
+        help: "Use a numbered prompt instead of the full-screen picker (default when TERM=dumb)",
+    },
+    OptSpec {
+        short: None,
+        long: "picker",
+        value: Some("NAME"),
+        help: "Choose with the builtin picker (default) or fzf",
+    },
+    OptSpec {
+        short: None,
+        long: "tmux-popup",
+        value: None,
+        help: "Choose the branch in a tmux popup over the current pane",
+    },
+    OptSpec {
+        short: None,
+        long: "accessible",
+        value: None,
+        help: "Announce the selection as plain lines instead of repainting the screen, for screen readers",
+    },
+    OptSpec {
+        short: None,
+        long: "ascii",
+        value: None,
+        help: "Draw the picker with ASCII characters and reverse video only, for limited terminals",
+    },
+    OptSpec {
+        short: Some('q'),
+        long: "quiet",
+        value: None,
+        help: "Print nothing on success (no banner, no git output)",
+    },
+    OptSpec {
+        short: None,
+        long: "dry-run",
+        value: None,
+        help: "Print mutating git commands (checkout, delete, ...) instead of running them",
+    },
+    OptSpec {
+        short: Some('v'),
+        long: "verbose",
+        value: None,
+        help: "Log every command run, with its duration and exit status, to stderr",
+    },
+    OptSpec {
+        short: None,
+        long: "log-file",
+        value: Some("PATH"),
+        help: "Append the --verbose log to PATH instead of stderr (implies --verbose)",
+    },
+    OptSpec {
+        short: None,
+        long: "timing",
+        value: None,
+        help: "Print on exit where startup time went (git commands, terminal setup, first render)",
+    },
+    OptSpec {
+        short: None,
+        long: "query",
+        value: Some("TEXT"),
+        help: "Start with the list filtered to branches containing TEXT",
+    },
+    OptSpec {
+        short: Some('1'),
+        long: "select-1",
+        value: None,
+        help: "With --query, pick the branch right away if it is the only match",
+    },
+    OptSpec {
+        short: Some('z'),
+        long: "null",
+        value: None,
+        help: "End --list, --format and --print output with NUL instead of newline",
+    },
+    OptSpec {
+        short: None,
+        long: "json",
+        value: None,
+        help: "Print recent branches with metadata as a JSON array",
+    },
+    OptSpec {
+        short: None,
+        long: "plain",
+        value: None,
+        help: "With top, print only the names, on one line separated by spaces",
+    },
+    OptSpec {
+        short: None,
+        long: "format",
+        value: Some("TEMPLATE"),
+        help: "Print recent branches using a template, e.g. '{name}\\t{date:relative}\\t{subject}'; with export, json or csv",
+    },
+    OptSpec {
+        short: None,
+        long: "serve",
+        value: None,
+        help: "Answer JSON-RPC requests on stdin (list, refresh, checkout, delete), for editor plugins",
+    },
+    OptSpec {
+        short: None,
+        long: "shortcut",
+        value: Some("[NAME]"),
+        help: "With install-alias, also add 'git NAME' (default: git rb)",
+    },
+    OptSpec {
+        short: None,
+        long: "uninstall",
+        value: None,
+        help: "With install-alias or install-hook, remove what they installed instead",
+    },
+    OptSpec {
+        short: Some('h'),
+        long: "help",
+        value: None,
+        help: "Print help and exit",
+    },
+    OptSpec {
+        short: Some('V'),
+        long: "version",
+        value: None,
+        help: "Print version and exit",
+    },
+    OptSpec {
+        short: None,
+        long: "man",
+        value: None,
+        help: "Print the man page (roff) and exit",
+    },
+];
+
+/// Settings collected from the command line.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub command: Option<Command>,
+    /// Directories to change into, in order, before running git.
+    pub repo: Vec<PathBuf>,
+    /// Config file profile to apply.
+    pub profile: Option<String>,
+    pub list: bool,
+    pub json: bool,
+    /// `top` prints the names alone, on one line.
+    pub plain: bool,
+    pub print: bool,
+    /// Branch name or 1-based index to check out without the picker.
+    pub checkout: Option<String>,
+    pub last: bool,
+    pub delete: bool,
+    pub fetch: bool,
+    /// Shell command run on the chosen branch instead of checking it out.
+    pub exec: Option<String>,
+    pub dry_run: bool,
+    pub verbose: bool,
+    /// Where `--verbose` logs go instead of stderr.
+    pub log_file: Option<PathBuf>,
+    pub timing: bool,
+    pub quiet: bool,
+    pub simple: bool,
+    /// Line-oriented picker output for screen readers.
+    pub accessible: bool,
+    /// No glyphs beyond ASCII and no colors in the picker.
+    pub ascii: bool,
+    /// Choose in a tmux popup and act on the choice here.
+    pub tmux_popup: bool,
+    pub picker: Option<Picker>,
+    /// NUL-terminated records in plain-text output.
+    pub null: bool,
+    /// Maximum number of branches to load; `Some(None)` means no limit.
+    pub limit: Option<Option<usize>>,
+    /// Rows of branches in the picker; at least 1.
+    pub height: Option<usize>,
+    pub query: Option<String>,
+    pub sort: Option<Sort>,
+    pub scope: Option<Scope>,
+    pub contains: Option<String>,
+    /// `--merged`/`--no-merged`, with `Some(None)` meaning the default branch.
+    pub merged: Option<Option<String>>,
+    pub no_merged: Option<Option<String>>,
+    /// Base for comparisons, overriding detection from `origin/HEAD`.
+    pub default_branch: Option<String>,
+    /// Minimum age in days of a branch's last commit.
+    pub stale: Option<u64>,
+    pub select_1: bool,
+    pub format: Option<Template>,
+    /// The format of `export`, taken from `--format`.
+    pub export: Option<ExportFormat>,
+    /// JSON-RPC on stdin and stdout instead of a picker.
+    pub serve: bool,
+    /// The shell `completions` or `init` writes a script for.
+    pub shell: Option<Shell>,
+    /// `--cd-file`, written by the shell functions of `init`.
+    pub cd_file: Option<PathBuf>,
+    /// `--shortcut` for `install-alias`, with `Some(None)` meaning `rb`.
+    pub shortcut: Option<Option<String>>,
+    pub uninstall: bool,
+}
+
+/// What the command line asked for.
+#[derive(Debug)]
+pub enum Parsed {
+    Run(Box<Args>),
+    Help,
+    Version,
+    /// `--man`: print the man page.
+    Man,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CliError {
+    UnknownOption(String),
+    MissingValue(&'static str),
+    UnexpectedValue(&'static str),
+    UnexpectedArgument(String),
+    /// `completions` or `init` without a shell, or with one it has no script for.
+    UnknownShell {
+        command: &'static str,
+        shell: Option<String>,
+        expected: &'static [&'static str],
+    },
+    InvalidValue {
+        option: &'static str,
+        message: String,
+    },
+    /// An option that makes no sense with the command given.
+    NotWithCommand {
+        option: &'static str,
+        command: &'static str,
+    },
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::UnknownOption(opt) => write!(f, "unknown option '{opt}'"),
+            CliError::MissingValue(long) => write!(f, "option '--{long}' requires a value"),
+            CliError::UnexpectedValue(long) => write!(f, "option '--{long}' does not take a value"),
+            CliError::UnexpectedArgument(arg) => write!(f, "unexpected argument '{arg}'"),
+            CliError::UnknownShell {
+                command,
+                shell,
+                expected,
+            } => {
+                match shell {
+                    Some(shell) => write!(f, "unknown shell '{shell}' for '{command}'")?,
+                    None => write!(f, "'{command}' needs a shell")?,
+                }
+                write!(f, " (expected one of: {})", expected.join(", "))
+            }
+            CliError::InvalidValue { option, message } => {
+                write!(f, "invalid value for '--{option}': {message}")
+            }
+            CliError::NotWithCommand { option, command } => {
+                write!(f, "option '--{option}' cannot be used with '{command}'")
+            }
+        }
+    }
+}
+
+impl Error for CliError {}
+
+fn find_long(name: &str) -> Option<&'static OptSpec> {
+    OPTIONS.iter().find(|o| o.long == name)
+}
+
+fn find_short(c: char) -> Option<&'static OptSpec> {
+    OPTIONS.iter().find(|o| o.short == Some(c))
+}
+
+/// Parse the arguments following the program name.
+///
+/// Accepts `--long`, `--long=value`, `--long value`, `-s value`, `-svalue`,
+/// bundled short flags (`-ab`) and `--` to end option parsing.
+pub fn parse<I>(args: I) -> Result<Parsed, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            positional.extend(args.by_ref());
+            break;
+        }
+
+        let mut found: Vec<(&'static OptSpec, Option<String>)> = Vec::new();
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let spec = find_long(name).ok_or_else(|| CliError::UnknownOption(arg.clone()))?;
+            if spec.value.is_none() && inline.is_some() {
+                return Err(CliError::UnexpectedValue(spec.long));
+            }
+            found.push((spec, inline));
+        } else if let Some(shorts) = arg.strip_prefix('-').filter(|s| !s.is_empty()) {
+            for (i, c) in shorts.char_indices() {
+                let spec = find_short(c).ok_or_else(|| CliError::UnknownOption(format!("-{c}")))?;
+                if spec.value.is_some() {
+                    // The rest of the bundle, if any, is this option's value.
+                    let rest = &shorts[i + c.len_utf8()..];
+                    found.push((spec, (!rest.is_empty()).then(|| rest.to_string())));
+                    break;
+                }
+                found.push((spec, None));
+            }
+        } else {
+            positional.push(arg);
+            continue;
+        }
+
+        for (spec, value) in found {
+            let value = match (spec.value, value) {
+                (Some(_), None) if !spec.optional_value() => {
+                    Some(args.next().ok_or(CliError::MissingValue(spec.long))?)
+                }
+                (_, value) => value,
+            };
+            if let Some(outcome) = apply(&mut parsed, spec, value)? {
+                return Ok(outcome);
+            }
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    if let Some(arg) = positional.next() {
+        let command = COMMANDS.iter().find(|(name, _, _)| *name == arg);
+        let (name, command, _) = command.ok_or(CliError::UnexpectedArgument(arg))?;
+        parsed.command = Some(*command);
+        let shells = match command {
+            Command::Completions => Some(Shell::NAMES),
+            Command::Init => Some(init::SHELLS),
+            _ => None,
+        };
+        if *command == Command::Export {
+            // `--format` names the export's format rather than a template here.
+            let template = parsed.format.take();
+            let name = template.as_ref().map(|t| t.as_literal().unwrap_or("{...}"));
+            parsed.export = Some(match name {
+                None => ExportFormat::default(),
+                Some(name) => ExportFormat::parse(name).ok_or_else(|| CliError::InvalidValue {
+                    option: "format",
+                    message: format!(
+                        "unknown export format '{name}' (expected one of: {})",
+                        ExportFormat::NAMES.join(", ")
+                    ),
+                })?,
+            });
+        }
+        if *command == Command::Tags {
+            // Tags are checked out or branched from, never deleted from the picker.
+            if parsed.delete {
+                return Err(CliError::NotWithCommand {
+                    option: "delete",
+                    command: name,
+                });
+            }
+            parsed.scope = Some(Scope::Tags);
+        }
+        if let Some(expected) = shells {
+            let shell = positional.next();
+            let parsed_shell = shell
+                .as_deref()
+                .filter(|s| expected.contains(s))
+                .and_then(Shell::parse);
+            parsed.shell = Some(parsed_shell.ok_or(CliError::UnknownShell {
+                command: name,
+                shell,
+                expected,
+            })?);
+        }
+    }
+    if let Some(arg) = positional.next() {
+        return Err(CliError::UnexpectedArgument(arg));
+    }
+    Ok(Parsed::Run(Box::new(parsed)))
+}
+
+/// Apply one option to `args`. Returns an early outcome for `--help`, `--version` and
+/// `--man`.
+fn apply(
+    args: &mut Args,
+    spec: &'static OptSpec,
+    value: Option<String>,
+) -> Result<Option<Parsed>, CliError> {
+    let invalid = |message: String| CliError::InvalidValue {
+        option: spec.long,
+        message,
+    };
+    let optional = value.clone().filter(|v| !v.is_empty());
+    let value = value.unwrap_or_default();
+    match spec.long {
+        "help" => return Ok(Some(Parsed::Help)),
+        "version" => return Ok(Some(Parsed::Version)),
+        "man" => return Ok(Some(Parsed::Man)),
+        "repo" => args.repo.push(PathBuf::from(refname::to_os(&value))),
+        "profile" => args.profile = Some(value),
+        "list" => args.list = true,
+        "json" => args.json = true,
+        "plain" => args.plain = true,
+        "serve" => args.serve = true,
+        "print" => args.print = true,
+        "checkout" => args.checkout = Some(value),
+        "last" => args.last = true,
+        "delete" => args.delete = true,
+        "fetch" => args.fetch = true,
+        "exec" => args.exec = Some(value),
+        "cd-file" => args.cd_file = Some(PathBuf::from(refname::to_os(&value))),
+        "dry-run" => args.dry_run = true,
+        "verbose" => args.verbose = true,
+        "timing" => args.timing = true,
+        "log-file" => {
+            args.verbose = true;
+            args.log_file = Some(PathBuf::from(refname::to_os(&value)));
+        }
+        "quiet" => args.quiet = true,
+        "simple" => args.simple = true,
+        "accessible" => args.accessible = true,
+        "ascii" => args.ascii = true,
+        "tmux-popup" => args.tmux_popup = true,
+        "null" => args.null = true,
+        "query" => args.query = Some(value),
+        "remote" => args.scope = Some(Scope::Remote),
+        "all" => args.scope = Some(Scope::All),
+        "contains" => args.contains = Some(value),
+        "merged" => args.merged = Some(optional),
+        "no-merged" => args.no_merged = Some(optional),
+        "shortcut" => args.shortcut = Some(optional),
+        "uninstall" => args.uninstall = true,
+        "default-branch" => args.default_branch = Some(value),
+        "stale" => {
+            args.stale = Some(
+                value
+                    .parse()
+                    .map_err(|_| invalid(format!("'{value}' is not a number of days")))?,
+            )
+        }
+        "sort" => {
+            args.sort = Some(Sort::parse(&value).ok_or_else(|| {
+                invalid(format!(
+                    "unknown sort key '{value}' (expected one of: {})",
+                    Sort::NAMES.join(", ")
+                ))
+            })?)
+        }
+        "picker" => {
+            args.picker = Some(Picker::parse(&value).ok_or_else(|| {
+                invalid(format!(
+                    "unknown picker '{value}' (expected one of: {})",
+                    Picker::NAMES.join(", ")
+                ))
+            })?)
+        }
+        "select-1" => args.select_1 = true,
+        "limit" => {
+            args.limit = Some(match value.as_str() {
+                "0" | "all" => None,
+                n => Some(
+                    n.parse()
+                        .map_err(|_| invalid(format!("'{n}' is not a number")))?,
+                ),
+            })
+        }
+        "height" => {
+            args.height = Some(
+                value
+                    .parse()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| invalid(format!("'{value}' is not a positive number")))?,
+            )
+        }
+        "format" => {
+            args.format = Some(Template::parse(&value).map_err(|e| invalid(e.to_string()))?)
+        }
+        long => unreachable!("option --{long} is declared but not handled"),
+    }
+    Ok(None)
+}
+
+/// Render `--help` from the option table.
+pub fn help_text() -> String {
+    let mut out = format!("{NAME} {VERSION}\n{ABOUT}\n\nUsage: {USAGE}\n\nCommands:\n");
+    let width = COMMANDS
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, _, help) in COMMANDS {
+        out.push_str(&format!("  {name:width$}  {help}\n"));
+    }
+    out.push_str("\nOptions:\n");
+    let rows: Vec<(String, &str)> = OPTIONS
+        .iter()
+        .map(|o| {
+            let short = o.short.map_or("    ".to_string(), |c| format!("-{c}, "));
+            let value = match o.value {
+                None => String::new(),
+                Some(v) if o.optional_value() => format!("[=<{}>]", v.trim_matches(['[', ']'])),
+                Some(v) => format!(" <{v}>"),
+            };
+            (format!("{short}--{}{value}", o.long), o.help)
+        })
+        .collect();
+    let width = rows.iter().map(|(left, _)| left.len()).max().unwrap_or(0);
+    for (left, help) in rows {
+        out.push_str(&format!("  {left:width$}  {help}\n"));
+    }
+    out
+}
+
+pub fn version_text() -> String {
+    format!("{NAME} {VERSION}")
+}
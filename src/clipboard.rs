@@ -0,0 +1,77 @@
+//! Copying text to the clipboard (the `yank` action). A local clipboard tool is used
+//! where one is found, and the OSC 52 escape sequence asks the terminal emulator to
+//! set its clipboard itself, which also works over SSH and in tmux, where no local tool
+//! reaches the user's clipboard.
+
+use std::env;
+use std::io::{self, Write};
+use std::process::Command;
+
+use crate::i18n;
+use crate::refname;
+use crate::terminal;
+use crate::trace;
+
+/// Clipboard tools, in the order they are tried, with their arguments and the
+/// environment variable that must be set for them to be of use.
+const TOOLS: &[(&str, &[&str], Option<&str>)] = &[
+    ("pbcopy", &[], None),
+    ("wl-copy", &[], Some("WAYLAND_DISPLAY")),
+    ("xclip", &["-selection", "clipboard"], Some("DISPLAY")),
+    ("xsel", &["--clipboard", "--input"], Some("DISPLAY")),
+    ("clip.exe", &[], None),
+];
+
+/// Copy `text` with the first clipboard tool that works, and with OSC 52 if `osc52`.
+/// Fails only if neither could be used.
+pub fn copy(text: &str, osc52: bool) -> Result<(), String> {
+    let bytes = refname::encode(text);
+    let copied = TOOLS.iter().any(|&(tool, args, needs)| {
+        if needs.is_some_and(|var| env::var_os(var).is_none_or(|v| v.is_empty())) {
+            return false;
+        }
+        trace::status_with_input(Command::new(tool).args(args), &bytes)
+            .is_ok_and(|status| status.success())
+    });
+    if osc52 {
+        // Terminals without OSC 52 ignore it; there is no telling whether it worked.
+        return write_osc52(&bytes)
+            .map_err(|e| i18n::message("clipboard.terminal_failed", &[("error", &e)]));
+    }
+    if copied {
+        Ok(())
+    } else {
+        let tools: Vec<&str> = TOOLS.iter().map(|&(tool, _, _)| tool).collect();
+        Err(i18n::message(
+            "clipboard.no_tool",
+            &[("tools", &tools.join(", "))],
+        ))
+    }
+}
+
+/// Set the terminal's clipboard to `bytes`: `ESC ] 52 ; c ; <base64> BEL`.
+fn write_osc52(bytes: &[u8]) -> io::Result<()> {
+    let mut ui = terminal::ui();
+    write!(ui, "\x1b]52;c;{}\x07", base64(bytes))?;
+    ui.flush()
+}
+
+/// Standard base64 with padding (RFC 4648).
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,344 @@
+//! Completion scripts for `git-recent completions <SHELL>`, generated from the option
+//! table in `cli`, so they never fall behind `--help`. Branch names for `--checkout`
+//! and the other options naming a branch come from `git-recent --list` at the time
+//! of completion.
+
+use crate::cli::{COMMANDS, NAME, OPTIONS, OptSpec};
+use crate::fzf::Picker;
+use crate::git::Sort;
+use crate::init;
+
+/// Shells `completions` writes scripts for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    PowerShell,
+}
+
+impl Shell {
+    pub const NAMES: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "powershell" => Some(Shell::PowerShell),
+            _ => None,
+        }
+    }
+}
+
+/// The command listing every branch, one per line, for dynamic completion.
+const LIST_BRANCHES: &str = "git-recent --list --limit 0";
+
+/// What an option's value completes to.
+enum Values {
+    None,
+    Directories,
+    Files,
+    Branches,
+    Words(&'static [&'static str]),
+}
+
+fn values(option: &OptSpec) -> Values {
+    match (option.long, option.value) {
+        (_, None) => Values::None,
+        ("repo", _) => Values::Directories,
+        ("log-file" | "cd-file", _) => Values::Files,
+        ("sort", _) => Values::Words(Sort::NAMES),
+        ("picker", _) => Values::Words(Picker::NAMES),
+        (_, Some("BRANCH" | "[REF]")) => Values::Branches,
+        _ => Values::None,
+    }
+}
+
+/// The completion script for `shell`.
+pub fn script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => bash(),
+        Shell::Zsh => zsh(),
+        Shell::Fish => fish(),
+        Shell::PowerShell => powershell(),
+    }
+}
+
+fn bash() -> String {
+    let mut cases = String::new();
+    let mut plain = Vec::new();
+    for option in OPTIONS.iter().filter(|o| o.value.is_some()) {
+        // Optional values are attached (`--merged=main`), so the next word is never one.
+        let mut names = vec![format!("--{}=", option.long)];
+        if !option.optional_value() {
+            names.push(format!("--{}", option.long));
+            names.extend(option.short.map(|c| format!("-{c}")));
+        }
+        let reply = match values(option) {
+            Values::None => {
+                plain.extend(names);
+                continue;
+            }
+            Values::Directories => "compgen -d -- \"$cur\"".to_string(),
+            Values::Files => "compgen -f -- \"$cur\"".to_string(),
+            Values::Branches => "compgen -W \"$(__git_recent_branches)\" -- \"$cur\"".to_string(),
+            Values::Words(words) => format!("compgen -W \"{}\" -- \"$cur\"", words.join(" ")),
+        };
+        cases.push_str(&format!(
+            "        {})\n            COMPREPLY=($({reply})); return ;;\n",
+            names.join("|")
+        ));
+    }
+    // Values nothing is known about: offer nothing rather than options.
+    if !plain.is_empty() {
+        cases.push_str(&format!(
+            "        {})\n            return ;;\n",
+            plain.join("|")
+        ));
+    }
+    let words: Vec<String> = OPTIONS
+        .iter()
+        .map(|o| format!("--{}", o.long))
+        .chain(COMMANDS.iter().map(|(name, _, _)| name.to_string()))
+        .collect();
+    format!(
+        r#"# bash completion for {NAME}; load with: source <({NAME} completions bash)
+
+__git_recent_branches() {{
+    {LIST_BRANCHES} 2>/dev/null
+}}
+
+_git_recent() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}"
+    local prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    # Bash splits words at "=", so --sort=na arrives as "--sort", "=" and "na".
+    if [ "$prev" = "=" ]; then
+        prev="${{COMP_WORDS[COMP_CWORD-2]}}="
+    fi
+    case "$prev" in
+{cases}        completions)
+            COMPREPLY=($(compgen -W "{shells}" -- "$cur")); return ;;
+        init)
+            COMPREPLY=($(compgen -W "{init_shells}" -- "$cur")); return ;;
+    esac
+    COMPREPLY=($(compgen -W "{words}" -- "$cur"))
+}}
+
+complete -F _git_recent {NAME}
+"#,
+        shells = Shell::NAMES.join(" "),
+        init_shells = init::SHELLS.join(" "),
+        words = words.join(" "),
+    )
+}
+
+fn zsh() -> String {
+    let mut specs = Vec::new();
+    for option in OPTIONS {
+        let help = zsh_escape(option.help);
+        let optional = option.optional_value();
+        let (long, short) = match (option.value, optional) {
+            (None, _) => (
+                format!("--{}", option.long),
+                option.short.map(|c| format!("-{c}")),
+            ),
+            (Some(_), true) => (
+                format!("--{}=-", option.long),
+                option.short.map(|c| format!("-{c}-")),
+            ),
+            (Some(_), false) => (
+                format!("--{}=", option.long),
+                option.short.map(|c| format!("-{c}+")),
+            ),
+        };
+        let names = match short {
+            Some(short) => format!(
+                "'(-{} --{})'{{{short},{long}}}'",
+                option.short.unwrap_or_default(),
+                option.long
+            ),
+            None => format!("'{long}"),
+        };
+        let action = match values(option) {
+            Values::None => String::new(),
+            Values::Directories => "_directories".to_string(),
+            Values::Files => "_files".to_string(),
+            Values::Branches => "__git_recent_branches".to_string(),
+            Values::Words(words) => format!("({})", words.join(" ")),
+        };
+        let argument = match option.value {
+            None => String::new(),
+            Some(value) => {
+                let name = value.trim_matches(['[', ']']).to_lowercase();
+                let colons = if optional { "::" } else { ":" };
+                format!("{colons}{name}:{action}")
+            }
+        };
+        specs.push(format!("{names}[{help}]{argument}'"));
+    }
+    let commands: Vec<String> = COMMANDS
+        .iter()
+        .map(|(name, _, help)| format!("{name}\\:{}", zsh_quote(help)))
+        .collect();
+    specs.push(format!("'1:command:(({}))'", commands.join(" ")));
+    specs.push(format!("'2:shell:({})'", Shell::NAMES.join(" ")));
+    format!(
+        r#"#compdef {NAME}
+# zsh completion for {NAME}; put it in a directory on $fpath as _{NAME},
+# or load it with: source <({NAME} completions zsh)
+
+__git_recent_branches() {{
+    local -a branches
+    branches=(${{(f)"$({LIST_BRANCHES} 2>/dev/null)"}})
+    compadd -a branches
+}}
+
+_git_recent() {{
+    _arguments -s -S \
+        {specs}
+}}
+
+if [ "$funcstack[1]" = "_git_recent" ]; then
+    _git_recent "$@"
+else
+    compdef _git_recent {NAME}
+fi
+"#,
+        specs = specs.join(" \\\n        "),
+    )
+}
+
+/// Escape option help for a single-quoted `_arguments` spec.
+fn zsh_escape(text: &str) -> String {
+    text.replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+/// A command description in a `((name\:description))` list, itself single-quoted.
+fn zsh_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\'', "'\\''").replace('"', "\\\""))
+}
+
+fn fish() -> String {
+    let mut out = format!(
+        "# fish completion for {NAME}; load with: {NAME} completions fish | source\n\n\
+         complete -c {NAME} -f\n"
+    );
+    let subcommands: Vec<&str> = COMMANDS.iter().map(|(name, _, _)| *name).collect();
+    for (name, _, help) in COMMANDS {
+        out.push_str(&format!(
+            "complete -c {NAME} -n 'not __fish_seen_subcommand_from {}' -a {name} -d {}\n",
+            subcommands.join(" "),
+            fish_quote(help)
+        ));
+    }
+    out.push_str(&format!(
+        "complete -c {NAME} -n '__fish_seen_subcommand_from completions' -a '{}'\n",
+        Shell::NAMES.join(" ")
+    ));
+    out.push_str(&format!(
+        "complete -c {NAME} -n '__fish_seen_subcommand_from init' -a '{}'\n",
+        init::SHELLS.join(" ")
+    ));
+    for option in OPTIONS {
+        let mut line = format!("complete -c {NAME}");
+        if let Some(c) = option.short {
+            line.push_str(&format!(" -s {c}"));
+        }
+        line.push_str(&format!(" -l {}", option.long));
+        if option.value.is_some() && !option.optional_value() {
+            line.push_str(match values(option) {
+                Values::Directories => " -r -a '(__fish_complete_directories)'",
+                Values::Files => " -r -F",
+                _ => " -x",
+            });
+        }
+        match values(option) {
+            Values::Branches => line.push_str(&format!(" -a '({LIST_BRANCHES} 2>/dev/null)'")),
+            Values::Words(words) => line.push_str(&format!(" -a '{}'", words.join(" "))),
+            _ => {}
+        }
+        line.push_str(&format!(" -d {}\n", fish_quote(option.help)));
+        out.push_str(&line);
+    }
+    out
+}
+
+fn fish_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn powershell() -> String {
+    let options: Vec<String> = OPTIONS
+        .iter()
+        .map(|o| format!("        @('--{}', {})", o.long, powershell_quote(o.help)))
+        .chain(
+            COMMANDS
+                .iter()
+                .map(|(name, _, help)| format!("        @('{name}', {})", powershell_quote(help))),
+        )
+        .collect();
+    let mut cases = String::new();
+    // Optional values are attached (`--merged=main`), so the next word is never one.
+    let takes_next = OPTIONS
+        .iter()
+        .filter(|o| o.value.is_some() && !o.optional_value());
+    for option in takes_next {
+        let mut names = vec![format!("'--{}'", option.long)];
+        names.extend(option.short.map(|c| format!("'-{c}'")));
+        let values = match values(option) {
+            Values::Branches => format!("{LIST_BRANCHES} 2>$null; break"),
+            Values::Words(words) => {
+                let words: Vec<String> = words.iter().map(|w| format!("'{w}'")).collect();
+                format!("{}; break", words.join(", "))
+            }
+            // No suggestions of our own; PowerShell falls back to paths.
+            _ => "return".to_string(),
+        };
+        cases.push_str(&format!(
+            "        {{ $_ -in {} }} {{ {values} }}\n",
+            names.join(", ")
+        ));
+    }
+    let quote = |names: &[&str]| -> String {
+        let quoted: Vec<String> = names.iter().map(|s| format!("'{s}'")).collect();
+        quoted.join(", ")
+    };
+    format!(
+        r#"# PowerShell completion for {NAME}; load with:
+# {NAME} completions powershell | Out-String | Invoke-Expression
+
+Register-ArgumentCompleter -Native -CommandName {NAME} -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements | ForEach-Object {{ $_.ToString() }})
+    $previous = if ($wordToComplete) {{ $words[-2] }} else {{ $words[-1] }}
+    $values = switch ($previous) {{
+{cases}        'completions' {{ {shells}; break }}
+        'init' {{ {init_shells}; break }}
+        default {{ $null }}
+    }}
+    if ($null -ne $values) {{
+        $values | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+            [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+        }}
+        return
+    }}
+    $options = @(
+{options}
+    )
+    $options | Where-Object {{ $_[0] -like "$wordToComplete*" }} | ForEach-Object {{
+        [System.Management.Automation.CompletionResult]::new($_[0], $_[0], 'ParameterName', $_[1])
+    }}
+}}
+"#,
+        shells = quote(Shell::NAMES),
+        init_shells = quote(init::SHELLS),
+        options = options.join(",\n"),
+    )
+}
+
+fn powershell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
@@ -0,0 +1,1168 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::app::NO_OF_VISIBLE_BRANCHES;
+use crate::filter::Case;
+use crate::fzf::Picker;
+use crate::git::{self, Sort, SwitchCommand};
+use crate::hooks::Hooks;
+use crate::i18n;
+use crate::keymap::{self, Action, Keymap};
+use crate::theme::{self, ColorDepth, Theme};
+use crate::trace;
+
+/// A setting's value as written in a config file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+    /// Formats the value as TOML.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{s:?}"),
+            Value::Integer(n) => write!(f, "{n}"),
+            Value::Boolean(b) => write!(f, "{b}"),
+            Value::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// The type a setting must have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    String,
+    Integer,
+    Boolean,
+    /// An array of strings; a single string is accepted as a one-element list.
+    Strings,
+}
+
+impl Kind {
+    fn describe(self) -> &'static str {
+        i18n::text(match self {
+            Kind::String => "config.kind_string",
+            Kind::Integer => "config.kind_integer",
+            Kind::Boolean => "config.kind_boolean",
+            Kind::Strings => "config.kind_strings",
+        })
+    }
+
+    /// Check `value` against this kind, normalizing a lone string for `Strings`.
+    fn check(self, value: Value) -> Option<Value> {
+        match (self, value) {
+            (Kind::String, v @ Value::String(_))
+            | (Kind::Integer, v @ Value::Integer(_))
+            | (Kind::Boolean, v @ Value::Boolean(_)) => Some(v),
+            (Kind::Strings, Value::String(s)) => Some(Value::Array(vec![Value::String(s)])),
+            (Kind::Strings, Value::Array(items))
+                if items.iter().all(|v| matches!(v, Value::String(_))) =>
+            {
+                Some(Value::Array(items))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Settings with a fixed name, in the order `config` lists them.
+pub const SETTINGS: &[(&str, Kind)] = &[
+    ("sort", Kind::String),
+    ("limit", Kind::Integer),
+    ("height", Kind::Integer),
+    ("filter_case", Kind::String),
+    ("exclude", Kind::Strings),
+    ("protected", Kind::Strings),
+    ("default_branch", Kind::String),
+    ("confirm.delete", Kind::Boolean),
+    ("confirm.checkout", Kind::Boolean),
+    ("clipboard.osc52", Kind::Boolean),
+    ("simple", Kind::Boolean),
+    ("accessible", Kind::Boolean),
+    ("ascii", Kind::Boolean),
+    ("picker", Kind::String),
+    ("pull_requests", Kind::Boolean),
+    ("annotate", Kind::String),
+    ("switch", Kind::Boolean),
+    ("switch_args", Kind::Strings),
+    ("jj", Kind::Boolean),
+    ("hooks.pre_checkout", Kind::String),
+    ("hooks.post_checkout", Kind::String),
+    ("notify.after", Kind::Integer),
+    ("action", Kind::String),
+    ("exec", Kind::String),
+    ("worktree_dir", Kind::String),
+    ("profile", Kind::String),
+    ("trust_repo_config", Kind::Boolean),
+];
+
+/// Settings a repository's shared `.git-recent.toml` may set: which branches to list
+/// and how to show them. Anything that runs commands, or changes what a key does, is
+/// left to the user's own configuration unless they set `trust_repo_config`, so that
+/// browsing a cloned repository never runs commands its author chose.
+const REPO_SETTINGS: &[&str] = &[
+    "sort",
+    "limit",
+    "height",
+    "filter_case",
+    "exclude",
+    "protected",
+    "default_branch",
+    "simple",
+    "accessible",
+    "ascii",
+    "profile",
+];
+
+/// Whether an untrusted repository config file may set `key`.
+fn repo_may_set(key: &str) -> bool {
+    REPO_SETTINGS.contains(&key) || key.starts_with("colors.")
+}
+
+/// Environment variables that don't follow the `GIT_RECENT_<SETTING>` naming.
+const ENV_ALIASES: &[(&str, &str)] = &[("GIT_RECENT_NO_TUI", "simple")];
+
+/// The kind of setting `key`, or `None` for an unknown key. Besides `SETTINGS`,
+/// this covers one `colors.<slot>` per theme color and one `keys.<action>` per action.
+pub fn kind_of(key: &str) -> Option<Kind> {
+    if let Some(&(_, kind)) = SETTINGS.iter().find(|(name, _)| *name == key) {
+        return Some(kind);
+    }
+    if let Some(slot) = key.strip_prefix("colors.") {
+        return theme::SLOTS.contains(&slot).then_some(Kind::String);
+    }
+    if let Some(action) = key.strip_prefix("keys.") {
+        return Action::from_name(action).map(|_| Kind::Strings);
+    }
+    None
+}
+
+/// Every valid setting name: `SETTINGS`, then colors, then key bindings.
+pub fn all_keys() -> Vec<String> {
+    let mut keys: Vec<String> = SETTINGS.iter().map(|(key, _)| key.to_string()).collect();
+    keys.extend(theme::SLOTS.iter().map(|slot| format!("colors.{slot}")));
+    keys.extend(Action::ALL.iter().map(|a| format!("keys.{}", a.name())));
+    keys
+}
+
+/// Map a git config variable (`recent.defaultBranch`, `recent.keys.page-up`) to its
+/// setting. Git folds case, and dashes and underscores are optional in git-style names.
+fn setting_for_git_key(name: &str) -> Option<String> {
+    let fold = |key: &str| key.to_ascii_lowercase().replace(['_', '-'], "");
+    let wanted = fold(name.strip_prefix("recent.")?);
+    all_keys().into_iter().find(|key| fold(key) == wanted)
+}
+
+impl Value {
+    /// Interpret text from git config or the environment as a setting of `kind`.
+    /// Booleans follow git: true/yes/on/1 and false/no/off/0.
+    fn from_text(kind: Kind, text: &str) -> Option<Value> {
+        Some(match kind {
+            Kind::String => Value::String(text.to_string()),
+            Kind::Integer => Value::Integer(text.trim().parse().ok()?),
+            Kind::Boolean => match text.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Value::Boolean(true),
+                "false" | "no" | "off" | "0" => Value::Boolean(false),
+                _ => return None,
+            },
+            Kind::Strings => Value::Array(vec![Value::String(text.to_string())]),
+        })
+    }
+}
+
+/// Where a setting's value came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    /// The repository's shared `.git-recent.toml`.
+    Repo(PathBuf),
+    /// `git config`, with the file it was read from.
+    GitConfig(String),
+    /// An environment variable.
+    Env(String),
+    /// A `[profile.<name>]` section of a config file.
+    Profile(PathBuf, String),
+    /// A command-line option.
+    Flag(String),
+    /// The built-in default.
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) | Source::Repo(path) => write!(f, "{}", path.display()),
+            Source::GitConfig(origin) => write!(f, "git config ({origin})"),
+            Source::Env(var) => write!(f, "${var}"),
+            Source::Profile(path, name) => write!(f, "{} (profile {name})", path.display()),
+            Source::Flag(option) => write!(f, "{option}"),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub value: Value,
+    pub source: Source,
+}
+
+/// Settings from every configuration layer; later layers override earlier ones.
+#[derive(Debug, Default)]
+pub struct Config {
+    entries: BTreeMap<String, Entry>,
+    /// `[profile.<name>]` sections of the config files, applied only when selected.
+    profiles: BTreeMap<String, BTreeMap<String, Entry>>,
+    /// Problems that did not stop loading, such as settings skipped in an untrusted
+    /// repository config file.
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Load, from lowest to highest priority: the repository's shared
+    /// `.git-recent.toml`, the user's configuration file, `recent.*` from git config
+    /// (system, global and the current repository's) and `GIT_RECENT_*` variables.
+    /// The repository's file only gets its way with `REPO_SETTINGS`, unless one of the
+    /// other layers sets `trust_repo_config`.
+    ///
+    /// `profile` (or else the `profile` setting) names a `[profile.<name>]` section
+    /// whose settings override the rest of the config files, but not git config or
+    /// the environment.
+    pub fn load(profile: Option<&str>) -> Result<Config, Box<dyn Error>> {
+        let mut config = Config::default();
+        config.load_layers(profile)?;
+        Ok(config)
+    }
+
+    /// The built-in defaults of the settings that have one. Code reading the
+    /// settings falls back to these by itself; this is for showing them.
+    pub fn defaults() -> Config {
+        let string = |s: &str| Value::String(s.to_string());
+        let mut values = vec![
+            ("sort".to_string(), string(Sort::default().name())),
+            (
+                "limit".to_string(),
+                Value::Integer(git::MAX_BRANCHES as i64),
+            ),
+            (
+                "height".to_string(),
+                Value::Integer(NO_OF_VISIBLE_BRANCHES as i64),
+            ),
+            ("filter_case".to_string(), string(Case::default().name())),
+            ("exclude".to_string(), Value::Array(Vec::new())),
+            ("protected".to_string(), Value::Array(Vec::new())),
+            ("confirm.delete".to_string(), Value::Boolean(true)),
+            ("confirm.checkout".to_string(), Value::Boolean(false)),
+            ("clipboard.osc52".to_string(), Value::Boolean(true)),
+            ("simple".to_string(), Value::Boolean(false)),
+            ("accessible".to_string(), Value::Boolean(false)),
+            ("ascii".to_string(), Value::Boolean(false)),
+            ("picker".to_string(), string(Picker::default().name())),
+            ("pull_requests".to_string(), Value::Boolean(false)),
+            ("switch".to_string(), Value::Boolean(false)),
+            ("switch_args".to_string(), Value::Array(Vec::new())),
+            ("action".to_string(), string("checkout")),
+        ];
+        values.extend(
+            theme::DEFAULT_COLORS
+                .iter()
+                .map(|&(slot, color)| (format!("colors.{slot}"), string(color))),
+        );
+        values.extend(Action::ALL.iter().map(|action| {
+            let keys = action.default_keys();
+            let keys = keys
+                .iter()
+                .map(|k| Value::String(keymap::key_name(k)))
+                .collect();
+            (format!("keys.{}", action.name()), Value::Array(keys))
+        }));
+        let entries = values
+            .into_iter()
+            .map(|(key, value)| {
+                let source = Source::Default;
+                (key, Entry { value, source })
+            })
+            .collect();
+        Config {
+            entries,
+            ..Config::default()
+        }
+    }
+
+    /// Merge every configuration layer over the current settings, in the order
+    /// described for `load`.
+    pub fn load_layers(&mut self, profile: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let mut user = Config::default();
+        if let Some(path) = user_config_path() {
+            user.load_file(&path)?;
+        }
+        // The profile may be chosen in git config or the environment, which still
+        // have to override its settings, or in either file.
+        let mut overrides = Config::default();
+        overrides.load_git_config()?;
+        overrides.load_env()?;
+        if let Some(path) = repo_config_path() {
+            let trusted = overrides
+                .boolean("trust_repo_config")
+                .or_else(|| user.boolean("trust_repo_config"))
+                .unwrap_or(false);
+            self.load_repo_file(&path, trusted)?;
+        }
+        self.merge(user);
+        let chosen = profile
+            .or_else(|| overrides.string("profile"))
+            .or_else(|| self.string("profile"))
+            .map(str::to_string);
+        if let Some(name) = chosen {
+            self.apply_profile(&name)?;
+        }
+        self.merge(overrides);
+        Ok(())
+    }
+
+    /// Take over `other`'s settings, profiles and warnings, its settings winning.
+    fn merge(&mut self, mut other: Config) {
+        self.entries.append(&mut other.entries);
+        for (name, mut settings) in other.profiles {
+            self.profiles.entry(name).or_default().append(&mut settings);
+        }
+        self.warnings.append(&mut other.warnings);
+    }
+
+    fn apply_profile(&mut self, name: &str) -> Result<(), String> {
+        let Some(settings) = self.profiles.get(name) else {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            return Err(if known.is_empty() {
+                i18n::message("config.no_profiles", &[("name", &name)])
+            } else {
+                i18n::message(
+                    "config.unknown_profile",
+                    &[("name", &name), ("known", &known.join(", "))],
+                )
+            });
+        };
+        let settings = settings.clone();
+        self.entries.extend(settings);
+        Ok(())
+    }
+
+    /// Merge `GIT_RECENT_<SETTING>` environment variables, e.g. `GIT_RECENT_SORT` or
+    /// `GIT_RECENT_CONFIRM_DELETE`. Lists are comma-separated; empty variables are ignored.
+    pub fn load_env(&mut self) -> Result<(), Box<dyn Error>> {
+        let names = all_keys().into_iter().map(|key| {
+            let var = format!("GIT_RECENT_{}", key.replace('.', "_").to_ascii_uppercase());
+            (var, key)
+        });
+        let aliases = ENV_ALIASES
+            .iter()
+            .map(|&(var, key)| (var.to_string(), key.to_string()));
+        for (var, key) in names.chain(aliases) {
+            let Some(text) = env::var(&var).ok().filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            let kind = kind_of(&key).unwrap_or(Kind::String);
+            let value = match kind {
+                Kind::Strings => Value::Array(
+                    text.split(',')
+                        .map(|item| Value::String(item.trim().to_string()))
+                        .collect(),
+                ),
+                kind => Value::from_text(kind, &text).ok_or_else(|| {
+                    i18n::message(
+                        "config.wrong_kind_text",
+                        &[
+                            ("key", &format!("${var}")),
+                            ("kind", &kind.describe()),
+                            ("text", &text),
+                        ],
+                    )
+                })?,
+            };
+            self.set(&key, value, Source::Env(var))?;
+        }
+        Ok(())
+    }
+
+    /// Merge `recent.*` variables from git config. Multi-valued variables such as
+    /// `recent.exclude` form a list; for the rest the last value wins, as in git.
+    pub fn load_git_config(&mut self) -> Result<(), Box<dyn Error>> {
+        let output = trace::output(git::command().args([
+            "config",
+            "-z",
+            "--show-origin",
+            "--get-regexp",
+            r"^recent\.",
+        ]))?;
+        if !output.status.success() {
+            // Exit status 1 just means no variables matched.
+            return Ok(());
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lists: BTreeMap<String, (Vec<Value>, Source)> = BTreeMap::new();
+        let mut records = stdout.split('\0');
+        while let (Some(origin), Some(variable)) = (records.next(), records.next()) {
+            // A variable without `= value` is "name" alone, meaning true.
+            let (name, text) = variable.split_once('\n').unwrap_or((variable, "true"));
+            let source = Source::GitConfig(origin.strip_prefix("file:").unwrap_or(origin).into());
+            // Newer versions may add settings; an older one still has to start.
+            let Some(key) = setting_for_git_key(name) else {
+                self.warnings.push(i18n::message(
+                    "config.unknown_git_key",
+                    &[("source", &source), ("key", &name)],
+                ));
+                continue;
+            };
+            let kind = kind_of(&key).unwrap_or(Kind::String);
+            let value = Value::from_text(kind, text).ok_or_else(|| {
+                i18n::message(
+                    "config.wrong_kind_text",
+                    &[
+                        ("key", &format!("{source}: '{name}'")),
+                        ("kind", &kind.describe()),
+                        ("text", &text),
+                    ],
+                )
+            })?;
+            match value {
+                Value::Array(items) if kind == Kind::Strings => {
+                    let list = lists
+                        .entry(key)
+                        .or_insert_with(|| (Vec::new(), source.clone()));
+                    list.0.extend(items);
+                    list.1 = source;
+                }
+                value => self.set(&key, value, source)?,
+            }
+        }
+        for (key, (items, source)) in lists {
+            self.set(&key, Value::Array(items), source)?;
+        }
+        Ok(())
+    }
+
+    /// Merge the settings of a TOML file; a missing file is not an error.
+    pub fn load_file(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.load_toml(path, Source::File(path.to_path_buf()), true)
+    }
+
+    /// Merge the repository's shared config file. Unless `trusted`, settings outside
+    /// `REPO_SETTINGS` (in profiles too) are skipped with a warning.
+    fn load_repo_file(&mut self, path: &Path, trusted: bool) -> Result<(), Box<dyn Error>> {
+        self.load_toml(path, Source::Repo(path.to_path_buf()), trusted)
+    }
+
+    fn load_toml(
+        &mut self,
+        path: &Path,
+        source: Source,
+        trusted: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                let path = path.display();
+                return Err(
+                    i18n::message("config.cannot_read", &[("path", &path), ("error", &e)]).into(),
+                );
+            }
+        };
+        for (line, key, value) in parse_toml(&text).map_err(|e| format!("{source}:{e}"))? {
+            let setting = match key.strip_prefix("profile.") {
+                Some(rest) => rest.split_once('.').map_or(key.as_str(), |(_, s)| s),
+                None => key.as_str(),
+            };
+            if !trusted && !repo_may_set(setting) {
+                let at = format!("{source}:{line}");
+                self.warnings.push(i18n::message(
+                    "config.untrusted",
+                    &[("source", &at), ("key", &key)],
+                ));
+                continue;
+            }
+            let result = match key.strip_prefix("profile.") {
+                Some(rest) => self.set_in_profile(rest, value, path),
+                None => self.set(&key, value, source.clone()),
+            };
+            result.map_err(|e| format!("{source}:{line}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Store `<name>.<setting>` from a `[profile.<name>]` section.
+    fn set_in_profile(&mut self, key: &str, value: Value, path: &Path) -> Result<(), String> {
+        let (name, setting) = key
+            .split_once('.')
+            .ok_or_else(|| i18n::message("config.profile_not_table", &[("key", &key)]))?;
+        if setting == "profile" {
+            return Err(i18n::text("config.profile_in_profile").to_string());
+        }
+        let source = Source::Profile(path.to_path_buf(), name.to_string());
+        let mut scratch = Config::default();
+        scratch.set(setting, value, source)?;
+        self.profiles
+            .entry(name.to_string())
+            .or_default()
+            .append(&mut scratch.entries);
+        Ok(())
+    }
+
+    /// Set `key`, checking that it exists and that `value` has the right type.
+    pub fn set(&mut self, key: &str, value: Value, source: Source) -> Result<(), String> {
+        let kind = kind_of(key)
+            .ok_or_else(|| i18n::message("config.unknown_setting", &[("key", &key)]))?;
+        let value = kind.check(value).ok_or_else(|| {
+            i18n::message(
+                "config.wrong_kind",
+                &[("key", &key), ("kind", &kind.describe())],
+            )
+        })?;
+        self.entries
+            .insert(key.to_string(), Entry { value, source });
+        Ok(())
+    }
+
+    /// Every setting with a value, in the order of `all_keys`.
+    pub fn entries(&self) -> Vec<(String, &Entry)> {
+        all_keys()
+            .into_iter()
+            .filter_map(|key| self.entries.get(&key).map(|entry| (key, entry)))
+            .collect()
+    }
+
+    pub fn string(&self, key: &str) -> Option<&str> {
+        match self.entries.get(key).map(|e| &e.value) {
+            Some(Value::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn integer(&self, key: &str) -> Option<i64> {
+        match self.entries.get(key).map(|e| &e.value) {
+            Some(Value::Integer(n)) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn boolean(&self, key: &str) -> Option<bool> {
+        match self.entries.get(key).map(|e| &e.value) {
+            Some(Value::Boolean(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn strings(&self, key: &str) -> Option<Vec<String>> {
+        match self.entries.get(key).map(|e| &e.value) {
+            Some(Value::Array(items)) => Some(
+                items
+                    .iter()
+                    .filter_map(|v| match v {
+                        Value::String(s) => Some(s.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Settings under `prefix.`, with the prefix removed.
+    pub fn section<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a Entry)> {
+        self.entries.iter().filter_map(move |(key, entry)| {
+            key.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .map(|rest| (rest, entry))
+        })
+    }
+
+    /// An error for a setting whose value is well-typed but not acceptable.
+    pub fn invalid(&self, key: &str, message: impl fmt::Display) -> String {
+        match self.entries.get(key) {
+            Some(entry) => i18n::message(
+                "config.invalid_in",
+                &[
+                    ("key", &key),
+                    ("source", &entry.source),
+                    ("message", &message),
+                ],
+            ),
+            None => i18n::message("config.invalid", &[("key", &key), ("message", &message)]),
+        }
+    }
+}
+
+/// Typed views of the settings, validated where they are read.
+impl Config {
+    pub fn sort(&self) -> Result<Option<Sort>, String> {
+        let Some(name) = self.string("sort") else {
+            return Ok(None);
+        };
+        Sort::parse(name).map(Some).ok_or_else(|| {
+            self.invalid(
+                "sort",
+                i18n::message(
+                    "config.unknown_sort",
+                    &[("name", &name), ("names", &Sort::NAMES.join(", "))],
+                ),
+            )
+        })
+    }
+
+    pub fn filter_case(&self) -> Result<Option<Case>, String> {
+        let Some(name) = self.string("filter_case") else {
+            return Ok(None);
+        };
+        Case::parse(name).map(Some).ok_or_else(|| {
+            self.invalid(
+                "filter_case",
+                i18n::message(
+                    "config.unknown_case",
+                    &[("name", &name), ("names", &Case::NAMES.join(", "))],
+                ),
+            )
+        })
+    }
+
+    pub fn picker(&self) -> Result<Option<Picker>, String> {
+        let Some(name) = self.string("picker") else {
+            return Ok(None);
+        };
+        Picker::parse(name).map(Some).ok_or_else(|| {
+            self.invalid(
+                "picker",
+                i18n::message(
+                    "config.unknown_picker",
+                    &[("name", &name), ("names", &Picker::NAMES.join(", "))],
+                ),
+            )
+        })
+    }
+
+    /// `limit`, where 0 means no limit (`Some(None)`).
+    pub fn limit(&self) -> Result<Option<Option<usize>>, String> {
+        match self.integer("limit") {
+            None => Ok(None),
+            Some(0) => Ok(Some(None)),
+            Some(n) => usize::try_from(n)
+                .map(|n| Some(Some(n)))
+                .map_err(|_| self.invalid("limit", i18n::text("config.negative"))),
+        }
+    }
+
+    /// `height`, the number of branches the picker shows at once.
+    pub fn height(&self) -> Result<Option<usize>, String> {
+        match self.integer("height") {
+            None => Ok(None),
+            Some(n) => usize::try_from(n)
+                .ok()
+                .filter(|&n| n > 0)
+                .map(Some)
+                .ok_or_else(|| self.invalid("height", i18n::text("config.below_one"))),
+        }
+    }
+
+    /// How to change branches: `switch` and `switch_args`.
+    pub fn switch_command(&self) -> SwitchCommand {
+        SwitchCommand {
+            use_switch: self.boolean("switch").unwrap_or(false),
+            args: self.strings("switch_args").unwrap_or_default(),
+            jj: self.boolean("jj"),
+        }
+    }
+
+    pub fn hooks(&self) -> Result<Hooks, String> {
+        let notify_after = match self.integer("notify.after") {
+            None => None,
+            Some(seconds) => Some(
+                u64::try_from(seconds)
+                    .map(Duration::from_secs)
+                    .map_err(|_| self.invalid("notify.after", i18n::text("config.negative")))?,
+            ),
+        };
+        Ok(Hooks {
+            pre_checkout: self.string("hooks.pre_checkout").map(String::from),
+            post_checkout: self.string("hooks.post_checkout").map(String::from),
+            notify_after,
+        })
+    }
+
+    /// The default key bindings with any `keys.<action>` overrides applied.
+    pub fn keymap(&self) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        for (name, _) in self.section("keys") {
+            let key = format!("keys.{name}");
+            let mut keys = Vec::new();
+            for spec in self.strings(&key).unwrap_or_default() {
+                let parsed = keymap::parse_key(&spec).ok_or_else(|| {
+                    self.invalid(&key, i18n::message("config.unknown_key", &[("key", &spec)]))
+                })?;
+                keys.push(parsed);
+            }
+            if let Some(action) = Action::from_name(name) {
+                keymap.bind(action, keys);
+            }
+        }
+        Ok(keymap)
+    }
+
+    pub fn theme(&self) -> Result<Theme, String> {
+        let depth = ColorDepth::detect();
+        let mut colors = Vec::new();
+        for (slot, entry) in self.section("colors") {
+            let Value::String(name) = &entry.value else {
+                continue;
+            };
+            // Each color on its own first, so an error names the layer that set it.
+            Theme::from_colors([(slot, name.as_str())], depth)
+                .map_err(|e| format!("{}: {e}", entry.source))?;
+            colors.push((slot, name.as_str()));
+        }
+        Theme::from_colors(colors, depth)
+    }
+}
+
+/// `.git-recent.toml` at the root of the current worktree, meant to be committed so a
+/// team shares the same defaults.
+pub fn repo_config_path() -> Option<PathBuf> {
+    Some(git::top_level()?.join(".git-recent.toml"))
+}
+
+/// `$XDG_CONFIG_HOME/git-recent/config.toml`, falling back to `~/.config`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("git-recent").join("config.toml"))
+}
+
+/// Parse the subset of TOML that configuration needs: `[tables]`, bare, quoted and
+/// dotted keys, strings, integers, booleans and (possibly multi-line) arrays.
+/// Returns `(line, dotted.key, value)` triples; errors are prefixed with their line.
+pub fn parse_toml(text: &str) -> Result<Vec<(usize, String, Value)>, String> {
+    let mut parser = Parser {
+        chars: text.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser
+        .document()
+        .map_err(|e| format!("{}: {e}", parser.line))
+}
+
+/// A character for an error message.
+fn describe(c: Option<char>) -> String {
+    match c {
+        Some('\n' | '\r') => i18n::text("toml.end_of_line").to_string(),
+        Some(c) => format!("'{c}'"),
+        None => i18n::text("toml.end_of_file").to_string(),
+    }
+}
+
+/// "expected `wanted`, found `found`".
+fn expected(wanted: &str, found: Option<char>) -> String {
+    i18n::message(
+        "toml.expected",
+        &[("wanted", &wanted), ("found", &describe(found))],
+    )
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    /// Consume `wanted`; anything else is left in place, so an error at a line
+    /// break still counts as on the line it ends.
+    fn expect(&mut self, wanted: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == wanted => {
+                self.bump();
+                Ok(())
+            }
+            found => Err(expected(&format!("'{wanted}'"), found)),
+        }
+    }
+
+    /// Skip spaces and tabs (not newlines).
+    fn skip_blanks(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    /// Skip whitespace, newlines and comments.
+    fn skip_space(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while self.peek().is_some_and(|c| c != '\n') {
+            self.bump();
+        }
+    }
+
+    /// After a header or value only a comment may follow on the same line.
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_blanks();
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => Ok(()),
+            found => Err(i18n::message(
+                "toml.after_value",
+                &[("found", &describe(found))],
+            )),
+        }
+    }
+
+    fn document(&mut self) -> Result<Vec<(usize, String, Value)>, String> {
+        let mut entries: Vec<(usize, String, Value)> = Vec::new();
+        let mut table = String::new();
+        loop {
+            self.skip_space();
+            let Some(c) = self.peek() else {
+                return Ok(entries);
+            };
+            if c == '[' {
+                self.bump();
+                if self.peek() == Some('[') {
+                    return Err(i18n::text("toml.table_arrays").to_string());
+                }
+                self.skip_blanks();
+                table = self.key()?;
+                self.skip_blanks();
+                self.expect(']')?;
+                self.end_of_line()?;
+                continue;
+            }
+
+            let line = self.line;
+            let key = self.key()?;
+            let key = if table.is_empty() {
+                key
+            } else {
+                format!("{table}.{key}")
+            };
+            self.skip_blanks();
+            self.expect('=')?;
+            self.skip_blanks();
+            let value = self.value()?;
+            self.end_of_line()?;
+            if entries.iter().any(|(_, k, _)| *k == key) {
+                return Err(i18n::message("toml.set_twice", &[("key", &key)]));
+            }
+            entries.push((line, key, value));
+        }
+    }
+
+    /// A possibly dotted key, returned joined with dots.
+    fn key(&mut self) -> Result<String, String> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_blanks();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while self
+                        .peek()
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.bump();
+                    }
+                    if self.pos == start {
+                        return Err(expected(i18n::text("toml.a_key"), self.peek()));
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_blanks();
+            if self.peek() != Some('.') {
+                return Ok(parts.join("."));
+            }
+            self.bump();
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some(c) if c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '_' => {
+                let start = self.pos;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || "+-_.:".contains(c))
+                {
+                    self.bump();
+                }
+                let word: String = self.chars[start..self.pos].iter().collect();
+                match word.as_str() {
+                    "true" => Ok(Value::Boolean(true)),
+                    "false" => Ok(Value::Boolean(false)),
+                    _ => word
+                        .replace('_', "")
+                        .parse()
+                        .map(Value::Integer)
+                        .map_err(|_| i18n::message("toml.unsupported_value", &[("value", &word)])),
+                }
+            }
+            found => Err(expected(i18n::text("toml.a_value"), found)),
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_space();
+            if self.peek() == Some(']') {
+                self.bump();
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_space();
+            match self.bump() {
+                Some(',') => {}
+                Some(']') => return Ok(Value::Array(items)),
+                Some(c) => {
+                    return Err(expected(i18n::text("toml.comma_or_bracket"), Some(c)));
+                }
+                None => return Err(i18n::text("toml.unterminated_array").to_string()),
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        if self.peek() == Some('"') && self.chars.get(self.pos + 1) == Some(&'"') {
+            return Err(i18n::text("toml.multi_line").to_string());
+        }
+        let mut out = String::new();
+        loop {
+            match self.string_char()? {
+                '"' => return Ok(out),
+                '\\' => out.push(self.escape()?),
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        Ok(match self.bump() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('e') => '\x1b',
+            Some(u @ ('u' | 'U')) => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let digits: String = (0..len).filter_map(|_| self.bump()).collect();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        let escape = format!("\\{u}{digits}");
+                        i18n::message("toml.invalid_unicode", &[("escape", &escape)])
+                    })?
+            }
+            Some(c) => {
+                let escape = format!("\\{c}");
+                return Err(i18n::message("toml.invalid_escape", &[("escape", &escape)]));
+            }
+            None => return Err(i18n::text("toml.unterminated_string").to_string()),
+        })
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut out = String::new();
+        loop {
+            match self.string_char()? {
+                '\'' => return Ok(out),
+                c => out.push(c),
+            }
+        }
+    }
+
+    /// The next character of a string, which has to end on its line.
+    fn string_char(&mut self) -> Result<char, String> {
+        match self.peek() {
+            Some(c) if c != '\n' => {
+                self.pos += 1;
+                Ok(c)
+            }
+            _ => Err(i18n::text("toml.unterminated_string").to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Vec<(usize, String, Value)> {
+        parse_toml(text).unwrap()
+    }
+
+    fn string(text: &str) -> Value {
+        Value::String(text.to_string())
+    }
+
+    #[test]
+    fn basic_strings_unescape_and_literal_strings_do_not() {
+        i18n::set_language("en");
+        let entries = parse("a = \"q\\\"b\\\\s\\tt\\nn\\u00e9\\U0001F600\"\nb = 'C:\\dir\\n'\n");
+        assert_eq!(
+            entries,
+            [
+                (1, "a".to_string(), string("q\"b\\s\tt\nn\u{e9}\u{1F600}")),
+                (2, "b".to_string(), string("C:\\dir\\n")),
+            ]
+        );
+        assert_eq!(
+            parse_toml("a = \"\\x\"").unwrap_err(),
+            "1: invalid escape '\\x'"
+        );
+        assert_eq!(
+            parse_toml("a = \"\\uD800\"").unwrap_err(),
+            "1: invalid unicode escape '\\uD800'"
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped_but_not_inside_strings() {
+        i18n::set_language("en");
+        let entries = parse(
+            "# leading\n\nsort = \"name\" # trailing\n[colors] # table\nhighlight = '#ff0000'\nexclude = [\n  \"a\", # first\n  \"b\",\n]\n",
+        );
+        assert_eq!(
+            entries,
+            [
+                (3, "sort".to_string(), string("name")),
+                (5, "colors.highlight".to_string(), string("#ff0000")),
+                (
+                    6,
+                    "colors.exclude".to_string(),
+                    Value::Array(vec![string("a"), string("b")])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_errors_with_their_line() {
+        i18n::set_language("en");
+        let cases = [
+            ("sort \"name\"", "1: expected '=', found '\"'"),
+            ("\nsort = \"name", "2: unterminated string"),
+            ("sort = 'name\nlimit = 1", "1: unterminated string"),
+            ("sort = \"name\" extra", "1: unexpected 'e' after value"),
+            ("limit = ten", "1: unsupported value 'ten'"),
+            (
+                "exclude = [\"a\" \"b\"]",
+                "1: expected ',' or ']' in array, found '\"'",
+            ),
+            (
+                "[colors\nhighlight = 1",
+                "1: expected ']', found end of line",
+            ),
+            ("[[hooks]]", "1: arrays of tables are not supported"),
+            ("= 1", "1: expected a key, found '='"),
+            ("sort =", "1: expected a value, found end of file"),
+        ];
+        for (text, error) in cases {
+            assert_eq!(parse_toml(text).unwrap_err(), error, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn duplicate_keys_are_errors_however_they_are_spelled() {
+        i18n::set_language("en");
+        assert_eq!(
+            parse_toml("limit = 1\nlimit = 2").unwrap_err(),
+            "2: 'limit' is set twice"
+        );
+        assert_eq!(
+            parse_toml("colors.highlight = \"red\"\n[colors]\n\"highlight\" = \"blue\"")
+                .unwrap_err(),
+            "3: 'colors.highlight' is set twice"
+        );
+        // The same name in another table is a different key.
+        assert_eq!(
+            parse("sort = \"name\"\n[profile.a]\nsort = \"name\"").len(),
+            2
+        );
+    }
+
+    #[test]
+    fn theme_errors_name_the_layer_of_the_bad_color() {
+        i18n::set_language("en");
+        let mut config = Config::default();
+        let user = Source::File(PathBuf::from("/home/me/config.toml"));
+        config
+            .set("colors.highlight", string("green"), user)
+            .unwrap();
+        config
+            .set(
+                "colors.pagination",
+                string("mauve"),
+                Source::Env("GIT_RECENT_COLORS_PAGINATION".to_string()),
+            )
+            .unwrap();
+        let error = config.theme().unwrap_err();
+        assert!(
+            error.starts_with("$GIT_RECENT_COLORS_PAGINATION: unknown color 'mauve'"),
+            "{error}"
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use crate::i18n;
+use crate::keys::Key;
+
+const RESET: &str = "\x1b[0m";
+
+/// Yes/no confirmation overlay for destructive actions.
+/// Defaults to "No" so a stray Enter never confirms anything.
+pub struct ConfirmDialog {
+    prompt: String,
+    yes_selected: bool,
+}
+
+impl ConfirmDialog {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        ConfirmDialog {
+            prompt: prompt.into(),
+            yes_selected: false,
+        }
+    }
+
+    /// Render the dialog as a single line with the focused button drawn with
+    /// `highlight`.
+    pub fn render(&self, highlight: &str) -> String {
+        let (yes, no) = if self.yes_selected {
+            (highlight, "")
+        } else {
+            ("", highlight)
+        };
+        format!(
+            "{} {yes}[ {} ]{RESET} {no}[ {} ]{RESET}",
+            self.prompt,
+            i18n::text("confirm.yes"),
+            i18n::text("confirm.no")
+        )
+    }
+
+    /// The dialog as plain text for accessible mode, naming the focused button.
+    pub fn announcement(&self) -> String {
+        let focused = if self.yes_selected {
+            i18n::text("confirm.yes")
+        } else {
+            i18n::text("confirm.no")
+        };
+        format!("{} [{focused}]", self.prompt)
+    }
+
+    /// Feed a key to the dialog. Returns the answer once the user has decided.
+    pub fn handle_key(&mut self, key: Key) -> Option<bool> {
+        match key {
+            Key::Char('y') | Key::Char('Y') => Some(true),
+            Key::Char('n') | Key::Char('N') | Key::Char('q') | Key::Esc | Key::Ctrl('g') => {
+                Some(false)
+            }
+            Key::Enter => Some(self.yes_selected),
+            Key::Left | Key::Right | Key::Tab | Key::Char('h') | Key::Char('l') => {
+                self.yes_selected = !self.yes_selected;
+                None
+            }
+            _ => None,
+        }
+    }
+}
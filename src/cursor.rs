@@ -0,0 +1,33 @@
+use std::fs;
+use std::io;
+
+use crate::history;
+
+/// Where the picker's selection was when it last closed in this repository.
+pub struct Cursor {
+    pub branch: String,
+    /// The selected row's position in the visible window.
+    pub row: usize,
+}
+
+impl Cursor {
+    /// The cursor saved for the current repository, if any.
+    pub fn load() -> Option<Cursor> {
+        let text = fs::read_to_string(history::state_file("cursor")?).ok()?;
+        let (row, branch) = text.trim_end_matches('\n').split_once('\t')?;
+        Some(Cursor {
+            branch: branch.to_string(),
+            row: row.parse().ok()?,
+        })
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = history::state_file("cursor") else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, format!("{}\t{}\n", self.row, self.branch))
+    }
+}
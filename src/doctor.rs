@@ -0,0 +1,303 @@
+//! `git-recent doctor`: checks of everything git-recent depends on (git itself, the
+//! repository, the terminal, the config files and the state directory), each with a
+//! suggested fix when something is off.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::IsTerminal;
+
+use crate::config::{self, Config};
+use crate::error::AppError;
+use crate::git::{self, Location};
+use crate::history;
+use crate::i18n;
+use crate::terminal::RawModeGuard;
+use crate::theme::ColorDepth;
+use crate::trace;
+
+/// The oldest git with everything git-recent uses (`git branch --show-current`).
+const MIN_GIT_VERSION: (u32, u32) = (2, 22);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Works, but not fully or not as the user may expect.
+    Warning,
+    /// Stops git-recent from working.
+    Problem,
+}
+
+/// The outcome of one check.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or problem.
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warning(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Warning,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn problem(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Problem,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match self.status {
+            Status::Ok => "ok",
+            Status::Warning => "warning",
+            Status::Problem => "PROBLEM",
+        };
+        write!(f, "{mark:<8} {:<11} {}", self.name, self.detail)?;
+        if let Some(fix) = &self.fix {
+            let label = i18n::text("doctor.fix");
+            write!(f, "\n{:<21}{label}: {fix}", "")?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every check, with the config profile `profile` applied.
+pub fn run(profile: Option<&str>) -> Vec<Check> {
+    let git = check_git();
+    let mut checks = Vec::new();
+    // Without git, asking it about the repository is pointless.
+    let has_git = git.status != Status::Problem;
+    checks.push(git);
+    if has_git {
+        checks.push(check_repository());
+    }
+    checks.push(check_input());
+    checks.push(check_terminal());
+    checks.push(check_config(profile));
+    checks.push(check_state());
+    checks
+}
+
+fn check_git() -> Check {
+    let output = match trace::output(git::command().arg("--version")) {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            return Check::problem(
+                "git",
+                i18n::message("doctor.git_failed", &[("status", &output.status)]),
+                i18n::text("doctor.reinstall_git"),
+            );
+        }
+        Err(e) => {
+            return Check::problem(
+                "git",
+                i18n::message("git.cannot_run", &[("error", &e)]),
+                i18n::text("doctor.install_git"),
+            );
+        }
+    };
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let number = version.strip_prefix("git version ").unwrap_or(&version);
+    let mut parts = number.split('.').map(|part| part.parse::<u32>().ok());
+    match (parts.next().flatten(), parts.next().flatten()) {
+        (Some(major), Some(minor)) if (major, minor) < MIN_GIT_VERSION => Check::problem(
+            "git",
+            i18n::message("doctor.git_too_old", &[("version", &version)]),
+            i18n::message(
+                "doctor.upgrade_git",
+                &[(
+                    "version",
+                    &format!("{}.{}", MIN_GIT_VERSION.0, MIN_GIT_VERSION.1),
+                )],
+            ),
+        ),
+        _ => Check::ok("git", version),
+    }
+}
+
+fn check_repository() -> Check {
+    match git::check_repository() {
+        Ok(Location::WorkTree) => {
+            if let Some(lock) = git::find_lock() {
+                return Check::warning(
+                    "repository",
+                    i18n::message("doctor.locked", &[("lock", &lock)]),
+                    i18n::text("doctor.wait_for_lock"),
+                );
+            }
+            let top = git::top_level().map_or_else(String::new, |p| p.display().to_string());
+            Check::ok(
+                "repository",
+                i18n::message("doctor.worktree", &[("path", &top)]),
+            )
+        }
+        Ok(Location::NoWorkTree) => Check::warning(
+            "repository",
+            i18n::text("doctor.bare"),
+            i18n::text("doctor.use_bare"),
+        ),
+        Err(AppError::NotARepository) => Check::warning(
+            "repository",
+            i18n::text("doctor.not_a_repository"),
+            i18n::text("doctor.find_repository"),
+        ),
+        Err(e) => Check::problem("repository", e.to_string(), i18n::text("doctor.check_git")),
+    }
+}
+
+fn check_input() -> Check {
+    if !std::io::stdin().is_terminal() {
+        return Check::warning(
+            "input",
+            i18n::text("doctor.stdin_not_terminal"),
+            i18n::text("doctor.use_terminal"),
+        );
+    }
+    if cfg!(unix) && !RawModeGuard::new().is_enabled() {
+        return Check::problem(
+            "input",
+            i18n::text("terminal.raw_mode"),
+            i18n::text("doctor.use_regular_terminal"),
+        );
+    }
+    Check::ok("input", i18n::text("doctor.input_ok"))
+}
+
+fn check_terminal() -> Check {
+    let term = env::var("TERM").unwrap_or_default();
+    if term.is_empty() || term == "dumb" {
+        let detail = if term.is_empty() {
+            "doctor.term_not_set"
+        } else {
+            "doctor.term_dumb"
+        };
+        return Check::warning(
+            "terminal",
+            i18n::text(detail),
+            i18n::text("doctor.set_term"),
+        );
+    }
+    let colors = ColorDepth::detect().describe();
+    // The picker draws in place below the prompt; it never switches screens.
+    Check::ok(
+        "terminal",
+        i18n::message(
+            "doctor.terminal_ok",
+            &[("term", &term), ("colors", &colors)],
+        ),
+    )
+}
+
+fn check_config(profile: Option<&str>) -> Check {
+    let files: Vec<String> = [config::repo_config_path(), config::user_config_path()]
+        .into_iter()
+        .flatten()
+        .filter(|path| path.is_file())
+        .map(|path| path.display().to_string())
+        .collect();
+    let fix = i18n::text("doctor.fix_setting");
+    let config = match Config::load(profile) {
+        Ok(config) => config,
+        Err(e) => return Check::problem("config", e.to_string(), fix),
+    };
+    // The same validation a run does before opening the picker.
+    let settings = [
+        config.sort().err(),
+        config.filter_case().err(),
+        config.picker().err(),
+        config.limit().err(),
+        config.height().err(),
+        config.keymap().err(),
+        config.theme().err(),
+    ];
+    if let Some(e) = settings.into_iter().flatten().next() {
+        return Check::problem("config", e, fix);
+    }
+    if let Some(warning) = config.warnings.first() {
+        return Check::warning("config", warning.clone(), i18n::text("doctor.untrusted"));
+    }
+    if files.is_empty() {
+        Check::ok("config", i18n::text("doctor.no_config"))
+    } else {
+        let files = files.join(", ");
+        Check::ok(
+            "config",
+            i18n::message("doctor.read_config", &[("files", &files)]),
+        )
+    }
+}
+
+fn check_state() -> Check {
+    let Some(dir) = history::state_dir() else {
+        return Check::warning(
+            "state",
+            i18n::text("doctor.no_state_dir"),
+            i18n::text("doctor.set_home"),
+        );
+    };
+    if !dir.exists() {
+        return Check::ok(
+            "state",
+            i18n::message("doctor.state_missing", &[("dir", &dir.display())]),
+        );
+    }
+    let probe = dir.join(format!(".doctor-{}", std::process::id()));
+    if let Err(e) = fs::write(&probe, "") {
+        return Check::problem(
+            "state",
+            i18n::message(
+                "doctor.state_unwritable",
+                &[("dir", &dir.display()), ("error", &e)],
+            ),
+            i18n::message("doctor.check_permissions", &[("dir", &dir.display())]),
+        );
+    }
+    let _ = fs::remove_file(&probe);
+    if let Some(file) = history::state_file("history")
+        && let Ok(text) = fs::read_to_string(&file)
+    {
+        let broken = text
+            .lines()
+            .filter(|line| {
+                line.split_once('\t')
+                    .is_none_or(|(time, _)| time.parse::<i64>().is_err())
+            })
+            .count();
+        if broken > 0 {
+            return Check::warning(
+                "state",
+                i18n::message(
+                    "doctor.broken_history",
+                    &[("count", &broken), ("file", &file.display())],
+                ),
+                i18n::text("doctor.delete_history"),
+            );
+        }
+    }
+    Check::ok(
+        "state",
+        i18n::message("doctor.state_ok", &[("dir", &dir.display())]),
+    )
+}
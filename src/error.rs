@@ -0,0 +1,100 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::i18n;
+
+/// Failures that wrappers may want to tell apart, each with its own exit status (see
+/// `exit_code`). Typed errors travel through `Box<dyn Error>` like any other and are
+/// recovered with `AppError::from`; everything else becomes `Other`.
+#[derive(Debug)]
+pub enum AppError {
+    /// The current directory is not inside a git repository.
+    NotARepository,
+    /// There is no `git` executable on the PATH.
+    GitNotFound,
+    /// A checkout was asked for in a bare repository (or inside `.git`).
+    NoWorkTree,
+    /// An invalid setting, with the file or variable it came from.
+    Config(String),
+    /// No branch matched what was asked for (`--checkout`, `--last`).
+    BranchNotFound(String),
+    /// git refused to switch branches because local changes would be overwritten.
+    CheckoutConflict(String),
+    /// Another git process holds the repository's `index.lock` (or `HEAD.lock`).
+    RepositoryLocked(String),
+    /// A `hooks.*` command failed.
+    HookFailed(String),
+    /// The picker needs a terminal, and there is none or it cannot be set up.
+    TerminalSetupFailed(String),
+    Other(String),
+}
+
+/// Exit status when `--print` ends without a selection, and for otherwise
+/// unclassified errors.
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit status for command-line usage errors.
+pub const EXIT_USAGE: i32 = 2;
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Other(_) => EXIT_FAILURE,
+            AppError::Config(_) => 3,
+            AppError::NotARepository => 4,
+            AppError::GitNotFound => 5,
+            AppError::BranchNotFound(_) => 6,
+            AppError::CheckoutConflict(_) => 7,
+            AppError::HookFailed(_) => 8,
+            AppError::TerminalSetupFailed(_) => 9,
+            AppError::NoWorkTree => 10,
+            AppError::RepositoryLocked(_) => 11,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotARepository => f.write_str(i18n::text("error.not_a_repository")),
+            AppError::GitNotFound => f.write_str(i18n::text("error.git_not_found")),
+            AppError::NoWorkTree => f.write_str(i18n::text("error.no_work_tree")),
+            AppError::Config(message)
+            | AppError::BranchNotFound(message)
+            | AppError::CheckoutConflict(message)
+            | AppError::RepositoryLocked(message)
+            | AppError::HookFailed(message)
+            | AppError::TerminalSetupFailed(message)
+            | AppError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Error for AppError {}
+
+impl From<Box<dyn Error>> for AppError {
+    fn from(error: Box<dyn Error>) -> Self {
+        match error.downcast::<AppError>() {
+            Ok(error) => *error,
+            Err(error) => AppError::Other(error.to_string()),
+        }
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(error: io::Error) -> Self {
+        AppError::Other(error.to_string())
+    }
+}
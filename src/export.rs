@@ -0,0 +1,113 @@
+//! `git-recent export`: every branch with the metadata git-recent computes for it, as
+//! JSON or CSV, for dashboards and scripts.
+
+use std::collections::HashMap;
+
+use crate::git::BranchInfo;
+use crate::json;
+use crate::refname;
+
+/// The output format, chosen with `--format`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub const NAMES: &[&str] = &["json", "csv"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(ExportFormat::Json),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// The columns, in order; also the CSV header and the JSON keys.
+const COLUMNS: &[&str] = &[
+    "name",
+    "sha",
+    "committer_date",
+    "subject",
+    "upstream",
+    "ahead",
+    "behind",
+    "current",
+    "checkouts",
+];
+
+/// `branches` in `format`, each with its number of checkouts from `checkouts`.
+pub fn render(
+    format: ExportFormat,
+    branches: &[BranchInfo],
+    checkouts: &HashMap<String, usize>,
+) -> String {
+    let rows = branches.iter().map(|b| {
+        let count = checkouts.get(&b.name).copied().unwrap_or(0);
+        (b, count)
+    });
+    match format {
+        ExportFormat::Json => {
+            let objects: Vec<String> = rows
+                .map(|(b, count)| {
+                    let values = [
+                        json::string(&refname::display(&b.name)),
+                        json::string(&b.sha),
+                        json::string(&b.committer_date),
+                        json::string(&b.subject),
+                        json::optional(b.upstream.as_deref(), json::string),
+                        json::optional(b.ahead, |n| n.to_string()),
+                        json::optional(b.behind, |n| n.to_string()),
+                        b.current.to_string(),
+                        count.to_string(),
+                    ];
+                    let members: Vec<String> = COLUMNS
+                        .iter()
+                        .zip(values)
+                        .map(|(key, value)| format!("\"{key}\": {value}"))
+                        .collect();
+                    format!("  {{{}}}", members.join(", "))
+                })
+                .collect();
+            if objects.is_empty() {
+                "[]\n".to_string()
+            } else {
+                format!("[\n{}\n]\n", objects.join(",\n"))
+            }
+        }
+        ExportFormat::Csv => {
+            let number = |n: Option<u32>| n.map_or(String::new(), |n| n.to_string());
+            let mut text = COLUMNS.join(",") + "\n";
+            for (b, count) in rows {
+                let fields = [
+                    csv_field(&b.name),
+                    csv_field(&b.sha),
+                    csv_field(&b.committer_date),
+                    csv_field(&b.subject),
+                    csv_field(b.upstream.as_deref().unwrap_or_default()),
+                    number(b.ahead),
+                    number(b.behind),
+                    b.current.to_string(),
+                    count.to_string(),
+                ];
+                text.push_str(&fields.join(","));
+                text.push('\n');
+            }
+            text
+        }
+    }
+}
+
+/// A CSV field as RFC 4180 writes it: quoted, with quotes doubled, when it holds a
+/// comma, a quote or a line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
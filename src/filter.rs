@@ -0,0 +1,155 @@
+use std::borrow::Cow;
+
+use crate::unicode;
+
+/// Whether filter queries tell upper from lower case.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Case {
+    /// Ignore case unless the query has an upper-case letter (the default).
+    #[default]
+    Smart,
+    Ignore,
+    Match,
+}
+
+impl Case {
+    pub const NAMES: &[&str] = &["smart", "ignore", "match"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "smart" => Some(Case::Smart),
+            "ignore" => Some(Case::Ignore),
+            "match" => Some(Case::Match),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Case::Smart => "smart",
+            Case::Ignore => "ignore",
+            Case::Match => "match",
+        }
+    }
+}
+
+/// A filter query, normalized once for matching many branch names against it.
+/// Query and names are compared in Unicode normalization form C, so an accented
+/// letter matches whether it was typed as one character or with a combining mark,
+/// and lower-cased as well when `case` says to ignore case.
+pub struct Query {
+    text: String,
+    fold: bool,
+}
+
+impl Query {
+    pub fn new(query: &str, case: Case) -> Self {
+        let text = unicode::nfc(query);
+        let fold = match case {
+            Case::Smart => !text.chars().any(char::is_uppercase),
+            Case::Ignore => true,
+            Case::Match => false,
+        };
+        let text = if fold {
+            text.to_lowercase()
+        } else {
+            text.into_owned()
+        };
+        Query { text, fold }
+    }
+
+    /// Whether the branch `name` contains the query.
+    pub fn matches(&self, name: &str) -> bool {
+        if self.text.is_empty() {
+            return true;
+        }
+        let name = unicode::nfc(name);
+        if self.fold {
+            lower(&name).contains(&self.text)
+        } else {
+            name.contains(&self.text)
+        }
+    }
+}
+
+/// Whether a branch name matches a filter query.
+pub fn matches(name: &str, query: &str, case: Case) -> bool {
+    Query::new(query, case).matches(name)
+}
+
+/// `text` in lower case, without copying text that already is.
+fn lower(text: &str) -> Cow<'_, str> {
+    if text.chars().any(char::is_uppercase) {
+        Cow::Owned(text.to_lowercase())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Up to three `candidates` closest to a mistyped `name`, best first, for
+/// "did you mean" hints. Candidates needing more than about a third of the name
+/// to be retyped are left out.
+pub fn suggestions<'a>(name: &str, candidates: &'a [String]) -> Vec<&'a str> {
+    let threshold = (name.chars().count() / 3).max(2);
+    let mut scored: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(|c| (edit_distance(name, c), c.as_str()))
+        .filter(|&(distance, _)| distance <= threshold)
+        .collect();
+    scored.sort_by_key(|&(distance, _)| distance);
+    scored.into_iter().take(3).map(|(_, c)| c).collect()
+}
+
+/// Levenshtein distance, counting an adjacent transposition ("fetaure") as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Three rows of the classic table: two back, previous and current.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut previous, current);
+    }
+    previous[b.len()]
+}
+
+/// Match `name` against a glob `pattern` where `*` matches any run of characters
+/// (including `/`) and `?` matches exactly one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was and how much of the name it has swallowed so far.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, swallowed)) => {
+                    p = star + 1;
+                    n = swallowed + 1;
+                    backtrack = Some((star, swallowed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
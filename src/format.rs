@@ -0,0 +1,193 @@
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::git::BranchInfo;
+
+/// Placeholders understood by `--format`.
+const FIELDS: &[&str] = &[
+    "name", "sha", "date", "subject", "upstream", "ahead", "behind", "current",
+];
+
+/// A parsed `--format` template such as `{name}\t{date:relative}\t{subject}`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field {
+        name: &'static str,
+        modifier: Option<String>,
+    },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct TemplateError(String);
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Template {
+    /// The text of a template without fields, such as `json`.
+    pub fn as_literal(&self) -> Option<&str> {
+        match self.parts.as_slice() {
+            [] => Some(""),
+            [Part::Literal(text)] => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Parse a template. `{{`/`}}` produce literal braces and `\t`, `\n`, `\\`
+    /// are unescaped so templates can be written without `$'...'` quoting.
+    pub fn parse(source: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return Err(TemplateError(format!("unclosed '{{{spec}'"))),
+                        }
+                    }
+                    let (name, modifier) = match spec.split_once(':') {
+                        Some((name, modifier)) => (name, Some(modifier.to_string())),
+                        None => (spec.as_str(), None),
+                    };
+                    let name = FIELDS.iter().copied().find(|f| *f == name).ok_or_else(|| {
+                        TemplateError(format!(
+                            "unknown field '{name}' (expected one of: {})",
+                            FIELDS.join(", ")
+                        ))
+                    })?;
+                    validate_modifier(name, modifier.as_deref())?;
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field { name, modifier });
+                }
+                '}' => return Err(TemplateError("unmatched '}' (use '}}')".to_string())),
+                '\\' => match chars.peek() {
+                    Some('t') => {
+                        chars.next();
+                        literal.push('\t');
+                    }
+                    Some('n') => {
+                        chars.next();
+                        literal.push('\n');
+                    }
+                    Some('\\') => {
+                        chars.next();
+                        literal.push('\\');
+                    }
+                    _ => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// Expand the template for one branch. `now` is the current Unix time, for relative dates.
+    pub fn render(&self, info: &BranchInfo, now: i64) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Field { name, modifier } => {
+                    out.push_str(&field(info, name, modifier.as_deref(), now))
+                }
+            }
+        }
+        out
+    }
+}
+
+fn validate_modifier(name: &str, modifier: Option<&str>) -> Result<(), TemplateError> {
+    let allowed: &[&str] = match name {
+        "date" => &["iso", "short", "relative", "unix"],
+        "sha" => &["short"],
+        _ => &[],
+    };
+    match modifier {
+        Some(m) if !allowed.contains(&m) => Err(TemplateError(if allowed.is_empty() {
+            format!("field '{name}' takes no modifier")
+        } else {
+            format!(
+                "unknown modifier '{m}' for '{name}' (expected one of: {})",
+                allowed.join(", ")
+            )
+        })),
+        _ => Ok(()),
+    }
+}
+
+fn field(info: &BranchInfo, name: &str, modifier: Option<&str>, now: i64) -> String {
+    let count = |n: Option<u32>| n.map_or(String::new(), |n| n.to_string());
+    match (name, modifier) {
+        ("name", _) => info.name.clone(),
+        ("sha", Some("short")) => info.sha.chars().take(7).collect(),
+        ("sha", _) => info.sha.clone(),
+        ("date", Some("short")) => info.committer_date.chars().take(10).collect(),
+        ("date", Some("relative")) => relative_time(now - info.committer_timestamp),
+        ("date", Some("unix")) => info.committer_timestamp.to_string(),
+        ("date", _) => info.committer_date.clone(),
+        ("subject", _) => info.subject.clone(),
+        ("upstream", _) => info.upstream.clone().unwrap_or_default(),
+        ("ahead", _) => count(info.ahead),
+        ("behind", _) => count(info.behind),
+        ("current", _) => if info.current { "*" } else { "" }.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Current Unix time in seconds.
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Describe an age in seconds the way `git log --date=relative` does ("3 days ago").
+pub fn relative_time(seconds: i64) -> String {
+    if seconds < 0 {
+        return "in the future".to_string();
+    }
+    let units = [
+        (60 * 60 * 24 * 365, "year"),
+        (60 * 60 * 24 * 30, "month"),
+        (60 * 60 * 24 * 7, "week"),
+        (60 * 60 * 24, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    for (size, unit) in units {
+        let n = seconds / size;
+        if n >= 1 {
+            let plural = if n == 1 { "" } else { "s" };
+            return format!("{n} {unit}{plural} ago");
+        }
+    }
+    "just now".to_string()
+}
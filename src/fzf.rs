@@ -0,0 +1,79 @@
+//! `--picker fzf`: choosing the branch with fzf instead of the built-in picker, for
+//! people who already have its keys (and `FZF_DEFAULT_OPTS`) the way they like them.
+
+use std::error::Error;
+use std::io;
+use std::process::Command;
+
+use crate::i18n;
+use crate::refname;
+use crate::trace;
+
+/// Which picker chooses the branch.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Picker {
+    /// git-recent's own picker (the default).
+    #[default]
+    Builtin,
+    Fzf,
+}
+
+impl Picker {
+    pub const NAMES: &[&str] = &["builtin", "fzf"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "builtin" => Some(Picker::Builtin),
+            "fzf" => Some(Picker::Fzf),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Picker::Builtin => "builtin",
+            Picker::Fzf => "fzf",
+        }
+    }
+}
+
+/// What fzf shows next to the list for the branch under the cursor; fzf fills in
+/// `{}`, quoted.
+const PREVIEW: &str = "git log --oneline --decorate --color=always -n 50 {} --";
+
+/// Let the user choose one of `branches` in fzf, starting from `query`. Returns
+/// `None` when fzf is left without a choice.
+pub fn pick(branches: &[String], query: Option<&str>) -> Result<Option<String>, Box<dyn Error>> {
+    let mut input = String::new();
+    for branch in branches {
+        input.push_str(branch);
+        input.push('\n');
+    }
+    let mut command = Command::new("fzf");
+    command.args([
+        "--no-multi",
+        "--prompt",
+        "git-recent> ",
+        "--preview",
+        PREVIEW,
+    ]);
+    if let Some(query) = query {
+        command.arg("--query").arg(refname::to_os(query));
+    }
+    let output = match trace::output_with_input(&mut command, &refname::encode(&input)) {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(i18n::text("fzf.not_found").into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    match output.status.code() {
+        Some(0) => {}
+        // No match (1) or Esc and Ctrl-C (130): nothing was chosen.
+        Some(1 | 130) => return Ok(None),
+        _ => return Err(i18n::message("fzf.failed", &[("status", &output.status)]).into()),
+    }
+    let chosen = refname::decode(&output.stdout);
+    let chosen = chosen.trim_end_matches('\n');
+    Ok((!chosen.is_empty()).then(|| chosen.to_string()))
+}
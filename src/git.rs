@@ -0,0 +1,1421 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::checkout_hook;
+use crate::error::AppError;
+use crate::filter::{self, Case};
+use crate::format;
+use crate::history::History;
+use crate::i18n;
+use crate::jj;
+use crate::pager;
+use crate::refname;
+use crate::spinner::with_spinner;
+use crate::trace;
+
+/// Default number of branches loaded when no limit is given.
+pub const MAX_BRANCHES: usize = 200;
+
+/// Set by `--dry-run`: mutating commands are recorded in `DRY_RUN_LOG` instead of run.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+static DRY_RUN_LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Ahead/behind counts and merged status worked out so far this session, keyed by the
+/// commits they were computed from: a ref that moved simply misses.
+#[derive(Default)]
+struct StatusMemo {
+    /// (branch tip, upstream tip) → (ahead, behind).
+    counts: HashMap<(String, String), (u32, u32)>,
+    /// (branch tip, target commit) → merged.
+    merged: HashMap<(String, String), bool>,
+}
+
+static STATUS_MEMO: LazyLock<Mutex<StatusMemo>> = LazyLock::new(Mutex::default);
+
+/// Field separator for `for-each-ref` formats; NUL cannot appear in ref names or dates.
+const FIELD_SEP: &str = "%00";
+
+/// Initial ordering of the branch list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// Most recent commit first (the default).
+    #[default]
+    CommitterDate,
+    AuthorDate,
+    /// Alphabetical by branch name.
+    Name,
+    /// Most recently checked out first, according to the HEAD reflog.
+    Checkout,
+}
+
+impl Sort {
+    pub const NAMES: &[&str] = &["committerdate", "authordate", "name", "checkout"];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "committerdate" => Some(Sort::CommitterDate),
+            "authordate" => Some(Sort::AuthorDate),
+            "name" => Some(Sort::Name),
+            "checkout" => Some(Sort::Checkout),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sort::CommitterDate => "committerdate",
+            Sort::AuthorDate => "authordate",
+            Sort::Name => "name",
+            Sort::Checkout => "checkout",
+        }
+    }
+
+    /// The `git for-each-ref --sort` key for refs in `scope`. Checkout order starts from
+    /// committer date and is then rearranged using the reflog. Tags go by the date they
+    /// were tagged instead, as annotated ones have no committer of their own.
+    fn git_key(self, scope: Scope) -> &'static str {
+        match self {
+            Sort::CommitterDate | Sort::Checkout if scope == Scope::Tags => "-creatordate",
+            Sort::CommitterDate | Sort::Checkout => "-committerdate",
+            Sort::AuthorDate => "-authordate",
+            Sort::Name => "refname",
+        }
+    }
+}
+
+/// Which branches to list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scope {
+    #[default]
+    Local,
+    /// Remote-tracking branches, named like `origin/feature`.
+    Remote,
+    /// Local and remote-tracking branches; remote ones are named like `remotes/origin/feature`.
+    All,
+    /// Tags instead of branches (`git-recent tags`), newest first by the date they were
+    /// tagged; checking one out detaches HEAD.
+    Tags,
+}
+
+impl Scope {
+    /// The scope after this one when cycling through them in the picker.
+    pub fn next(self) -> Self {
+        match self {
+            Scope::Local => Scope::Remote,
+            Scope::Remote => Scope::All,
+            Scope::All => Scope::Local,
+            // Tags are a picker of their own.
+            Scope::Tags => Scope::Tags,
+        }
+    }
+
+    /// Short label for the picker header, empty for the default scope.
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::Local | Scope::Tags => "",
+            Scope::Remote => i18n::text("scope.remote"),
+            Scope::All => i18n::text("scope.all"),
+        }
+    }
+
+    /// Whether `branch`, as listed in this scope, is a remote-tracking branch.
+    pub fn is_remote(self, branch: &str) -> bool {
+        match self {
+            Scope::Local | Scope::Tags => false,
+            Scope::Remote => true,
+            Scope::All => branch.starts_with("remotes/"),
+        }
+    }
+
+    /// The full ref name of `branch` as listed in this scope.
+    pub fn refname(self, branch: &str) -> String {
+        match self {
+            Scope::Remote => format!("refs/remotes/{branch}"),
+            Scope::Tags => format!("refs/tags/{branch}"),
+            Scope::All if branch.starts_with("remotes/") => format!("refs/{branch}"),
+            Scope::Local | Scope::All => format!("refs/heads/{branch}"),
+        }
+    }
+
+    /// The local branch name to check out for `branch`: remote-tracking branches map to
+    /// their name without the remote, which lets `git checkout` create a tracking branch.
+    /// Tags keep their full ref name, which `checkout` detaches at.
+    pub fn checkout_name(self, branch: &str) -> String {
+        if self == Scope::Tags {
+            return self.refname(branch);
+        }
+        if !self.is_remote(branch) {
+            return branch.to_string();
+        }
+        let name = branch.strip_prefix("remotes/").unwrap_or(branch);
+        name.split_once('/')
+            .map_or(name, |(_remote, rest)| rest)
+            .to_string()
+    }
+}
+
+/// What to load and in which order; kept by the picker so a reload matches startup.
+#[derive(Clone, Debug, Default)]
+pub struct LoadOptions {
+    /// Maximum number of branches, `None` for all of them.
+    pub limit: Option<usize>,
+    pub sort: Sort,
+    pub scope: Scope,
+    /// Only branches containing this commit (`git branch --contains`).
+    pub contains: Option<String>,
+    /// Only branches merged / not merged into these refs.
+    pub merged: Option<String>,
+    pub no_merged: Option<String>,
+    /// Only branches whose last commit is at least this many days old.
+    pub stale_days: Option<u64>,
+    /// Glob patterns (`*`, `?`) of branch names to leave out.
+    pub exclude: Vec<String>,
+    /// Only branches matching this filter query, applied before `limit`. The picker
+    /// leaves it unset and filters as you type.
+    pub query: Option<String>,
+    /// Whether `query` and the picker's filter tell upper from lower case.
+    pub case: Case,
+    /// Also count commits ahead of and behind each upstream, which takes a while
+    /// with many branches.
+    pub track: bool,
+    /// Export jj's bookmarks to git first, in a colocated jj repository (`jj`).
+    pub jj: bool,
+}
+
+/// Every branch in `options.sort` order, ignoring `options.limit`: just the names,
+/// so even tens of thousands of branches stay small enough to filter through. The
+/// picker shows the first `options.limit` of them until a filter is typed.
+/// Returns an error if the git command fails.
+pub fn load_recent(options: &LoadOptions) -> Result<(String, Vec<String>), Box<dyn Error>> {
+    let mut names = Vec::new();
+    let current_branch = for_each_branch(options, false, |info| names.push(info.name))?;
+    if options.sort == Sort::Checkout {
+        let order = checkout_order()?;
+        sort_by_checkout(&mut names, &order, |name| name);
+    }
+    Ok((current_branch, names))
+}
+
+/// Up to `options.limit` branches in `options.sort` order, with each branch's
+/// metadata, all from one `for-each-ref` call. Only the branches returned are kept in
+/// memory, except with `Sort::Checkout`, whose order is known only at the end.
+pub fn load_branches(options: &LoadOptions) -> Result<(String, Vec<BranchInfo>), Box<dyn Error>> {
+    let limit = options.limit.unwrap_or(usize::MAX);
+    let keep = if options.sort == Sort::Checkout {
+        usize::MAX
+    } else {
+        limit
+    };
+    let mut branches = Vec::new();
+    let current_branch = for_each_branch(options, true, |info| {
+        if branches.len() < keep {
+            branches.push(info);
+        }
+    })?;
+    if options.sort == Sort::Checkout {
+        let order = checkout_order()?;
+        sort_by_checkout(&mut branches, &order, |info| &info.name);
+    }
+    branches.truncate(limit);
+    Ok((current_branch, branches))
+}
+
+/// Stream the branches `options` select to `each`, in git's committer date order,
+/// as `for-each-ref` prints them, and return the current branch. The listing is
+/// parsed line by line and never held in memory as a whole. Without `metadata`, only
+/// the names are asked for (the other fields are left empty), which halves the time
+/// git takes with many branches.
+fn for_each_branch(
+    options: &LoadOptions,
+    metadata: bool,
+    mut each: impl FnMut(BranchInfo) + Send,
+) -> Result<String, Box<dyn Error>> {
+    if options.jj {
+        jj::export_bookmarks();
+    }
+    let sort = format!("--sort={}", options.sort.git_key(options.scope));
+    // A tag's date is the tagger's, or for lightweight tags the commit's.
+    let date_atom = match options.scope {
+        Scope::Tags => "creatordate",
+        _ => "committerdate",
+    };
+    // `%(HEAD)` is "*" for the checked-out branch; `%(symref)` is set for symbolic
+    // refs such as origin/HEAD, which are not branches of their own.
+    let field = |wanted: bool, atom| if wanted { atom } else { "" };
+    let format = [
+        "%(HEAD)",
+        "%(refname)",
+        "%(symref)",
+        field(metadata, "%(objectname)"),
+        field(metadata, &format!("%({date_atom}:iso-strict)")),
+        &format!("%({date_atom}:unix)"),
+        field(metadata, "%(upstream:short)"),
+        field(metadata && options.track, "%(upstream:track,nobracket)"),
+        field(metadata, "%(contents:subject)"),
+    ]
+    .join(FIELD_SEP);
+    let format = format!("--format={format}");
+    let mut args = vec!["for-each-ref", sort.as_str(), format.as_str()];
+    if let Some(commit) = &options.contains {
+        args.extend(["--contains", commit]);
+    }
+    if let Some(target) = &options.merged {
+        args.extend(["--merged", target]);
+    }
+    if let Some(target) = &options.no_merged {
+        args.extend(["--no-merged", target]);
+    }
+    match options.scope {
+        Scope::Local => args.push("refs/heads"),
+        Scope::Remote => args.push("refs/remotes"),
+        Scope::All => args.extend(["refs/heads", "refs/remotes"]),
+        Scope::Tags => args.push("refs/tags"),
+    }
+    let os_args: Vec<_> = args.iter().map(|arg| refname::to_os(arg)).collect();
+    // Any number of days too large to count in seconds reaches before every commit.
+    let cutoff = options.stale_days.map(|days| {
+        let seconds = days
+            .checked_mul(24 * 60 * 60)
+            .and_then(|seconds| i64::try_from(seconds).ok())
+            .unwrap_or(i64::MAX);
+        format::now().saturating_sub(seconds)
+    });
+
+    let query = options
+        .query
+        .as_ref()
+        .map(|query| filter::Query::new(query, options.case));
+    let mut current_branch = None;
+    let (mut listed, mut excluded, mut fresh) = (0, 0, 0);
+    let output = with_spinner(i18n::text("progress.loading"), || {
+        trace::output_lines(command().args(&os_args), |line| {
+            let Some(info) = parse_branch(&refname::decode(line), options) else {
+                return;
+            };
+            if info.current {
+                current_branch = Some(info.name.clone());
+            }
+            listed += 1;
+            if options
+                .exclude
+                .iter()
+                .any(|p| filter::glob_match(p, &info.name))
+            {
+                excluded += 1;
+            } else if cutoff.is_some_and(|cutoff| info.committer_timestamp > cutoff) {
+                fresh += 1;
+            } else if query.as_ref().is_none_or(|query| query.matches(&info.name)) {
+                each(info);
+            }
+        })
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = match stderr.lines().next() {
+            Some(line) => line.to_string(),
+            None => output.status.to_string(),
+        };
+        return Err(git_failed("for-each-ref", error).into());
+    }
+    if excluded > 0 {
+        trace::note(format_args!(
+            "{excluded} of {listed} branches hidden by exclude"
+        ));
+    }
+    if let Some(days) = options.stale_days {
+        trace::note(format_args!(
+            "{fresh} branches with commits in the last {days} days left out by --stale"
+        ));
+    }
+
+    Ok(match current_branch {
+        Some(name) => name,
+        // Remote and tag listings carry no current-branch marker.
+        None if matches!(options.scope, Scope::Remote | Scope::Tags) => current_branch_name()?,
+        None => String::new(),
+    })
+}
+
+/// One line of the `for-each-ref` listing in `for_each_branch`; `None` for symbolic
+/// refs and lines that do not have every field.
+fn parse_branch(line: &str, options: &LoadOptions) -> Option<BranchInfo> {
+    let fields: Vec<&str> = line.split('\0').collect();
+    let [
+        head,
+        full_name,
+        symref,
+        sha,
+        date,
+        timestamp,
+        upstream,
+        track,
+        subject,
+    ] = fields[..]
+    else {
+        return None;
+    };
+    if !symref.is_empty() {
+        return None;
+    }
+    // Named as `git branch` names them: `main`, `origin/main`, or with both local
+    // and remote branches listed, `remotes/origin/main`.
+    let name = match (options.scope, full_name.strip_prefix("refs/heads/")) {
+        (_, Some(local)) => local,
+        (Scope::Remote, None) => full_name.strip_prefix("refs/remotes/").unwrap_or(full_name),
+        (Scope::Tags, None) => full_name.strip_prefix("refs/tags/").unwrap_or(full_name),
+        (_, None) => full_name.strip_prefix("refs/").unwrap_or(full_name),
+    };
+    let upstream = (!upstream.is_empty()).then(|| upstream.to_string());
+    let (ahead, behind) = match (&upstream, options.track, parse_track(track)) {
+        (Some(_), true, Some(counts)) => (Some(counts.0), Some(counts.1)),
+        _ => (None, None),
+    };
+    Some(BranchInfo {
+        name: name.to_string(),
+        sha: sha.to_string(),
+        committer_date: date.to_string(),
+        committer_timestamp: timestamp.parse().unwrap_or(0),
+        subject: subject.to_string(),
+        upstream,
+        ahead,
+        behind,
+        current: head == "*",
+    })
+}
+
+/// Branch names in the order `Sort::Checkout` puts them: checkouts recorded by
+/// git-recent itself first, then the reflog's.
+fn checkout_order() -> Result<Vec<String>, Box<dyn Error>> {
+    let mut order = History::open()
+        .map(|history| history.recent_first())
+        .unwrap_or_default();
+    let mut known: HashSet<String> = order.iter().cloned().collect();
+    for name in checkout_history()? {
+        if known.insert(name.clone()) {
+            order.push(name);
+        }
+    }
+    Ok(order)
+}
+
+/// The repository's main line: what `origin/HEAD` points at, or else a local `main`
+/// or `master`.
+pub fn default_branch() -> Result<String, Box<dyn Error>> {
+    let output = trace::output(command().args([
+        "symbolic-ref",
+        "--quiet",
+        "--short",
+        "refs/remotes/origin/HEAD",
+    ]))?;
+    if output.status.success() {
+        let branch = refname::decode(&output.stdout).trim().to_string();
+        trace::note(format_args!("default branch from origin/HEAD: '{branch}'"));
+        return Ok(branch);
+    }
+    for name in ["main", "master"] {
+        if branch_exists(name)? {
+            trace::note(format_args!("no origin/HEAD; default branch is '{name}'"));
+            return Ok(name.to_string());
+        }
+    }
+    Err(i18n::text("git.no_default_branch").into())
+}
+
+fn branch_exists(name: &str) -> Result<bool, Box<dyn Error>> {
+    #[cfg(feature = "native-refs")]
+    if let Some(exists) = crate::refs::branch_exists(name) {
+        return Ok(exists);
+    }
+    Ok(trace::output(
+        command()
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(refname::to_os(&format!("refs/heads/{name}"))),
+    )?
+    .status
+    .success())
+}
+
+/// Number of paths with uncommitted changes (staged, unstaged or untracked), as listed
+/// by `git status --porcelain`.
+pub fn uncommitted_changes() -> Result<usize, Box<dyn Error>> {
+    let output = trace::output(command().args(["status", "--porcelain"]))?;
+    if !output.status.success() {
+        return Err(git_failed("status", output.status).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).lines().count())
+}
+
+/// The checked-out branch name (git branch --show-current); empty when HEAD is detached.
+pub fn current_branch_name() -> Result<String, Box<dyn Error>> {
+    #[cfg(feature = "native-refs")]
+    if let Some(name) = crate::refs::current_branch() {
+        return Ok(name);
+    }
+    let output = trace::output(command().args(["branch", "--show-current"]))?;
+    if !output.status.success() {
+        return Err(git_failed("branch", output.status).into());
+    }
+    Ok(refname::decode(&output.stdout).trim().to_string())
+}
+
+/// The abbreviated commit HEAD points at when it is detached, `None` when a branch is
+/// checked out.
+pub fn detached_head() -> Result<Option<String>, Box<dyn Error>> {
+    let on_branch = trace::status(
+        command()
+            .args(["symbolic-ref", "--quiet", "HEAD"])
+            .stdout(Stdio::null()),
+    )?
+    .success();
+    if on_branch {
+        return Ok(None);
+    }
+    let output = trace::output(command().args(["rev-parse", "--short", "HEAD"]))?;
+    if !output.status.success() {
+        return Err(git_failed("rev-parse", output.status).into());
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// The root directory of the current worktree; `None` outside one.
+pub fn top_level() -> Option<PathBuf> {
+    let output = trace::output(command().args(["rev-parse", "--show-toplevel"])).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let root = refname::decode(&output.stdout);
+    Some(PathBuf::from(refname::to_os(root.trim_end_matches('\n'))))
+}
+
+/// The repository all git commands are pointed at once `pin_repository` ran.
+struct Repository {
+    git_dir: PathBuf,
+    work_tree: Option<PathBuf>,
+}
+
+static REPOSITORY: OnceLock<Repository> = OnceLock::new();
+
+/// A `git` command. After `pin_repository` it carries explicit `--git-dir` and
+/// `--work-tree` options, so every command works on the same repository whatever
+/// `GIT_DIR`, `GIT_WORK_TREE` and the current directory say.
+pub fn command() -> Command {
+    let mut command = Command::new("git");
+    if let Some(repository) = REPOSITORY.get() {
+        command.arg(path_option("--git-dir=", &repository.git_dir));
+        if let Some(work_tree) = &repository.work_tree {
+            command.arg(path_option("--work-tree=", work_tree));
+        }
+    }
+    command
+}
+
+/// The git directory `pin_repository` resolved, once it ran. In a linked worktree
+/// this is the worktree's own directory, holding its `HEAD`.
+pub fn pinned_git_dir() -> Option<&'static Path> {
+    REPOSITORY
+        .get()
+        .map(|repository| repository.git_dir.as_path())
+}
+
+fn path_option(option: &str, path: &Path) -> OsString {
+    let mut arg = OsString::from(option);
+    arg.push(path);
+    arg
+}
+
+/// Whether `arg` is one of the options `command` adds, which traces leave out.
+pub fn is_repository_option(arg: &OsStr) -> bool {
+    let arg = arg.as_encoded_bytes();
+    arg.starts_with(b"--git-dir=") || arg.starts_with(b"--work-tree=")
+}
+
+/// Resolve the repository git-recent was started in (found at `location`), with
+/// absolute paths, and point every later `command` at it.
+pub fn pin_repository(location: Location) -> Result<(), Box<dyn Error>> {
+    let output = trace::output(command().args(["rev-parse", "--absolute-git-dir"]))?;
+    if !output.status.success() {
+        return Err(git_failed("rev-parse", output.status).into());
+    }
+    let git_dir = refname::decode(&output.stdout);
+    let git_dir = PathBuf::from(refname::to_os(git_dir.trim_end_matches('\n')));
+    let work_tree = match location {
+        Location::WorkTree => top_level(),
+        Location::NoWorkTree => None,
+    };
+    trace::note(format_args!(
+        "repository: git dir {}, work tree {}",
+        git_dir.display(),
+        work_tree
+            .as_deref()
+            .map_or_else(|| "none".to_string(), |p| p.display().to_string())
+    ));
+    // Only the first repository counts; later calls change nothing.
+    let _ = REPOSITORY.set(Repository { git_dir, work_tree });
+    Ok(())
+}
+
+/// Where in a repository git-recent was started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Location {
+    /// A worktree: the main one or one added with `git worktree add`, each with its
+    /// own HEAD.
+    WorkTree,
+    /// A bare repository, or inside a `.git` directory: branches but no checkout.
+    NoWorkTree,
+}
+
+/// Make sure `git` can be run and the current directory is inside a repository, and
+/// find out whether branches can be checked out there.
+pub fn check_repository() -> Result<Location, AppError> {
+    let output = trace::output(
+        command()
+            .args(["rev-parse", "--is-inside-work-tree"])
+            .stderr(Stdio::null()),
+    )
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => AppError::GitNotFound,
+        _ => AppError::Other(i18n::message("git.cannot_run", &[("error", &e)])),
+    })?;
+    if !output.status.success() {
+        return Err(AppError::NotARepository);
+    }
+    Ok(match output.stdout.trim_ascii() {
+        b"true" => Location::WorkTree,
+        // Outside the work tree that GIT_WORK_TREE names, but it is there all the same.
+        _ if top_level().is_some() => Location::WorkTree,
+        _ => Location::NoWorkTree,
+    })
+}
+
+/// The branch checked out before the current one: the most recent reflog entry that
+/// still names an existing branch other than the current one. With HEAD detached, that
+/// is the branch the last checkout moved away from, even though the checkout named
+/// another branch (`git checkout --detach main`).
+pub fn previous_branch() -> Result<Option<String>, Box<dyn Error>> {
+    let current = current_branch_name()?;
+    if current.is_empty()
+        && let Some(from) = head_reflog()?
+            .iter()
+            .find_map(|line| line.strip_prefix("checkout: moving from "))
+            .and_then(|moves| moves.rsplit_once(" to "))
+            .map(|(from, _)| from.to_string())
+        && branch_exists(&from)?
+    {
+        trace::note(format_args!(
+            "HEAD is detached; previous branch is '{from}', where the last checkout left"
+        ));
+        return Ok(Some(from));
+    }
+    for name in checkout_history()? {
+        if name == current {
+            continue;
+        }
+        if branch_exists(&name)? {
+            trace::note(format_args!(
+                "previous branch from the HEAD reflog: '{name}'"
+            ));
+            return Ok(Some(name));
+        }
+        trace::note(format_args!(
+            "skipping '{name}' from the HEAD reflog: no such branch"
+        ));
+    }
+    Ok(None)
+}
+
+/// Branches named in the HEAD reflog's "checkout: moving from A to B" entries,
+/// most recent first, without duplicates.
+fn checkout_history() -> Result<Vec<String>, Box<dyn Error>> {
+    let mut seen = Vec::new();
+    let mut known = HashSet::new();
+    for line in head_reflog()? {
+        let Some(moves) = line.strip_prefix("checkout: moving from ") else {
+            continue;
+        };
+        if let Some((from, to)) = moves.rsplit_once(" to ") {
+            // The newest entry's origin was checked out just before it.
+            for name in [to, from] {
+                if known.insert(name.to_string()) {
+                    seen.push(name.to_string());
+                }
+            }
+        }
+    }
+    Ok(seen)
+}
+
+/// How often each branch was checked out, counting the HEAD reflog's
+/// "checkout: moving from A to B" entries (so only as far back as the reflog goes).
+pub fn checkout_counts() -> Result<HashMap<String, usize>, Box<dyn Error>> {
+    let mut counts = HashMap::new();
+    for line in head_reflog()? {
+        let Some(moves) = line.strip_prefix("checkout: moving from ") else {
+            continue;
+        };
+        if let Some((_, to)) = moves.rsplit_once(" to ") {
+            *counts.entry(to.to_string()).or_insert(0) += 1;
+        }
+    }
+    Ok(counts)
+}
+
+/// The HEAD reflog's messages (`%gs`), newest first.
+fn head_reflog() -> Result<Vec<String>, Box<dyn Error>> {
+    #[cfg(feature = "native-refs")]
+    if let Some(messages) = crate::refs::head_reflog() {
+        return Ok(messages);
+    }
+    let output = trace::output(command().args(["reflog", "--format=%gs"]))?;
+    if !output.status.success() {
+        // A fresh repository has no reflog yet; that is simply no history.
+        return Ok(Vec::new());
+    }
+    Ok(refname::decode(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+/// Stable-reorder `branches` so those in `history` come first, in history order.
+fn sort_by_checkout<T>(branches: &mut [T], history: &[String], name: impl Fn(&T) -> &str) {
+    let position: HashMap<&str, usize> = history
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, h)| (h.as_str(), i))
+        .collect();
+    branches.sort_by_key(|b| position.get(name(b)).copied().unwrap_or(usize::MAX));
+}
+
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// In a dry run, record `command` (a shell command line) and return true; the caller
+/// then skips running it and carries on as if it had succeeded.
+pub fn skip_for_dry_run(command: String) -> bool {
+    if !is_dry_run() {
+        return false;
+    }
+    DRY_RUN_LOG
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(command);
+    true
+}
+
+/// The commands a dry run skipped so far, in order, for printing as a script.
+pub fn take_dry_run_log() -> Vec<String> {
+    std::mem::take(&mut *DRY_RUN_LOG.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// `git` with `args` as a shell command line.
+fn git_command_line(args: &[&str]) -> String {
+    let mut line = "git".to_string();
+    for arg in args {
+        line.push(' ');
+        line.push_str(&shell_quote(arg));
+    }
+    line
+}
+
+/// Quote `word` for POSIX shells, leaving plain words (most branch names) as they are.
+pub fn shell_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Build a shell command for `branch` from a template (`--exec`, `annotate`): every
+/// `{}` becomes the branch name, quoted for where it stands (bare, or inside single or
+/// double quotes), and the name is appended as the last argument when there is no
+/// placeholder.
+pub fn branch_command(template: &str, branch: &str) -> String {
+    if !template.contains("{}") {
+        return format!("{template} {}", shell_quote(branch));
+    }
+    let mut command = String::new();
+    // The quote the template is inside at this point, if any.
+    let mut quote = None;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'}') => {
+                chars.next();
+                match quote {
+                    None => command.push_str(&shell_quote(branch)),
+                    Some('\'') => command.push_str(&branch.replace('\'', "'\\''")),
+                    Some(_) => {
+                        for c in branch.chars() {
+                            if matches!(c, '"' | '\\' | '$' | '`') {
+                                command.push('\\');
+                            }
+                            command.push(c);
+                        }
+                    }
+                }
+                continue;
+            }
+            // A backslash escapes the next character, except inside single quotes.
+            '\\' if quote != Some('\'') => {
+                command.push(c);
+                command.extend(chars.next());
+                continue;
+            }
+            '\'' | '"' if quote.is_none() => quote = Some(c),
+            c if quote == Some(c) => quote = None,
+            _ => {}
+        }
+        command.push(c);
+    }
+    command
+}
+
+/// Run a mutating `git` command behind a spinner, returning git's error text on failure.
+pub fn run_git(label: &str, args: &[&str]) -> Result<(), String> {
+    if skip_for_dry_run(git_command_line(args)) {
+        return Ok(());
+    }
+    let args: Vec<_> = args.iter().map(|arg| refname::to_os(arg)).collect();
+    let output =
+        with_spinner(label, || trace::output(command().args(&args))).map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(stderr
+            .lines()
+            .next()
+            .unwrap_or("git command failed")
+            .to_string())
+    }
+}
+
+/// The error for `git <command>` failing with `error` (its exit status, or what it said).
+pub fn git_failed(command: &str, error: impl fmt::Display) -> String {
+    i18n::message("git.failed", &[("command", &command), ("error", &error)])
+}
+
+/// Whether `branch` is merged the way `git branch -d` asks for: into its upstream,
+/// or into HEAD when it has none. Decided from the commits rather than from git's
+/// message, which follows the user's locale.
+pub fn is_merged(branch: &str) -> Result<bool, Box<dyn Error>> {
+    let full_name = format!("refs/heads/{branch}");
+    let output = trace::output(
+        command()
+            .args(["for-each-ref", "--format=%(upstream)"])
+            .arg(refname::to_os(&full_name)),
+    )?;
+    if !output.status.success() {
+        return Err(git_failed("for-each-ref", output.status).into());
+    }
+    let upstream = refname::decode(&output.stdout).trim().to_string();
+    let base = if upstream.is_empty() {
+        "HEAD"
+    } else {
+        &upstream
+    };
+    let status = trace::status(
+        command()
+            .args(["merge-base", "--is-ancestor"])
+            .arg(refname::to_os(&full_name))
+            .arg(refname::to_os(base)),
+    )?;
+    Ok(status.success())
+}
+
+/// Push `branch` to the remote it tracks (`origin` if none), setting it as upstream.
+pub fn push(branch: &str) -> Result<(), String> {
+    let remote = trace::output(
+        command()
+            .args(["config", "--get"])
+            .arg(refname::to_os(&format!("branch.{branch}.remote"))),
+    )
+    .ok()
+    .filter(|output| output.status.success())
+    .map(|output| refname::decode(&output.stdout).trim().to_string())
+    .unwrap_or_else(|| "origin".to_string());
+    run_git(
+        &i18n::message("progress.pushing", &[("branch", &refname::display(branch))]),
+        &["push", "--set-upstream", &remote, branch],
+    )
+}
+
+/// Run `git diff` on `range` in git's pager, on the UI's terminal.
+pub fn diff(range: &str) -> Result<(), Box<dyn Error>> {
+    pager::page_git(&["diff", range])
+}
+
+/// Run `git log` on `branch` in git's pager, on the UI's terminal.
+pub fn log(branch: &str) -> Result<(), Box<dyn Error>> {
+    pager::page_git(&["log", branch, "--"])
+}
+
+/// The first `max_lines` lines of `git diff` on `range`, colored, for the picker's
+/// preview pane. Control characters other than tab and escape are dropped, so a line
+/// cannot move the cursor around the frame.
+pub fn diff_preview(range: &str, max_lines: usize) -> Result<Vec<String>, String> {
+    let output = trace::output_head(
+        command()
+            .args(["diff", "--color=always"])
+            .arg(refname::to_os(range)),
+        max_lines,
+    )
+    .map_err(|e| git_failed("diff", e))?;
+    // Killed once it had enough lines, which leaves it without an exit code.
+    if output.status.code().is_some_and(|code| code != 0) {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(refname::decode(&output.stdout)
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter(|&c| !c.is_control() || c == '\t' || c == '\x1b')
+                .collect()
+        })
+        .collect())
+}
+
+/// Update all remotes with `git fetch --all --prune`. Git draws its own progress on
+/// stderr (unless `quiet`), so no spinner is shown; stdout is kept clean for list output.
+pub fn fetch(quiet: bool) -> Result<(), Box<dyn Error>> {
+    let mut args = vec!["fetch", "--all", "--prune"];
+    if quiet {
+        args.push("--quiet");
+    }
+    if skip_for_dry_run(git_command_line(&args)) {
+        return Ok(());
+    }
+    let status = trace::status(command().args(&args).stdout(Stdio::null()))?;
+    if !status.success() {
+        return Err(git_failed("fetch", status).into());
+    }
+    Ok(())
+}
+
+/// The command that changes branches: `git checkout` (the default) or `git switch`,
+/// with extra arguments placed before the branch name; or in a colocated jj
+/// repository `jj new`.
+#[derive(Clone, Debug, Default)]
+pub struct SwitchCommand {
+    pub use_switch: bool,
+    pub args: Vec<String>,
+    /// Switch with `jj new` (`jj`); `None` means if the repository is colocated.
+    pub jj: Option<bool>,
+}
+
+impl SwitchCommand {
+    fn args<'a>(&'a self, branch: &'a str) -> Vec<&'a str> {
+        let mut args = vec![if self.use_switch {
+            "switch"
+        } else {
+            "checkout"
+        }];
+        args.extend(self.args.iter().map(String::as_str));
+        // `git switch` only leaves the branches behind when told to.
+        if tag_name(branch).is_some() {
+            args.push("--detach");
+        }
+        args.push(branch);
+        args
+    }
+}
+
+/// The tag `reference` names if it is a tag's full ref name (as `Scope::Tags` checks
+/// them out), or `None` for a branch.
+pub fn tag_name(reference: &str) -> Option<&str> {
+    reference.strip_prefix("refs/tags/")
+}
+
+/// Check out `branch` behind a spinner, passing git's own output through. With `quiet`,
+/// git's output is only shown when the checkout fails.
+pub fn checkout(branch: &str, quiet: bool, switch: &SwitchCommand) -> Result<(), Box<dyn Error>> {
+    if switch.jj.unwrap_or_else(jj::colocated) {
+        return jj::new_change(tag_name(branch).unwrap_or(branch), quiet);
+    }
+    let args = switch.args(branch);
+    if skip_for_dry_run(git_command_line(&args)) {
+        return Ok(());
+    }
+    // git would only say it cannot create the lock file; say who holds it instead.
+    if let Some(lock) = find_lock() {
+        return Err(AppError::RepositoryLocked(lock.to_string()).into());
+    }
+    // Run git off the UI thread so slow checkouts show progress instead of a frozen screen.
+    let os_args: Vec<_> = args.iter().map(|arg| refname::to_os(arg)).collect();
+    let output = with_spinner(
+        &i18n::message(
+            "progress.checking_out",
+            &[("branch", &refname::display(branch))],
+        ),
+        || {
+            trace::output(
+                command()
+                    .args(&os_args)
+                    .env(checkout_hook::RECORDING_VAR, "1"),
+            )
+        },
+    )?;
+    if quiet && output.status.success() {
+        return Ok(());
+    }
+    io::stdout().write_all(&output.stdout)?;
+    io::stderr().write_all(&output.stderr)?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let message = git_failed(args[0], output.status);
+    // git's messages follow the user's locale, so the cause is read from the
+    // repository instead of from what git said.
+    // Each branch can be checked out in one worktree only.
+    if let Some(path) = other_worktree_branches()?.get(branch) {
+        return Err(i18n::message(
+            "checkout.in_worktree",
+            &[
+                ("branch", &refname::display(branch)),
+                ("path", &path.display()),
+            ],
+        )
+        .into());
+    }
+    // Taken by another process after the check above.
+    if let Some(lock) = find_lock() {
+        return Err(AppError::RepositoryLocked(lock.to_string()).into());
+    }
+    // With a clean worktree there is nothing a checkout could overwrite.
+    if uncommitted_changes()? > 0 {
+        return Err(AppError::CheckoutConflict(i18n::message(
+            "checkout.stash_first",
+            &[("error", &message)],
+        ))
+        .into());
+    }
+    Err(message.into())
+}
+
+/// A lock file that keeps git from updating the index or HEAD, left by a git process
+/// that is still running or by one that crashed.
+#[derive(Debug)]
+pub struct Lock {
+    pub path: PathBuf,
+    /// How long ago the lock was taken.
+    pub age: Option<Duration>,
+    /// The git process most likely holding it, as "pid 1234: git rebase -i".
+    pub owner: Option<String>,
+}
+
+impl fmt::Display for Lock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let age = self.age.map_or_else(String::new, |age| {
+            let ago = format::relative_time(age.as_secs() as i64);
+            i18n::message("lock.age", &[("ago", &ago)])
+        });
+        let path = self.path.display();
+        let text = match &self.owner {
+            Some(owner) => i18n::message(
+                "lock.held",
+                &[("path", &path), ("age", &age), ("owner", owner)],
+            ),
+            None => i18n::message("lock.left", &[("path", &path), ("age", &age)]),
+        };
+        f.write_str(&text)
+    }
+}
+
+/// The repository's `index.lock` or `HEAD.lock`, if either exists; both stop a
+/// checkout.
+pub fn find_lock() -> Option<Lock> {
+    let output = trace::output(command().args([
+        "rev-parse",
+        "--git-path",
+        "index.lock",
+        "--git-path",
+        "HEAD.lock",
+    ]))
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let paths = refname::decode(&output.stdout);
+    let path = paths
+        .lines()
+        .map(|line| PathBuf::from(refname::to_os(line)))
+        .find(|path| path.exists())?;
+    let age = fs::metadata(&path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    let owner = top_level().and_then(|top| lock_owner(&top));
+    Some(Lock { path, age, owner })
+}
+
+/// Wait until `find_lock` finds nothing, behind a spinner. Stopped with Ctrl-C.
+pub fn wait_for_unlock() {
+    with_spinner(i18n::text("progress.waiting_for_lock"), || {
+        while find_lock().is_some() {
+            thread::sleep(Duration::from_millis(200));
+        }
+    });
+}
+
+/// A running git process working in `worktree`, found through `/proc` (so only on
+/// Linux): "pid 1234: git rebase -i".
+fn lock_owner(worktree: &Path) -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let own_pid = std::process::id().to_string();
+    fs::read_dir("/proc").ok()?.flatten().find_map(|entry| {
+        let pid = entry.file_name().into_string().ok()?;
+        if !pid.bytes().all(|b| b.is_ascii_digit()) || pid == own_pid {
+            return None;
+        }
+        let dir = entry.path();
+        if fs::read_to_string(dir.join("comm")).ok()?.trim() != "git" {
+            return None;
+        }
+        if !fs::read_link(dir.join("cwd")).ok()?.starts_with(worktree) {
+            return None;
+        }
+        let cmdline = fs::read(dir.join("cmdline")).ok()?;
+        let words: Vec<String> = cmdline
+            .split(|&b| b == 0)
+            .filter(|word| !word.is_empty())
+            .map(|word| refname::display(&refname::decode(word)).into_owned())
+            .collect();
+        Some(format!("pid {pid}: {}", words.join(" ")))
+    })
+}
+
+/// The worktree that has `branch` checked out, adding one if there is none. New
+/// worktrees go into `dir` (default: next to the main worktree) and are named
+/// `<repo>-<branch>`, with slashes in the branch name turned into dashes.
+pub fn worktree_for(branch: &str, dir: Option<&str>) -> Result<PathBuf, Box<dyn Error>> {
+    let worktrees = worktree_list()?;
+    if let Some(worktree) = worktrees
+        .iter()
+        .find(|w| w.branch.as_deref() == Some(branch))
+    {
+        return Ok(worktree.path.clone());
+    }
+
+    // The main worktree is always listed first.
+    let main = worktrees.into_iter().next().map(|w| w.path);
+    let main = main.ok_or("git worktree list printed no worktrees")?;
+    let repo = main
+        .file_name()
+        .map_or("repo".into(), |n| refname::decode(n.as_encoded_bytes()));
+    let base = match dir {
+        Some(dir) => PathBuf::from(dir),
+        None => main
+            .parent()
+            .map_or_else(|| main.clone(), Path::to_path_buf),
+    };
+    let target = base.join(refname::to_os(&format!(
+        "{repo}-{}",
+        branch.replace('/', "-")
+    )));
+    let target_arg = refname::decode(target.as_os_str().as_encoded_bytes());
+    run_git(
+        &i18n::message(
+            "progress.adding_worktree",
+            &[("branch", &refname::display(branch))],
+        ),
+        &["worktree", "add", &target_arg, branch],
+    )?;
+    Ok(target)
+}
+
+/// A worktree of the repository, as listed by `git worktree list`.
+struct Worktree {
+    path: PathBuf,
+    /// The branch checked out in it; `None` when its HEAD is detached.
+    branch: Option<String>,
+}
+
+/// Every worktree of the repository, the main worktree first.
+fn worktree_list() -> Result<Vec<Worktree>, Box<dyn Error>> {
+    let output = trace::output(command().args(["worktree", "list", "--porcelain"]))?;
+    if !output.status.success() {
+        return Err(git_failed("worktree", output.status).into());
+    }
+    let mut worktrees: Vec<Worktree> = Vec::new();
+    for line in refname::decode(&output.stdout).lines() {
+        if let Some(path) = line.strip_prefix("worktree ") {
+            worktrees.push(Worktree {
+                path: PathBuf::from(refname::to_os(path)),
+                branch: None,
+            });
+        } else if let Some(branch) = line.strip_prefix("branch refs/heads/")
+            && let Some(worktree) = worktrees.last_mut()
+        {
+            worktree.branch = Some(branch.to_string());
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Branches checked out in worktrees other than the current one, with the path of
+/// the worktree holding each. They cannot be checked out here.
+pub fn other_worktree_branches() -> Result<HashMap<String, PathBuf>, Box<dyn Error>> {
+    let current = top_level();
+    Ok(worktree_list()?
+        .into_iter()
+        .filter(|w| Some(&w.path) != current.as_ref())
+        .filter_map(|w| Some((w.branch?, w.path)))
+        .collect())
+}
+
+/// A branch and its metadata, as listed by `load_branches`.
+pub struct BranchInfo {
+    pub name: String,
+    pub sha: String,
+    /// Committer date in strict ISO 8601 format.
+    pub committer_date: String,
+    /// Committer date as a Unix timestamp.
+    pub committer_timestamp: i64,
+    pub subject: String,
+    pub upstream: Option<String>,
+    /// Commits ahead of/behind the upstream; `None` without an upstream or when it is gone.
+    pub ahead: Option<u32>,
+    pub behind: Option<u32>,
+    pub current: bool,
+}
+
+/// How a branch compares to its upstream and the main line; slower to work out than
+/// `BranchInfo`, so the picker asks for it only for the rows it shows.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BranchStatus {
+    /// Commits ahead of/behind the upstream, or the base chosen with `--default-branch`;
+    /// `None` without either, or when the upstream is gone.
+    pub ahead: Option<u32>,
+    pub behind: Option<u32>,
+    /// Whether the branch is merged into the branch it was compared with.
+    pub merged: bool,
+}
+
+/// The status of each of `branches` (named as listed in `scope`), in order, with
+/// ahead/behind counted against `counts_against` instead of each branch's upstream
+/// if given, and merged status against `merged_into` if given.
+///
+/// Only cheap listings of the branch and upstream tips run every time; the counts and
+/// merged checks behind them are remembered per commit for the rest of the session,
+/// so showing the same branches again (in another scope, or after a reload that did
+/// not move them) costs no history walks.
+pub fn branch_status(
+    branches: &[String],
+    scope: Scope,
+    counts_against: Option<&str>,
+    merged_into: Option<&str>,
+) -> Result<Vec<BranchStatus>, Box<dyn Error>> {
+    let refs: Vec<String> = branches.iter().map(|b| scope.refname(b)).collect();
+    // Full ref name → (tip, upstream ref name).
+    let tips: HashMap<String, (String, String)> = list_refs(
+        &[],
+        &format!("%(refname){FIELD_SEP}%(objectname){FIELD_SEP}%(upstream)"),
+        &refs,
+    )?
+    .lines()
+    .filter_map(|line| {
+        let mut fields = line.split('\0');
+        let full_name = fields.next()?.to_string();
+        Some((
+            full_name,
+            (fields.next()?.to_string(), fields.next()?.to_string()),
+        ))
+    })
+    .collect();
+    let mut upstreams: Vec<String> = tips
+        .values()
+        .map(|(_, upstream)| upstream.clone())
+        .filter(|upstream| !upstream.is_empty())
+        .collect();
+    upstreams.sort();
+    upstreams.dedup();
+    // A deleted upstream is just not listed, leaving its branches without counts.
+    let upstream_tips: HashMap<String, String> = list_refs(
+        &[],
+        &format!("%(refname){FIELD_SEP}%(objectname)"),
+        &upstreams,
+    )?
+    .lines()
+    .filter_map(|line| {
+        let (name, tip) = line.split_once('\0')?;
+        Some((name.to_string(), tip.to_string()))
+    })
+    .collect();
+    let target = match merged_into {
+        Some(target) => resolve_commit(target)?,
+        None => None,
+    };
+    let base = match counts_against {
+        Some(base) => resolve_commit(base)?,
+        None => None,
+    };
+    // The commits a branch's counts are between: its tip and what it is compared with.
+    let count_key = |full_name: &str| {
+        let (tip, upstream) = tips.get(full_name)?;
+        let other = match counts_against {
+            Some(_) => base.clone()?,
+            None => upstream_tips.get(upstream)?.clone(),
+        };
+        Some((tip.clone(), other))
+    };
+    let merged_key = |full_name: &str| Some((tips.get(full_name)?.0.clone(), target.clone()?));
+
+    let mut memo = STATUS_MEMO.lock().unwrap_or_else(|e| e.into_inner());
+    let uncounted: Vec<String> = refs
+        .iter()
+        .filter(|r| count_key(r).is_some_and(|key| !memo.counts.contains_key(&key)))
+        .cloned()
+        .collect();
+    if counts_against.is_some() {
+        for full_name in &uncounted {
+            if let Some(key) = count_key(full_name)
+                && let Some(counts) = count_commits(&key.1, &key.0)?
+            {
+                memo.counts.insert(key, counts);
+            }
+        }
+    }
+    // Without a base, git counts against each upstream while listing the branches.
+    let tracking = match counts_against {
+        Some(_) => String::new(),
+        None => list_refs(
+            &[],
+            &format!("%(refname){FIELD_SEP}%(objectname){FIELD_SEP}%(upstream:track,nobracket)"),
+            &uncounted,
+        )?,
+    };
+    for line in tracking.lines() {
+        let mut fields = line.split('\0');
+        let (Some(full_name), Some(tip), Some(track)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        // Skip a branch that moved since its tip was listed, rather than file its counts
+        // under the wrong commit.
+        if let Some(key) = count_key(full_name).filter(|key| key.0 == tip)
+            && let Some(counts) = parse_track(track)
+        {
+            memo.counts.insert(key, counts);
+        }
+    }
+    if let Some(target) = &target {
+        let unchecked: Vec<String> = refs
+            .iter()
+            .filter(|r| merged_key(r).is_some_and(|key| !memo.merged.contains_key(&key)))
+            .cloned()
+            .collect();
+        let merged = list_refs(&["--merged", target], "%(refname)", &unchecked)?;
+        let merged: HashSet<&str> = merged.lines().collect();
+        for full_name in &unchecked {
+            if let Some(key) = merged_key(full_name) {
+                memo.merged.insert(key, merged.contains(full_name.as_str()));
+            }
+        }
+    }
+
+    Ok(refs
+        .iter()
+        .map(|full_name| {
+            let counts = count_key(full_name).and_then(|key| memo.counts.get(&key).copied());
+            BranchStatus {
+                ahead: counts.map(|c| c.0),
+                behind: counts.map(|c| c.1),
+                merged: merged_key(full_name)
+                    .and_then(|key| memo.merged.get(&key).copied())
+                    .unwrap_or(false),
+            }
+        })
+        .collect())
+}
+
+/// Commits on `tip` but not `base`, and on `base` but not `tip`; `None` if git cannot
+/// count them.
+fn count_commits(base: &str, tip: &str) -> Result<Option<(u32, u32)>, Box<dyn Error>> {
+    let output = trace::output(
+        command()
+            .args(["rev-list", "--left-right", "--count"])
+            .arg(format!("{base}...{tip}")),
+    )?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let counts = String::from_utf8_lossy(&output.stdout);
+    // The left side, `base`, is what the branch is behind.
+    let mut counts = counts.split_whitespace().map(|count| count.parse().ok());
+    Ok(match (counts.next().flatten(), counts.next().flatten()) {
+        (Some(behind), Some(ahead)) => Some((ahead, behind)),
+        _ => None,
+    })
+}
+
+/// `git for-each-ref` over exactly `refs` (nothing at all for none, where git would
+/// list every ref).
+fn list_refs(extra: &[&str], format: &str, refs: &[String]) -> Result<String, Box<dyn Error>> {
+    if refs.is_empty() {
+        return Ok(String::new());
+    }
+    let output = trace::output(
+        command()
+            .arg("for-each-ref")
+            .arg(format!("--format={format}"))
+            .args(extra)
+            .args(refs.iter().map(|r| refname::to_os(r))),
+    )?;
+    if !output.status.success() {
+        return Err(git_failed("for-each-ref", output.status).into());
+    }
+    Ok(refname::decode(&output.stdout))
+}
+
+/// The commit `rev` names, or `None` if it names none.
+fn resolve_commit(rev: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let output = trace::output(
+        command()
+            .args(["rev-parse", "--verify", "--quiet"])
+            .arg(refname::to_os(&format!("{rev}^{{commit}}"))),
+    )?;
+    Ok(output
+        .status
+        .success()
+        .then(|| refname::decode(&output.stdout).trim().to_string()))
+}
+
+/// Parse `%(upstream:track,nobracket)`: "ahead 2, behind 1", "ahead 2", "behind 1",
+/// "" (in sync) or "gone" (upstream deleted), the last yielding `None`.
+fn parse_track(track: &str) -> Option<(u32, u32)> {
+    if track == "gone" {
+        return None;
+    }
+    let mut counts = (0, 0);
+    for part in track.split(", ").filter(|p| !p.is_empty()) {
+        match part.split_once(' ') {
+            Some(("ahead", n)) => counts.0 = n.parse().ok()?,
+            Some(("behind", n)) => counts.1 = n.parse().ok()?,
+            _ => return None,
+        }
+    }
+    Some(counts)
+}
@@ -0,0 +1,137 @@
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::format;
+use crate::git;
+use crate::refname;
+use crate::trace;
+
+/// Entries older than this are dropped when the file is rewritten.
+const MAX_AGE_SECS: i64 = 180 * 24 * 60 * 60;
+/// At most this many entries are kept per repository.
+const MAX_ENTRIES: usize = 1000;
+
+/// Checkouts made through git-recent in one repository, oldest first, stored as
+/// `<unix time>\t<branch>` lines in a state file keyed by the repository path.
+pub struct History {
+    path: PathBuf,
+    entries: Vec<(i64, String)>,
+}
+
+impl History {
+    /// The history of the current repository, or `None` outside a repository or
+    /// without a home directory. A missing or unreadable file is an empty history.
+    pub fn open() -> Option<History> {
+        let path = state_file("history")?;
+        let entries = fs::read_to_string(&path)
+            .map(|text| {
+                text.lines()
+                    .filter_map(|line| {
+                        let (time, branch) = line.split_once('\t')?;
+                        Some((time.parse().ok()?, branch.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(History { path, entries })
+    }
+
+    /// Branch names, most recently checked out first, without duplicates.
+    pub fn recent_first(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for (_, branch) in self.entries.iter().rev() {
+            if !names.contains(branch) {
+                names.push(branch.clone());
+            }
+        }
+        names
+    }
+
+    /// Append a checkout of `branch` and rewrite the file, pruning old entries and
+    /// branches that no longer exist.
+    pub fn record(&mut self, branch: &str) -> io::Result<()> {
+        let now = format::now();
+        self.entries.push((now, branch.to_string()));
+        let existing = local_branches();
+        self.entries.retain(|(time, name)| {
+            now - time <= MAX_AGE_SECS && existing.as_ref().is_none_or(|e| e.contains(name))
+        });
+        let excess = self.entries.len().saturating_sub(MAX_ENTRIES);
+        self.entries.drain(..excess);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write a sibling file and rename it over the old one, so a crash never
+        // leaves a truncated history behind.
+        let temp = self.path.with_extension("tmp");
+        let mut file = fs::File::create(&temp)?;
+        for (time, branch) in &self.entries {
+            writeln!(file, "{time}\t{branch}")?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+/// The current repository's state file with the given extension, named after a hash
+/// of the repository's path. `None` outside a repository or without a home directory.
+pub fn state_file(extension: &str) -> Option<PathBuf> {
+    state_file_of(&repository_dir()?, extension)
+}
+
+/// The state file with the given extension of the repository whose git directory is
+/// `git_dir`, for callers that found it without asking git.
+pub fn state_file_of(git_dir: &Path, extension: &str) -> Option<PathBuf> {
+    let repo = fs::canonicalize(git_dir).ok()?;
+    let hash = fnv1a(repo.as_os_str().as_encoded_bytes());
+    Some(state_dir()?.join(format!("{hash:016x}.{extension}")))
+}
+
+/// `$XDG_STATE_HOME/git-recent`, falling back to `~/.local/state/git-recent`.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/state")))?;
+    Some(base.join("git-recent"))
+}
+
+/// The git directory of the current worktree. Linked worktrees have their own HEAD
+/// and so their own checkout history; for the main worktree this is the repository's
+/// `.git` directory.
+fn repository_dir() -> Option<PathBuf> {
+    let output = trace::output(git::command().args(["rev-parse", "--git-dir"])).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let dir = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    fs::canonicalize(dir).ok()
+}
+
+fn local_branches() -> Option<Vec<String>> {
+    let output = trace::output(git::command().args([
+        "for-each-ref",
+        "--format=%(refname:short)",
+        "refs/heads",
+    ]))
+    .ok()?;
+    output.status.success().then(|| {
+        refname::decode(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect()
+    })
+}
+
+/// 64-bit FNV-1a, a stable hash for naming state files.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
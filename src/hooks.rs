@@ -0,0 +1,76 @@
+use std::process::Command;
+use std::time::Duration;
+
+use crate::git;
+use crate::i18n;
+use crate::refname;
+use crate::trace;
+
+/// User commands run around a checkout (`hooks.pre_checkout`, `hooks.post_checkout`),
+/// e.g. to restart a dev server. They run with `sh -c`, with the target branch in
+/// `GIT_RECENT_BRANCH` and the branch being left in `GIT_RECENT_PREVIOUS_BRANCH`.
+#[derive(Clone, Debug, Default)]
+pub struct Hooks {
+    /// Runs before switching; if it fails, the checkout does not happen.
+    pub pre_checkout: Option<String>,
+    pub post_checkout: Option<String>,
+    /// A checkout taking at least this long ends with a desktop notification
+    /// (`notify.after`).
+    pub notify_after: Option<Duration>,
+}
+
+impl Hooks {
+    pub fn pre_checkout(&self, branch: &str, previous: &str) -> Result<(), String> {
+        run(
+            "pre_checkout",
+            self.pre_checkout.as_deref(),
+            branch,
+            previous,
+        )
+    }
+
+    pub fn post_checkout(&self, branch: &str, previous: &str) -> Result<(), String> {
+        run(
+            "post_checkout",
+            self.post_checkout.as_deref(),
+            branch,
+            previous,
+        )
+    }
+}
+
+fn run(name: &str, command: Option<&str>, branch: &str, previous: &str) -> Result<(), String> {
+    let Some(command) = command else {
+        return Ok(());
+    };
+    let env = [
+        ("GIT_RECENT_BRANCH", branch),
+        ("GIT_RECENT_PREVIOUS_BRANCH", previous),
+    ];
+    let line = env
+        .iter()
+        .map(|(var, value)| format!("{var}={} ", git::shell_quote(value)))
+        .collect::<String>()
+        + &format!("sh -c {}", git::shell_quote(command));
+    if git::skip_for_dry_run(line) {
+        return Ok(());
+    }
+    let status = trace::status(
+        Command::new("sh")
+            .args(["-c", command])
+            .envs(env.map(|(var, value)| (var, refname::to_os(value)))),
+    )
+    .map_err(|e| {
+        i18n::message(
+            "hooks.cannot_run",
+            &[("name", &name), ("command", &command), ("error", &e)],
+        )
+    })?;
+    if !status.success() {
+        return Err(i18n::message(
+            "hooks.failed",
+            &[("name", &name), ("status", &status)],
+        ));
+    }
+    Ok(())
+}
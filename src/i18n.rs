@@ -0,0 +1,1112 @@
+//! User-facing text in the user's language. Every message has an id and its English
+//! text in `ENGLISH`; each translation in `TRANSLATIONS` maps ids to its own text and
+//! falls back to English for any it lacks. The language comes from `LC_ALL`,
+//! `LC_MESSAGES` or `LANG`, the first one set, like other command line tools.
+//!
+//! Texts may contain `{name}` placeholders, filled in by `message`. To add a
+//! language, add a table with the ids of `ENGLISH` and list it in `TRANSLATIONS`.
+
+use std::borrow::Cow;
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+type Catalog = &'static [(&'static str, &'static str)];
+
+const ENGLISH: Catalog = &[
+    ("picker.title", "Select recent branch"),
+    ("picker.tag_title", "Select recent tag"),
+    (
+        "picker.delete_title",
+        "Delete branches (Space to mark, Enter to delete)",
+    ),
+    ("picker.detached", "HEAD detached at {commit}"),
+    ("picker.filter", "filter: {filter}"),
+    ("picker.limited", "first {shown} of {total}, / searches all"),
+    ("picker.less", "(less)"),
+    ("picker.more", "(more)"),
+    ("picker.no_matches", "(no matches)"),
+    ("picker.merged", "(merged)"),
+    ("picker.protected", "(protected)"),
+    ("pull.open", "open"),
+    ("pull.draft", "draft"),
+    ("pull.approved", "approved"),
+    ("pull.merged", "merged"),
+    ("pull.closed", "closed"),
+    ("picker.return_to", "Enter returns to '{branch}'"),
+    (
+        "accessible.selected",
+        "selected: {branch} ({index} of {total})",
+    ),
+    ("accessible.current", "current branch"),
+    (
+        "accessible.other_worktree",
+        "checked out in another worktree",
+    ),
+    ("accessible.marked", "marked"),
+    ("accessible.protected", "protected"),
+    ("accessible.ahead", "{count} ahead"),
+    ("accessible.behind", "{count} behind"),
+    ("accessible.merged", "merged"),
+    ("accessible.pull", "pull request {pull}"),
+    ("scope.remote", "remote"),
+    ("scope.all", "all"),
+    ("scope.empty", "No {scope} branches"),
+    (
+        "tags.not_a_branch",
+        "Tags can only be checked out, branched from or looked at here",
+    ),
+    ("prompt.filter", "Filter: "),
+    ("prompt.new_branch", "New branch from '{base}': "),
+    ("prompt.rename", "Rename '{branch}' to: "),
+    ("prompt.branch_number", "Branch number (empty to cancel): "),
+    (
+        "prompt.repository_number",
+        "Repository number (empty to cancel): ",
+    ),
+    ("confirm.yes", "Yes"),
+    ("confirm.no", "No"),
+    ("menu.title", "Actions for '{branch}':"),
+    ("action.check_out", "Check out"),
+    ("action.create", "Create a branch from it"),
+    ("action.rename", "Rename"),
+    ("action.delete", "Delete"),
+    ("action.diff", "Diff against the current branch"),
+    ("action.log", "Show its log"),
+    ("action.push", "Push"),
+    (
+        "action.pull_request",
+        "Open its pull request (or create one)",
+    ),
+    ("action.yank", "Copy the name"),
+    ("reload.done", "Reloaded {count} branches"),
+    ("create.done", "Created branch '{name}' from '{base}'"),
+    ("alias.added", "Added 'git {name}'"),
+    ("alias.removed", "Removed 'git {name}'"),
+    ("alias.none", "No git aliases run git-recent"),
+    (
+        "hook.installed",
+        "Installed the post-checkout hook at {path}",
+    ),
+    ("hook.removed", "Removed the post-checkout hook at {path}"),
+    (
+        "hook.none",
+        "No post-checkout hook of git-recent is installed",
+    ),
+    (
+        "fzf.not_found",
+        "fzf is not installed or not on the PATH; use --picker builtin",
+    ),
+    ("fzf.failed", "fzf failed: {status}"),
+    ("tmux.not_running", "--tmux-popup only works inside tmux"),
+    (
+        "tmux.failed",
+        "tmux could not open a popup ({status}); display-popup needs tmux 3.2 or later",
+    ),
+    ("rename.remote", "Cannot rename remote branch '{branch}'"),
+    (
+        "rename.protected",
+        "Cannot rename protected branch '{branch}'",
+    ),
+    ("rename.done", "Renamed '{from}' to '{to}'"),
+    ("diff.current", "'{branch}' is the current branch"),
+    ("notify.checked_out", "Now on '{branch}'"),
+    ("notify.fetched", "Fetched all remotes"),
+    ("preview.title", "Changes on '{branch}'"),
+    ("preview.position", "(lines {first}–{last} of {total})"),
+    ("preview.empty", "'{branch}' makes no changes"),
+    (
+        "preview.truncated",
+        "… only the first {count} lines are shown",
+    ),
+    ("push.remote", "Cannot push remote branch '{branch}'"),
+    ("push.done", "Pushed '{branch}'"),
+    ("yank.done", "Copied '{branch}' to the clipboard"),
+    (
+        "pull.opened",
+        "Opened the pull request of '{branch}' in the browser",
+    ),
+    ("pull.started", "Started a pull request for '{branch}'"),
+    (
+        "pull.no_tool",
+        "{tool} is not installed or not on the PATH; it is needed for pull requests",
+    ),
+    ("delete.remote", "Cannot delete remote branch '{branch}'"),
+    (
+        "delete.current",
+        "Cannot delete the current branch '{branch}'",
+    ),
+    (
+        "delete.protected",
+        "Cannot delete protected branch '{branch}'",
+    ),
+    ("delete.confirm", "Delete branch '{branch}'?"),
+    ("delete.confirm_marked", "Delete {count} marked branches?"),
+    ("delete.done", "Deleted branch '{branch}'"),
+    ("delete.done_many", "Deleted {count} branches"),
+    (
+        "delete.needs_picker",
+        "--delete needs the interactive picker (a terminal, not --simple)",
+    ),
+    (
+        "delete.unmerged",
+        "Branch '{branch}' is not fully merged. Force delete?",
+    ),
+    (
+        "delete.unmerged_many",
+        "{count} branches are not fully merged. Force delete?",
+    ),
+    ("checkout.confirm", "Check out '{branch}' ({status})?"),
+    ("checkout.clean", "working tree clean"),
+    ("checkout.one_change", "1 uncommitted change"),
+    ("checkout.changes", "{count} uncommitted changes"),
+    ("checkout.running", "Checking out branch: {branch}"),
+    ("checkout.index_zero", "branch indexes start at 1"),
+    (
+        "checkout.index_too_large",
+        "there are only {count} recent branches, cannot check out #{index}",
+    ),
+    ("checkout.no_such", "no recent branch named '{name}'"),
+    (
+        "checkout.did_you_mean",
+        "no recent branch named '{name}'; did you mean {suggestion}?",
+    ),
+    (
+        "checkout.did_you_mean_many",
+        "no recent branch named '{name}'; did you mean one of {suggestions}?",
+    ),
+    ("list.empty", "No branches found"),
+    (
+        "config.untrusted",
+        "warning: {source}: ignoring '{key}', which a repository's config file may only set with trust_repo_config (e.g. git config recent.trustRepoConfig true)",
+    ),
+    (
+        "config.unknown_git_key",
+        "warning: {source}: ignoring unknown setting '{key}'",
+    ),
+    ("list.no_tags", "No tags found"),
+    ("chdir.failed", "cannot change to '{dir}': {error}"),
+    ("exec.failed", "cannot run '{command}': {error}"),
+    ("log_file.failed", "cannot open the log file: {error}"),
+    (
+        "repositories.heading",
+        "Not a git repository. Recently used repositories:",
+    ),
+    (
+        "repositories.no_such",
+        "no recent repository numbered '{answer}'",
+    ),
+    (
+        "fetch.failed",
+        "Warning: {error}; listing branches as of the last fetch",
+    ),
+    ("error", "Error: {error}"),
+    (
+        "error.not_a_repository",
+        "not a git repository; run git-recent inside one, or point it at one with -C <path>",
+    ),
+    (
+        "error.git_not_found",
+        "git is not installed or not on the PATH",
+    ),
+    (
+        "error.no_work_tree",
+        "this is a bare repository (or a .git directory) with nothing to check out; \
+         use --list, --print or --exec to pick a branch",
+    ),
+    ("terminal.raw_mode", "cannot put the terminal into raw mode"),
+    (
+        "terminal.none",
+        "no terminal available to choose a branch: {error}",
+    ),
+    ("git.failed", "git {command} failed: {error}"),
+    ("git.cannot_run", "cannot run git: {error}"),
+    (
+        "git.no_default_branch",
+        "cannot determine the default branch (no origin/HEAD, main or master)",
+    ),
+    (
+        "checkout.in_worktree",
+        "'{branch}' is checked out in the worktree at {path}; work on it there",
+    ),
+    (
+        "checkout.stash_first",
+        "{error}; commit or stash your changes first",
+    ),
+    ("lock.age", " (created {ago})"),
+    (
+        "lock.held",
+        "'{path}' exists{age}; another git command is using the repository ({owner}), so wait for it to finish",
+    ),
+    (
+        "lock.left",
+        "'{path}' exists{age}; if no other git command is running, it was left behind by one that crashed: remove it with rm '{path}'",
+    ),
+    ("progress.loading", "Loading branches..."),
+    ("progress.checking_out", "Checking out {branch}..."),
+    (
+        "progress.waiting_for_lock",
+        "Waiting for the lock to clear (Ctrl-C to give up)...",
+    ),
+    (
+        "progress.adding_worktree",
+        "Adding worktree for {branch}...",
+    ),
+    ("progress.creating", "Creating {branch}..."),
+    ("progress.renaming", "Renaming {branch}..."),
+    ("progress.deleting", "Deleting {branch}..."),
+    ("progress.pushing", "Pushing {branch}..."),
+    ("progress.switching", "Switching to {branch}..."),
+    ("progress.adding_alias", "Adding 'git {name}'..."),
+    ("progress.removing_alias", "Removing 'git {name}'..."),
+    (
+        "hooks.cannot_run",
+        "cannot run hooks.{name} '{command}': {error}",
+    ),
+    ("hooks.failed", "hooks.{name} failed: {status}"),
+    (
+        "jj.not_installed",
+        "jj is not installed or not on the PATH; set jj = false to check out with git",
+    ),
+    ("jj.cannot_run", "cannot run jj: {error}"),
+    ("jj.failed", "jj new failed: {status}"),
+    (
+        "clipboard.terminal_failed",
+        "cannot write to the terminal: {error}",
+    ),
+    (
+        "clipboard.no_tool",
+        "no clipboard tool found ({tools}); set clipboard.osc52 = true to copy through the terminal",
+    ),
+    (
+        "alias.taken",
+        "'git {name}' is already an alias for '{current}'; remove it with 'git config --global --unset alias.{name}' or pick another shortcut",
+    ),
+    (
+        "config.unknown_profile",
+        "unknown profile '{name}' (defined: {known})",
+    ),
+    (
+        "config.no_profiles",
+        "unknown profile '{name}' (no [profile.<name>] sections are defined)",
+    ),
+    ("config.cannot_read", "cannot read {path}: {error}"),
+    (
+        "config.profile_not_table",
+        "profile.{key} must be a table of settings",
+    ),
+    (
+        "config.profile_in_profile",
+        "a profile cannot select another profile",
+    ),
+    ("config.unknown_setting", "unknown setting '{key}'"),
+    ("config.wrong_kind", "'{key}' must be {kind}"),
+    (
+        "config.wrong_kind_text",
+        "{key} must be {kind}, not '{text}'",
+    ),
+    ("config.kind_string", "a string"),
+    ("config.kind_integer", "an integer"),
+    ("config.kind_boolean", "true or false"),
+    ("config.kind_strings", "a list of strings"),
+    ("config.invalid", "invalid '{key}': {message}"),
+    (
+        "config.invalid_in",
+        "invalid '{key}' in {source}: {message}",
+    ),
+    (
+        "config.unknown_sort",
+        "unknown sort key '{name}' (expected one of: {names})",
+    ),
+    (
+        "config.unknown_case",
+        "unknown case '{name}' (expected one of: {names})",
+    ),
+    (
+        "config.unknown_picker",
+        "unknown picker '{name}' (expected one of: {names})",
+    ),
+    ("config.negative", "must not be negative"),
+    ("config.below_one", "must be at least 1"),
+    ("config.unknown_key", "unknown key '{key}'"),
+    ("config.exec_missing", "'exec' needs the 'exec' setting"),
+    (
+        "config.unknown_action",
+        "unknown action '{action}' (expected checkout, print, worktree or exec)",
+    ),
+    (
+        "config.unknown_color",
+        "unknown color '{name}' for colors.{slot} (expected one of: {names}, optionally prefixed with 'bright-', a number from 0 to 255 or #rrggbb)",
+    ),
+    ("config.unknown_slot", "unknown color slot '{slot}'"),
+    ("toml.expected", "expected {wanted}, found {found}"),
+    ("toml.a_key", "a key"),
+    ("toml.a_value", "a value"),
+    ("toml.comma_or_bracket", "',' or ']' in array"),
+    ("toml.end_of_line", "end of line"),
+    ("toml.end_of_file", "end of file"),
+    ("toml.after_value", "unexpected {found} after value"),
+    ("toml.table_arrays", "arrays of tables are not supported"),
+    ("toml.set_twice", "'{key}' is set twice"),
+    ("toml.unsupported_value", "unsupported value '{value}'"),
+    ("toml.unterminated_array", "unterminated array"),
+    ("toml.unterminated_string", "unterminated string"),
+    ("toml.multi_line", "multi-line strings are not supported"),
+    ("toml.invalid_escape", "invalid escape '{escape}'"),
+    ("toml.invalid_unicode", "invalid unicode escape '{escape}'"),
+    ("colors.basic", "8 colors"),
+    ("colors.indexed", "256 colors"),
+    ("colors.true_color", "24-bit color"),
+    ("doctor.fix", "fix"),
+    ("doctor.git_failed", "'git --version' failed: {status}"),
+    ("doctor.reinstall_git", "reinstall git"),
+    (
+        "doctor.install_git",
+        "install git and make sure it is on your PATH",
+    ),
+    ("doctor.git_too_old", "{version} is too old"),
+    ("doctor.upgrade_git", "upgrade to git {version} or newer"),
+    ("doctor.locked", "checkouts are blocked: {lock}"),
+    (
+        "doctor.wait_for_lock",
+        "wait for the other git command, or remove the lock file if none is running",
+    ),
+    ("doctor.worktree", "worktree at {path}"),
+    (
+        "doctor.bare",
+        "bare repository: branches can be listed but not checked out",
+    ),
+    (
+        "doctor.use_bare",
+        "use --list, --print or --exec here, or action = \"worktree\"",
+    ),
+    ("doctor.not_a_repository", "not inside a git repository"),
+    (
+        "doctor.find_repository",
+        "run git-recent inside one, or point it at one with -C <path>",
+    ),
+    ("doctor.check_git", "check that git works here"),
+    (
+        "doctor.stdin_not_terminal",
+        "stdin is not a terminal, so the numbered prompt is used",
+    ),
+    (
+        "doctor.use_terminal",
+        "run git-recent from an interactive terminal for the full-screen picker",
+    ),
+    (
+        "doctor.use_regular_terminal",
+        "run git-recent from a regular terminal, or use --simple",
+    ),
+    ("doctor.input_ok", "stdin is a terminal and raw mode works"),
+    (
+        "doctor.term_not_set",
+        "TERM is not set, so the numbered prompt is used without colors",
+    ),
+    (
+        "doctor.term_dumb",
+        "TERM is dumb, so the numbered prompt is used without colors",
+    ),
+    (
+        "doctor.set_term",
+        "set TERM to your terminal's type, e.g. TERM=xterm-256color",
+    ),
+    (
+        "doctor.terminal_ok",
+        "TERM={term}, {colors}; no alternate screen needed",
+    ),
+    (
+        "doctor.fix_setting",
+        "correct the setting named above; 'git-recent config' shows every setting",
+    ),
+    (
+        "doctor.untrusted",
+        "remove the setting from the repository's file, or trust it with trust_repo_config",
+    ),
+    ("doctor.no_config", "no config files; using defaults"),
+    ("doctor.read_config", "read {files}"),
+    (
+        "doctor.no_state_dir",
+        "neither XDG_STATE_HOME nor HOME is set, so no history is kept",
+    ),
+    ("doctor.set_home", "set HOME"),
+    (
+        "doctor.state_missing",
+        "{dir} does not exist yet (created on first use)",
+    ),
+    ("doctor.state_unwritable", "cannot write to {dir}: {error}"),
+    (
+        "doctor.check_permissions",
+        "check the owner and permissions of {dir}",
+    ),
+    (
+        "doctor.broken_history",
+        "{count} unreadable lines in {file}",
+    ),
+    (
+        "doctor.delete_history",
+        "delete the file to start a fresh checkout history",
+    ),
+    ("doctor.state_ok", "{dir} is writable"),
+    ("doctor.one_problem", "doctor found a problem"),
+    ("doctor.problems", "doctor found {count} problems"),
+    (
+        "usage.try_help",
+        "Try '{name} --help' for more information.",
+    ),
+    ("checkout.no_previous", "no previous branch in the reflog"),
+    ("cd.cannot_write", "cannot write {path}: {error}"),
+];
+
+const GERMAN: Catalog = &[
+    ("picker.title", "Letzten Branch wählen"),
+    ("picker.tag_title", "Letzten Tag wählen"),
+    (
+        "picker.delete_title",
+        "Branches löschen (Leertaste markiert, Enter löscht)",
+    ),
+    ("picker.detached", "HEAD losgelöst bei {commit}"),
+    ("picker.filter", "Filter: {filter}"),
+    (
+        "picker.limited",
+        "erste {shown} von {total}, / durchsucht alle",
+    ),
+    ("picker.less", "(weniger)"),
+    ("picker.more", "(mehr)"),
+    ("picker.no_matches", "(keine Treffer)"),
+    ("picker.merged", "(gemergt)"),
+    ("picker.protected", "(geschützt)"),
+    ("pull.open", "offen"),
+    ("pull.draft", "Entwurf"),
+    ("pull.approved", "genehmigt"),
+    ("pull.merged", "gemergt"),
+    ("pull.closed", "geschlossen"),
+    ("picker.return_to", "Enter kehrt zu '{branch}' zurück"),
+    (
+        "accessible.selected",
+        "ausgewählt: {branch} ({index} von {total})",
+    ),
+    ("accessible.current", "aktueller Branch"),
+    (
+        "accessible.other_worktree",
+        "in einem anderen Worktree ausgecheckt",
+    ),
+    ("accessible.marked", "markiert"),
+    ("accessible.protected", "geschützt"),
+    ("accessible.ahead", "{count} voraus"),
+    ("accessible.behind", "{count} zurück"),
+    ("accessible.merged", "gemergt"),
+    ("accessible.pull", "Pull-Request {pull}"),
+    ("scope.remote", "remote"),
+    ("scope.all", "alle"),
+    ("scope.empty", "Keine Branches ({scope})"),
+    (
+        "tags.not_a_branch",
+        "Tags lassen sich hier nur auschecken, abzweigen oder ansehen",
+    ),
+    ("prompt.filter", "Filter: "),
+    ("prompt.new_branch", "Neuer Branch von '{base}': "),
+    ("prompt.rename", "'{branch}' umbenennen in: "),
+    (
+        "prompt.branch_number",
+        "Branch-Nummer (leer zum Abbrechen): ",
+    ),
+    (
+        "prompt.repository_number",
+        "Repository-Nummer (leer zum Abbrechen): ",
+    ),
+    ("confirm.yes", "Ja"),
+    ("confirm.no", "Nein"),
+    ("menu.title", "Aktionen für '{branch}':"),
+    ("action.check_out", "Auschecken"),
+    ("action.create", "Neuen Branch davon erstellen"),
+    ("action.rename", "Umbenennen"),
+    ("action.delete", "Löschen"),
+    ("action.diff", "Mit dem aktuellen Branch vergleichen"),
+    ("action.log", "Log anzeigen"),
+    ("action.push", "Pushen"),
+    (
+        "action.pull_request",
+        "Pull-Request öffnen (oder erstellen)",
+    ),
+    ("action.yank", "Namen kopieren"),
+    ("reload.done", "{count} Branches neu geladen"),
+    ("create.done", "Branch '{name}' von '{base}' erstellt"),
+    ("alias.added", "'git {name}' hinzugefügt"),
+    ("alias.removed", "'git {name}' entfernt"),
+    ("alias.none", "Kein Git-Alias startet git-recent"),
+    (
+        "hook.installed",
+        "post-checkout-Hook in {path} eingerichtet",
+    ),
+    ("hook.removed", "post-checkout-Hook in {path} entfernt"),
+    (
+        "hook.none",
+        "Kein post-checkout-Hook von git-recent eingerichtet",
+    ),
+    (
+        "fzf.not_found",
+        "fzf ist nicht installiert oder nicht im PATH; --picker builtin nutzt die eingebaute Auswahl",
+    ),
+    ("fzf.failed", "fzf fehlgeschlagen: {status}"),
+    (
+        "tmux.not_running",
+        "--tmux-popup funktioniert nur innerhalb von tmux",
+    ),
+    (
+        "tmux.failed",
+        "tmux konnte kein Popup öffnen ({status}); display-popup braucht tmux 3.2 oder neuer",
+    ),
+    (
+        "rename.remote",
+        "Remote-Branch '{branch}' kann nicht umbenannt werden",
+    ),
+    (
+        "rename.protected",
+        "Geschützter Branch '{branch}' kann nicht umbenannt werden",
+    ),
+    ("rename.done", "'{from}' in '{to}' umbenannt"),
+    ("diff.current", "'{branch}' ist der aktuelle Branch"),
+    ("notify.checked_out", "Jetzt auf '{branch}'"),
+    ("notify.fetched", "Alle Remotes abgerufen"),
+    ("preview.title", "Änderungen auf '{branch}'"),
+    ("preview.position", "(Zeilen {first}–{last} von {total})"),
+    ("preview.empty", "'{branch}' enthält keine Änderungen"),
+    (
+        "preview.truncated",
+        "… nur die ersten {count} Zeilen werden angezeigt",
+    ),
+    (
+        "push.remote",
+        "Remote-Branch '{branch}' kann nicht gepusht werden",
+    ),
+    ("push.done", "'{branch}' gepusht"),
+    ("yank.done", "'{branch}' in die Zwischenablage kopiert"),
+    (
+        "pull.opened",
+        "Pull-Request von '{branch}' im Browser geöffnet",
+    ),
+    ("pull.started", "Pull-Request für '{branch}' begonnen"),
+    (
+        "pull.no_tool",
+        "{tool} ist nicht installiert oder nicht im PATH; es wird für Pull-Requests gebraucht",
+    ),
+    (
+        "delete.remote",
+        "Remote-Branch '{branch}' kann nicht gelöscht werden",
+    ),
+    (
+        "delete.current",
+        "Der aktuelle Branch '{branch}' kann nicht gelöscht werden",
+    ),
+    (
+        "delete.protected",
+        "Geschützter Branch '{branch}' kann nicht gelöscht werden",
+    ),
+    ("delete.confirm", "Branch '{branch}' löschen?"),
+    (
+        "delete.confirm_marked",
+        "{count} markierte Branches löschen?",
+    ),
+    ("delete.done", "Branch '{branch}' gelöscht"),
+    ("delete.done_many", "{count} Branches gelöscht"),
+    (
+        "delete.needs_picker",
+        "--delete braucht die interaktive Auswahl (ein Terminal, nicht --simple)",
+    ),
+    (
+        "delete.unmerged",
+        "Branch '{branch}' ist nicht vollständig gemergt. Trotzdem löschen?",
+    ),
+    (
+        "delete.unmerged_many",
+        "{count} Branches sind nicht vollständig gemergt. Trotzdem löschen?",
+    ),
+    ("checkout.confirm", "'{branch}' auschecken ({status})?"),
+    ("checkout.clean", "keine Änderungen"),
+    ("checkout.one_change", "1 nicht committete Änderung"),
+    ("checkout.changes", "{count} nicht committete Änderungen"),
+    ("checkout.running", "Branch wird ausgecheckt: {branch}"),
+    ("checkout.index_zero", "Branch-Nummern beginnen bei 1"),
+    (
+        "checkout.index_too_large",
+        "es gibt nur {count} letzte Branches, #{index} kann nicht ausgecheckt werden",
+    ),
+    ("checkout.no_such", "kein letzter Branch namens '{name}'"),
+    (
+        "checkout.did_you_mean",
+        "kein letzter Branch namens '{name}'; meinten Sie {suggestion}?",
+    ),
+    (
+        "checkout.did_you_mean_many",
+        "kein letzter Branch namens '{name}'; meinten Sie einen von {suggestions}?",
+    ),
+    ("list.empty", "Keine Branches gefunden"),
+    (
+        "config.untrusted",
+        "Warnung: {source}: '{key}' wird ignoriert; die Konfigurationsdatei eines Repositorys darf das nur mit trust_repo_config setzen (z. B. git config recent.trustRepoConfig true)",
+    ),
+    (
+        "config.unknown_git_key",
+        "Warnung: {source}: unbekannte Einstellung '{key}' wird ignoriert",
+    ),
+    ("list.no_tags", "Keine Tags gefunden"),
+    (
+        "chdir.failed",
+        "Wechsel nach '{dir}' nicht möglich: {error}",
+    ),
+    (
+        "exec.failed",
+        "'{command}' lässt sich nicht ausführen: {error}",
+    ),
+    (
+        "log_file.failed",
+        "die Log-Datei lässt sich nicht öffnen: {error}",
+    ),
+    (
+        "repositories.heading",
+        "Kein git-Repository. Zuletzt verwendete Repositories:",
+    ),
+    (
+        "repositories.no_such",
+        "kein zuletzt verwendetes Repository mit der Nummer '{answer}'",
+    ),
+    (
+        "fetch.failed",
+        "Warnung: {error}; Branches werden mit dem Stand des letzten Fetch angezeigt",
+    ),
+    ("error", "Fehler: {error}"),
+    (
+        "error.not_a_repository",
+        "kein git-Repository; git-recent in einem starten oder mit -C <Pfad> auf eines zeigen",
+    ),
+    (
+        "error.git_not_found",
+        "git ist nicht installiert oder nicht im PATH",
+    ),
+    (
+        "error.no_work_tree",
+        "dies ist ein Bare-Repository (oder ein .git-Verzeichnis) ohne Arbeitsverzeichnis; \
+         mit --list, --print oder --exec lässt sich ein Branch wählen",
+    ),
+    (
+        "terminal.raw_mode",
+        "das Terminal lässt sich nicht in den Raw-Modus schalten",
+    ),
+    (
+        "terminal.none",
+        "kein Terminal, um einen Branch zu wählen: {error}",
+    ),
+    ("git.failed", "git {command} ist fehlgeschlagen: {error}"),
+    (
+        "git.cannot_run",
+        "git kann nicht ausgeführt werden: {error}",
+    ),
+    (
+        "git.no_default_branch",
+        "der Standard-Branch lässt sich nicht bestimmen (kein origin/HEAD, main oder master)",
+    ),
+    (
+        "checkout.in_worktree",
+        "'{branch}' ist im Worktree unter {path} ausgecheckt; arbeiten Sie dort daran",
+    ),
+    (
+        "checkout.stash_first",
+        "{error}; committen oder stashen Sie zuerst Ihre Änderungen",
+    ),
+    ("lock.age", " (angelegt {ago})"),
+    (
+        "lock.held",
+        "'{path}' existiert{age}; ein anderer git-Befehl verwendet das Repository ({owner}), warten Sie, bis er fertig ist",
+    ),
+    (
+        "lock.left",
+        "'{path}' existiert{age}; läuft kein anderer git-Befehl, hat ein abgestürzter sie hinterlassen: entfernen Sie sie mit rm '{path}'",
+    ),
+    ("progress.loading", "Branches werden geladen..."),
+    ("progress.checking_out", "{branch} wird ausgecheckt..."),
+    (
+        "progress.waiting_for_lock",
+        "Warten, bis die Sperre aufgehoben ist (Strg-C zum Aufgeben)...",
+    ),
+    (
+        "progress.adding_worktree",
+        "Worktree für {branch} wird angelegt...",
+    ),
+    ("progress.creating", "{branch} wird erstellt..."),
+    ("progress.renaming", "{branch} wird umbenannt..."),
+    ("progress.deleting", "{branch} wird gelöscht..."),
+    ("progress.pushing", "{branch} wird gepusht..."),
+    ("progress.switching", "Wechsel zu {branch}..."),
+    ("progress.adding_alias", "'git {name}' wird hinzugefügt..."),
+    ("progress.removing_alias", "'git {name}' wird entfernt..."),
+    (
+        "hooks.cannot_run",
+        "hooks.{name} '{command}' kann nicht ausgeführt werden: {error}",
+    ),
+    ("hooks.failed", "hooks.{name} ist fehlgeschlagen: {status}"),
+    (
+        "jj.not_installed",
+        "jj ist nicht installiert oder nicht im PATH; setzen Sie jj = false, um mit git auszuchecken",
+    ),
+    ("jj.cannot_run", "jj kann nicht ausgeführt werden: {error}"),
+    ("jj.failed", "jj new ist fehlgeschlagen: {status}"),
+    (
+        "clipboard.terminal_failed",
+        "in das Terminal kann nicht geschrieben werden: {error}",
+    ),
+    (
+        "clipboard.no_tool",
+        "kein Programm für die Zwischenablage gefunden ({tools}); setzen Sie clipboard.osc52 = true, um über das Terminal zu kopieren",
+    ),
+    (
+        "alias.taken",
+        "'git {name}' ist bereits ein Alias für '{current}'; entfernen Sie ihn mit 'git config --global --unset alias.{name}' oder wählen Sie ein anderes Kürzel",
+    ),
+    (
+        "config.unknown_profile",
+        "unbekanntes Profil '{name}' (definiert: {known})",
+    ),
+    (
+        "config.no_profiles",
+        "unbekanntes Profil '{name}' (es gibt keine [profile.<name>]-Abschnitte)",
+    ),
+    (
+        "config.cannot_read",
+        "{path} kann nicht gelesen werden: {error}",
+    ),
+    (
+        "config.profile_not_table",
+        "profile.{key} muss eine Tabelle von Einstellungen sein",
+    ),
+    (
+        "config.profile_in_profile",
+        "ein Profil kann kein anderes Profil auswählen",
+    ),
+    ("config.unknown_setting", "unbekannte Einstellung '{key}'"),
+    ("config.wrong_kind", "'{key}' muss {kind} sein"),
+    (
+        "config.wrong_kind_text",
+        "{key} muss {kind} sein, nicht '{text}'",
+    ),
+    ("config.kind_string", "eine Zeichenkette"),
+    ("config.kind_integer", "eine ganze Zahl"),
+    ("config.kind_boolean", "true oder false"),
+    ("config.kind_strings", "eine Liste von Zeichenketten"),
+    ("config.invalid", "ungültige Einstellung '{key}': {message}"),
+    (
+        "config.invalid_in",
+        "ungültige Einstellung '{key}' in {source}: {message}",
+    ),
+    (
+        "config.unknown_sort",
+        "unbekannter Sortierschlüssel '{name}' (erwartet: {names})",
+    ),
+    (
+        "config.unknown_case",
+        "unbekannte Schreibweise '{name}' (erwartet: {names})",
+    ),
+    (
+        "config.unknown_picker",
+        "unbekannte Auswahl '{name}' (erwartet: {names})",
+    ),
+    ("config.negative", "darf nicht negativ sein"),
+    ("config.below_one", "muss mindestens 1 sein"),
+    ("config.unknown_key", "unbekannte Taste '{key}'"),
+    (
+        "config.exec_missing",
+        "'exec' braucht die Einstellung 'exec'",
+    ),
+    (
+        "config.unknown_action",
+        "unbekannte Aktion '{action}' (erwartet: checkout, print, worktree oder exec)",
+    ),
+    (
+        "config.unknown_color",
+        "unbekannte Farbe '{name}' für colors.{slot} (erwartet: {names}, wahlweise mit 'bright-' davor, eine Zahl von 0 bis 255 oder #rrggbb)",
+    ),
+    ("config.unknown_slot", "unbekannter Farbplatz '{slot}'"),
+    ("toml.expected", "{wanted} erwartet, {found} gefunden"),
+    ("toml.a_key", "Schlüssel"),
+    ("toml.a_value", "Wert"),
+    ("toml.comma_or_bracket", "',' oder ']' im Array"),
+    ("toml.end_of_line", "Zeilenende"),
+    ("toml.end_of_file", "Dateiende"),
+    ("toml.after_value", "unerwartet {found} nach dem Wert"),
+    (
+        "toml.table_arrays",
+        "Arrays von Tabellen werden nicht unterstützt",
+    ),
+    ("toml.set_twice", "'{key}' ist doppelt gesetzt"),
+    (
+        "toml.unsupported_value",
+        "nicht unterstützter Wert '{value}'",
+    ),
+    ("toml.unterminated_array", "nicht abgeschlossenes Array"),
+    (
+        "toml.unterminated_string",
+        "nicht abgeschlossene Zeichenkette",
+    ),
+    (
+        "toml.multi_line",
+        "mehrzeilige Zeichenketten werden nicht unterstützt",
+    ),
+    ("toml.invalid_escape", "ungültige Escape-Sequenz '{escape}'"),
+    (
+        "toml.invalid_unicode",
+        "ungültige Unicode-Escape-Sequenz '{escape}'",
+    ),
+    ("colors.basic", "8 Farben"),
+    ("colors.indexed", "256 Farben"),
+    ("colors.true_color", "24-Bit-Farbe"),
+    ("doctor.fix", "Abhilfe"),
+    (
+        "doctor.git_failed",
+        "'git --version' ist fehlgeschlagen: {status}",
+    ),
+    ("doctor.reinstall_git", "installieren Sie git neu"),
+    (
+        "doctor.install_git",
+        "installieren Sie git und sorgen Sie dafür, dass es im PATH liegt",
+    ),
+    ("doctor.git_too_old", "{version} ist zu alt"),
+    (
+        "doctor.upgrade_git",
+        "aktualisieren Sie auf git {version} oder neuer",
+    ),
+    ("doctor.locked", "Checkouts sind blockiert: {lock}"),
+    (
+        "doctor.wait_for_lock",
+        "warten Sie auf den anderen git-Befehl, oder entfernen Sie die Sperrdatei, falls keiner läuft",
+    ),
+    ("doctor.worktree", "Worktree unter {path}"),
+    (
+        "doctor.bare",
+        "Bare-Repository: Branches lassen sich auflisten, aber nicht auschecken",
+    ),
+    (
+        "doctor.use_bare",
+        "verwenden Sie hier --list, --print oder --exec, oder action = \"worktree\"",
+    ),
+    ("doctor.not_a_repository", "nicht in einem git-Repository"),
+    (
+        "doctor.find_repository",
+        "starten Sie git-recent in einem, oder verweisen Sie mit -C <Pfad> darauf",
+    ),
+    ("doctor.check_git", "prüfen Sie, ob git hier funktioniert"),
+    (
+        "doctor.stdin_not_terminal",
+        "stdin ist kein Terminal, daher wird die nummerierte Eingabe verwendet",
+    ),
+    (
+        "doctor.use_terminal",
+        "starten Sie git-recent in einem interaktiven Terminal, um die Vollbild-Auswahl zu bekommen",
+    ),
+    (
+        "doctor.use_regular_terminal",
+        "starten Sie git-recent in einem normalen Terminal, oder verwenden Sie --simple",
+    ),
+    (
+        "doctor.input_ok",
+        "stdin ist ein Terminal und der Raw-Modus funktioniert",
+    ),
+    (
+        "doctor.term_not_set",
+        "TERM ist nicht gesetzt, daher wird die nummerierte Eingabe ohne Farben verwendet",
+    ),
+    (
+        "doctor.term_dumb",
+        "TERM ist dumb, daher wird die nummerierte Eingabe ohne Farben verwendet",
+    ),
+    (
+        "doctor.set_term",
+        "setzen Sie TERM auf den Typ Ihres Terminals, z. B. TERM=xterm-256color",
+    ),
+    (
+        "doctor.terminal_ok",
+        "TERM={term}, {colors}; kein alternativer Bildschirm nötig",
+    ),
+    (
+        "doctor.fix_setting",
+        "korrigieren Sie die oben genannte Einstellung; 'git-recent config' zeigt alle Einstellungen",
+    ),
+    (
+        "doctor.untrusted",
+        "entfernen Sie die Einstellung aus der Datei des Repositorys, oder vertrauen Sie ihr mit trust_repo_config",
+    ),
+    (
+        "doctor.no_config",
+        "keine Konfigurationsdateien; es gelten die Standardwerte",
+    ),
+    ("doctor.read_config", "gelesen: {files}"),
+    (
+        "doctor.no_state_dir",
+        "weder XDG_STATE_HOME noch HOME ist gesetzt, daher wird kein Verlauf geführt",
+    ),
+    ("doctor.set_home", "setzen Sie HOME"),
+    (
+        "doctor.state_missing",
+        "{dir} existiert noch nicht (wird bei der ersten Verwendung angelegt)",
+    ),
+    (
+        "doctor.state_unwritable",
+        "in {dir} kann nicht geschrieben werden: {error}",
+    ),
+    (
+        "doctor.check_permissions",
+        "prüfen Sie Eigentümer und Rechte von {dir}",
+    ),
+    (
+        "doctor.broken_history",
+        "{count} unlesbare Zeilen in {file}",
+    ),
+    (
+        "doctor.delete_history",
+        "löschen Sie die Datei, um einen neuen Checkout-Verlauf zu beginnen",
+    ),
+    ("doctor.state_ok", "{dir} ist beschreibbar"),
+    ("doctor.one_problem", "doctor hat ein Problem gefunden"),
+    ("doctor.problems", "doctor hat {count} Probleme gefunden"),
+    (
+        "usage.try_help",
+        "Weitere Informationen erhalten Sie mit '{name} --help'.",
+    ),
+    ("checkout.no_previous", "kein vorheriger Branch im Reflog"),
+    (
+        "cd.cannot_write",
+        "{path} kann nicht geschrieben werden: {error}",
+    ),
+];
+
+/// Translations by language code (the part of the locale before `_`).
+const TRANSLATIONS: &[(&str, Catalog)] = &[("de", GERMAN)];
+
+static LANGUAGE: OnceLock<Option<Catalog>> = OnceLock::new();
+
+/// Use `language` (such as `de`, or `en` for no translation) instead of the one the
+/// environment names. Only works before the first text is looked up.
+pub fn set_language(language: &str) {
+    let _ = LANGUAGE.set(translation(language));
+}
+
+/// The language code of the user's locale, such as `de` for `de_DE.UTF-8`; `None`
+/// when no locale is set or it is `C`/`POSIX`.
+fn locale_language() -> Option<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())?;
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+    match language {
+        "" | "C" | "POSIX" => None,
+        language => Some(language.to_ascii_lowercase()),
+    }
+}
+
+fn translation(language: &str) -> Option<Catalog> {
+    TRANSLATIONS
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map(|&(_, catalog)| catalog)
+}
+
+fn lookup(catalog: Catalog, id: &str) -> Option<&'static str> {
+    catalog
+        .iter()
+        .find(|(key, _)| *key == id)
+        .map(|&(_, text)| text)
+}
+
+/// The text of message `id` in the user's language, or in English if there is no
+/// translation for it.
+pub fn text(id: &'static str) -> &'static str {
+    let translated = LANGUAGE
+        .get_or_init(|| translation(&locale_language()?))
+        .and_then(|catalog| lookup(catalog, id));
+    // An unknown id shows up as itself rather than failing.
+    translated.or_else(|| lookup(ENGLISH, id)).unwrap_or(id)
+}
+
+/// The text of message `id` with each `{name}` placeholder replaced by its value
+/// in `args`.
+pub fn message(id: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(text(id), args)
+}
+
+/// `text` with each `{name}` placeholder replaced by its value in `args`, in one
+/// pass: values are never searched for placeholders themselves, since a branch name
+/// such as `x{error}` is valid. Braces that name no argument are kept.
+pub fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &rest[1..end])?;
+            Some((end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                out.push_str(&value.to_string());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `text` spelled with ASCII characters only, for terminals that show nothing else
+/// (`--ascii`): umlauts become "ae", "oe" and "ue", dashes, quotes and ellipses their
+/// plain forms, and anything else "?".
+pub fn to_ascii(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c.is_ascii() => out.push(c),
+            'ä' => out.push_str("ae"),
+            'ö' => out.push_str("oe"),
+            'ü' => out.push_str("ue"),
+            'Ä' => out.push_str("Ae"),
+            'Ö' => out.push_str("Oe"),
+            'Ü' => out.push_str("Ue"),
+            'ß' => out.push_str("ss"),
+            '–' | '—' => out.push('-'),
+            '‘' | '’' | '‚' => out.push('\''),
+            '“' | '”' | '„' => out.push('"'),
+            '…' => out.push_str("..."),
+            _ => out.push('?'),
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_not_searched_for_placeholders() {
+        let branch = "x{error}";
+        assert_eq!(
+            fill(
+                "cannot delete '{branch}': {error}",
+                &[("branch", &branch), ("error", &"not merged")],
+            ),
+            "cannot delete 'x{error}': not merged"
+        );
+    }
+
+    #[test]
+    fn unknown_and_unclosed_braces_are_kept() {
+        assert_eq!(fill("{a} {b} {a", &[("a", &1)]), "1 {b} {a");
+    }
+}
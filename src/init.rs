@@ -0,0 +1,119 @@
+//! `git-recent init <SHELL>`: a shell function wrapping git-recent, so choosing a
+//! worktree (`action = "worktree"`) also moves the shell there. A program cannot
+//! change its parent's directory, so the function passes `--cd-file`, and git-recent
+//! writes a `cd` command into that file for the function to run afterwards. The
+//! scripts also bind a key to a widget that inserts a chosen branch's name into the
+//! command line.
+
+use crate::cli::NAME;
+use crate::completions::Shell;
+use crate::git::shell_quote;
+
+/// Shells `init` writes a function for. PowerShell is missing because the `cd`
+/// command in the file is quoted for POSIX shells.
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// The `cd` command `--cd-file` gets for `path`, quoted for POSIX shells. fish reads
+/// the same quoting back unchanged unless the path holds a backslash.
+pub fn cd_directive(path: &str) -> String {
+    format!("cd {}\n", shell_quote(path))
+}
+
+/// The key sequence the widgets are bound to, as each shell writes it.
+const BASH_KEY: &str = r"\C-g\C-b";
+const ZSH_KEY: &str = "^G^B";
+const FISH_KEY: &str = r"\cg\cb";
+
+/// The function for `shell`, followed by a widget that opens the picker with
+/// `--print` on Ctrl-G Ctrl-B and inserts the chosen branch at the cursor; `None` for
+/// shells not in `SHELLS`.
+pub fn script(shell: Shell) -> Option<String> {
+    match shell {
+        // zsh reserves `status`, so the exit status is kept in `ret` in both.
+        Shell::Bash | Shell::Zsh => {
+            let (rc, name) = if shell == Shell::Bash {
+                ("bashrc", "bash")
+            } else {
+                ("zshrc", "zsh")
+            };
+            let function = format!(
+                r#"# {NAME} shell integration; add to ~/.{rc}: eval "$({NAME} init {name})"
+
+{NAME}() {{
+    local cd_file ret
+    cd_file="$(mktemp "${{TMPDIR:-/tmp}}/{NAME}.XXXXXX")" || return
+    command {NAME} --cd-file "$cd_file" "$@"
+    ret=$?
+    if [ -s "$cd_file" ]; then
+        . "$cd_file"
+    fi
+    rm -f "$cd_file"
+    return $ret
+}}
+"#
+            );
+            let widget = if shell == Shell::Bash {
+                format!(
+                    r#"
+# Ctrl-G Ctrl-B: choose a branch and insert its name at the cursor.
+__git_recent_widget() {{
+    local branch
+    branch="$(command {NAME} --print)" || return
+    branch="$(printf '%q' "$branch")"
+    READLINE_LINE="${{READLINE_LINE:0:READLINE_POINT}}$branch${{READLINE_LINE:READLINE_POINT}}"
+    READLINE_POINT=$((READLINE_POINT + ${{#branch}}))
+}}
+if [[ $- == *i* ]]; then
+    bind -x '"{BASH_KEY}": __git_recent_widget'
+fi
+"#
+                )
+            } else {
+                format!(
+                    r#"
+# Ctrl-G Ctrl-B: choose a branch and insert its name at the cursor.
+__git_recent_widget() {{
+    local branch
+    branch="$(command {NAME} --print </dev/tty)" && LBUFFER+="${{(q-)branch}}"
+    zle reset-prompt
+}}
+if [[ -o interactive ]]; then
+    zle -N __git_recent_widget
+    bindkey '{ZSH_KEY}' __git_recent_widget
+fi
+"#
+                )
+            };
+            Some(function + &widget)
+        }
+        Shell::Fish => Some(format!(
+            r#"# {NAME} shell integration; add to ~/.config/fish/config.fish:
+# {NAME} init fish | source
+
+function {NAME} --wraps {NAME}
+    set -l cd_file (mktemp)
+    or return
+    command {NAME} --cd-file $cd_file $argv
+    set -l ret $status
+    if test -s $cd_file
+        source $cd_file
+    end
+    rm -f $cd_file
+    return $ret
+end
+
+# Ctrl-G Ctrl-B: choose a branch and insert its name at the cursor.
+function __git_recent_widget
+    set -l branch (command {NAME} --print </dev/tty)
+    and commandline -i -- (string escape -- $branch)
+    commandline -f repaint
+end
+if status is-interactive
+    bind {FISH_KEY} __git_recent_widget
+    bind -M insert {FISH_KEY} __git_recent_widget 2>/dev/null
+end
+"#
+        )),
+        Shell::PowerShell => None,
+    }
+}
@@ -0,0 +1,75 @@
+//! Jujutsu (`jj`) in colocated repositories, where `.jj` sits next to `.git`. jj keeps
+//! bookmarks (its branches) as git branches, but writes them out only when a `jj`
+//! command runs, and it expects the working copy to be moved with `jj new` rather
+//! than `git checkout`, which leaves jj's view of it behind. With `jj` on (detected by
+//! default), git-recent exports the bookmarks before listing and switches with
+//! `jj new <bookmark>`.
+
+use std::error::Error;
+use std::io::{self, Write};
+use std::process::Command;
+
+use crate::git::{self, shell_quote};
+use crate::i18n;
+use crate::refname;
+use crate::spinner::with_spinner;
+use crate::trace;
+
+/// Whether the repository is a colocated jj repository: `.jj` is next to its `.git`.
+/// Linked git worktrees are never jj workspaces.
+pub fn colocated() -> bool {
+    git::pinned_git_dir()
+        .filter(|dir| dir.file_name().is_some_and(|name| name == ".git"))
+        .and_then(|dir| dir.parent())
+        .is_some_and(|top| top.join(".jj").is_dir())
+}
+
+/// Write jj's bookmarks to git branches (`jj git export`), so bookmarks made since the
+/// last `jj` command are listed too. Failures only leave the listing as git has it.
+pub fn export_bookmarks() {
+    let args = ["git", "export", "--ignore-working-copy"];
+    if git::skip_for_dry_run(format!("jj {}", args.join(" "))) {
+        return;
+    }
+    match trace::output(Command::new("jj").args(args)) {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => trace::note(format_args!(
+            "jj git export failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => trace::note(format_args!("cannot run jj: {e}")),
+    }
+}
+
+/// Start a new change on top of `bookmark` or tag (`jj new`), jj's way of switching to it.
+/// As with `git::checkout`, jj's output is shown unless `quiet` and it succeeds.
+pub fn new_change(bookmark: &str, quiet: bool) -> Result<(), Box<dyn Error>> {
+    // A string literal in a revset names the bookmark exactly, whatever its characters.
+    let revset = format!(
+        "\"{}\"",
+        bookmark.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    if git::skip_for_dry_run(format!("jj new {}", shell_quote(&revset))) {
+        return Ok(());
+    }
+    let output = with_spinner(
+        &i18n::message(
+            "progress.switching",
+            &[("branch", &refname::display(bookmark))],
+        ),
+        || trace::output(Command::new("jj").arg("new").arg(refname::to_os(&revset))),
+    )
+    .map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => i18n::text("jj.not_installed").to_string(),
+        _ => i18n::message("jj.cannot_run", &[("error", &e)]),
+    })?;
+    if quiet && output.status.success() {
+        return Ok(());
+    }
+    io::stdout().write_all(&output.stdout)?;
+    io::stderr().write_all(&output.stderr)?;
+    if !output.status.success() {
+        return Err(i18n::message("jj.failed", &[("status", &output.status)]).into());
+    }
+    Ok(())
+}
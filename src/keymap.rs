@@ -0,0 +1,245 @@
+use crate::keys::Key;
+
+/// Something a key can be bound to in the branch list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    First,
+    Last,
+    PageUp,
+    PageDown,
+    /// Confirm the highlighted branch (in delete mode: delete the marked ones).
+    Select,
+    /// Mark or unmark the highlighted branch in delete mode.
+    Mark,
+    Cancel,
+    Filter,
+    Create,
+    Rename,
+    Delete,
+    Reload,
+    ToggleScope,
+    Repaint,
+    /// Open the menu of actions for the highlighted branch.
+    Menu,
+    /// Show the highlighted branch's changes against the current branch.
+    Diff,
+    Log,
+    Push,
+    /// Open the highlighted branch's pull request in the browser, or start one.
+    PullRequest,
+    /// Copy the highlighted branch's name to the clipboard.
+    Yank,
+    /// Show or hide the diff of the highlighted branch below the list.
+    Preview,
+    /// Scroll the diff preview by half its height.
+    PreviewUp,
+    PreviewDown,
+}
+
+impl Action {
+    pub const ALL: &[Action] = &[
+        Action::Up,
+        Action::Down,
+        Action::First,
+        Action::Last,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Select,
+        Action::Mark,
+        Action::Cancel,
+        Action::Filter,
+        Action::Create,
+        Action::Rename,
+        Action::Delete,
+        Action::Reload,
+        Action::ToggleScope,
+        Action::Repaint,
+        Action::Menu,
+        Action::Diff,
+        Action::Log,
+        Action::Push,
+        Action::PullRequest,
+        Action::Yank,
+        Action::Preview,
+        Action::PreviewUp,
+        Action::PreviewDown,
+    ];
+
+    /// The name used for `keys.<name>` in the config file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::PageUp => "page_up",
+            Action::PageDown => "page_down",
+            Action::Select => "select",
+            Action::Mark => "mark",
+            Action::Cancel => "cancel",
+            Action::Filter => "filter",
+            Action::Create => "create",
+            Action::Rename => "rename",
+            Action::Delete => "delete",
+            Action::Reload => "reload",
+            Action::ToggleScope => "toggle_scope",
+            Action::Repaint => "repaint",
+            Action::Menu => "menu",
+            Action::Diff => "diff",
+            Action::Log => "log",
+            Action::Push => "push",
+            Action::PullRequest => "pull_request",
+            Action::Yank => "yank",
+            Action::Preview => "preview",
+            Action::PreviewUp => "preview_up",
+            Action::PreviewDown => "preview_down",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|a| a.name() == name)
+    }
+
+    pub fn default_keys(self) -> Vec<Key> {
+        use Key::*;
+        match self {
+            Action::Up => vec![Up, Char('k'), Char('w'), Ctrl('p')],
+            Action::Down => vec![Down, Char('j'), Char('s'), Ctrl('n')],
+            Action::First => vec![Home],
+            Action::Last => vec![End],
+            Action::PageUp => vec![PageUp],
+            Action::PageDown => vec![PageDown],
+            Action::Select => vec![Enter, Char(' ')],
+            Action::Mark => vec![Char(' ')],
+            Action::Cancel => vec![Ctrl('g'), Char('q'), Char('Q'), Esc],
+            Action::Filter => vec![Char('/')],
+            Action::Create => vec![Char('n')],
+            Action::Rename => vec![Char('m')],
+            Action::Delete => vec![Char('x'), Delete],
+            Action::Reload => vec![Char('r'), F(5)],
+            Action::ToggleScope => vec![Tab],
+            Action::Repaint => vec![Ctrl('l')],
+            Action::Menu => vec![Char('a')],
+            Action::Yank => vec![Char('y')],
+            Action::Preview => vec![Char('p')],
+            Action::PreviewUp => vec![Ctrl('u')],
+            Action::PreviewDown => vec![Ctrl('d')],
+            // Reachable from the menu; bind keys in the config file if wanted.
+            Action::Diff | Action::Log | Action::Push | Action::PullRequest => vec![],
+        }
+    }
+}
+
+/// Key bindings for the branch list. Ctrl-C always interrupts and is not listed.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    /// Earlier bindings win when a key is bound to more than one action.
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .flat_map(|&action| action.default_keys().into_iter().map(move |k| (k, action)))
+            .collect();
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// Replace the keys of `action`. The new keys take precedence over other actions
+    /// they were already bound to.
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        self.bindings.retain(|(_, a)| *a != action);
+        let new = keys.into_iter().map(|k| (k, action));
+        self.bindings.splice(0..0, new);
+    }
+
+    /// The action `key` triggers, if any.
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn is_bound(&self, action: Action, key: &Key) -> bool {
+        self.bindings.iter().any(|(k, a)| k == key && *a == action)
+    }
+}
+
+/// The config-file name of `key`, as accepted by `parse_key`.
+pub fn key_name(key: &Key) -> String {
+    match key {
+        Key::Char(' ') => "space".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("ctrl-{c}"),
+        Key::Alt(c) => format!("alt-{c}"),
+        Key::F(n) => format!("f{n}"),
+        Key::Enter => "enter".to_string(),
+        Key::Tab => "tab".to_string(),
+        Key::BackTab => "backtab".to_string(),
+        Key::Esc => "esc".to_string(),
+        Key::Backspace => "backspace".to_string(),
+        Key::Delete => "delete".to_string(),
+        Key::Insert => "insert".to_string(),
+        Key::Up => "up".to_string(),
+        Key::Down => "down".to_string(),
+        Key::Left => "left".to_string(),
+        Key::Right => "right".to_string(),
+        Key::Home => "home".to_string(),
+        Key::End => "end".to_string(),
+        Key::PageUp => "pageup".to_string(),
+        Key::PageDown => "pagedown".to_string(),
+        Key::Paste(_) => "paste".to_string(),
+    }
+}
+
+/// Parse a key name from the config file: a single character (`"k"`), a named key
+/// (`"enter"`, `"space"`, `"tab"`, `"esc"`, `"up"`, `"pageup"`, `"f5"`, ...) or a
+/// chord (`"ctrl-p"`, `"alt-x"`).
+pub fn parse_key(spec: &str) -> Option<Key> {
+    let mut chars = spec.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+    let lower = spec.to_ascii_lowercase();
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = lower.strip_prefix("ctrl-") {
+        return single(rest).filter(char::is_ascii_lowercase).map(Key::Ctrl);
+    }
+    if let Some(rest) = spec.get(4..).filter(|_| lower.starts_with("alt-")) {
+        return single(rest).map(Key::Alt);
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        return (1..=12).contains(&n).then_some(Key::F(n));
+    }
+    Some(match lower.as_str() {
+        "space" => Key::Char(' '),
+        "enter" | "return" => Key::Enter,
+        "tab" => Key::Tab,
+        "backtab" | "shift-tab" => Key::BackTab,
+        "esc" | "escape" => Key::Esc,
+        "backspace" => Key::Backspace,
+        "delete" | "del" => Key::Delete,
+        "insert" => Key::Insert,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" | "pgup" => Key::PageUp,
+        "pagedown" | "pgdn" => Key::PageDown,
+        _ => return None,
+    })
+}
@@ -0,0 +1,348 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::terminal;
+
+/// How long to wait for the rest of an escape sequence before treating ESC as a keypress.
+const ESC_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long to wait for the terminal to answer capability queries at startup.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Query for the kitty keyboard protocol, followed by a primary device attributes
+/// request that every terminal answers, so lack of support shows up without a timeout.
+const KITTY_QUERY: &str = "\x1b[?u\x1b[c";
+
+/// Bracketed paste markers wrapped around pasted text.
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Kitty modifier bits (the protocol transmits `1 + bits`).
+const MOD_ALT: u32 = 0b10;
+const MOD_CTRL: u32 = 0b100;
+/// Kitty event type for key release.
+const EVENT_RELEASE: &str = "3";
+
+/// A decoded keypress.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    /// Control chord such as Ctrl-C, stored as the lowercase letter.
+    Ctrl(char),
+    /// Alt (Meta) chord, e.g. Alt-x.
+    Alt(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    /// Function key F1-F12.
+    F(u8),
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Esc,
+    /// Text delivered through bracketed paste, never interpreted as keystrokes.
+    Paste(String),
+}
+
+/// Result of trying to decode the front of the input buffer.
+enum Decoded {
+    Key(Key, usize),
+    /// Recognized but meaningless input (e.g. an unknown CSI sequence) to be dropped.
+    Skip(usize),
+    /// A sequence that may still be completed by more bytes.
+    Incomplete,
+}
+
+/// Incremental decoder that turns raw terminal bytes into keys.
+/// Bytes arrive in arbitrary chunks, so sequences are only decoded once complete.
+#[derive(Default)]
+pub struct KeyReader {
+    buffer: Vec<u8>,
+}
+
+impl KeyReader {
+    pub fn new() -> Self {
+        KeyReader { buffer: Vec::new() }
+    }
+
+    /// Block until a key is available and return it.
+    /// Returns `None` for input that does not map to a known key.
+    pub fn read_key(&mut self) -> io::Result<Option<Key>> {
+        loop {
+            match decode(&self.buffer) {
+                Decoded::Key(key, len) => {
+                    self.buffer.drain(..len);
+                    return Ok(Some(key));
+                }
+                Decoded::Skip(len) => {
+                    self.buffer.drain(..len);
+                    return Ok(None);
+                }
+                Decoded::Incomplete => {}
+            }
+
+            // A partial sequence only gets a short grace period; a lone ESC is a keypress.
+            // Pastes can legitimately trickle in slowly, so they wait for their end marker.
+            let pasting = self.buffer.starts_with(PASTE_START);
+            let timeout = (!self.buffer.is_empty() && !pasting).then_some(ESC_TIMEOUT);
+            if !terminal::poll_input(timeout)? {
+                return Ok(Some(self.flush_partial()));
+            }
+
+            let mut chunk = [0u8; 64];
+            let n = terminal::read_input(&mut chunk)?;
+            if n == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "input closed"));
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// Whether bytes already read are waiting to be decoded, which `read_key` returns
+    /// without touching stdin.
+    pub fn has_buffered_input(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// Ask the terminal whether it supports the kitty keyboard protocol.
+    /// Answers are consumed here; any keys typed meanwhile stay buffered.
+    pub fn detect_kitty_protocol(&mut self) -> io::Result<bool> {
+        let mut out = terminal::ui();
+        write!(out, "{KITTY_QUERY}")?;
+        out.flush()?;
+
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        let mut supported = false;
+        loop {
+            while let Some((range, final_byte)) = find_query_response(&self.buffer) {
+                self.buffer.drain(range);
+                match final_byte {
+                    b'u' => supported = true,
+                    // Device attributes always come last: the terminal has answered everything.
+                    _ => return Ok(supported),
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || !terminal::poll_input(Some(remaining))? {
+                return Ok(supported);
+            }
+            let mut chunk = [0u8; 64];
+            let n = terminal::read_input(&mut chunk)?;
+            if n == 0 {
+                return Ok(supported);
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+
+    /// Resolve a timed-out partial sequence: the leading ESC becomes a key of its own
+    /// and anything after it is decoded afresh on the next call.
+    fn flush_partial(&mut self) -> Key {
+        let first = self.buffer.remove(0);
+        if first == 27 {
+            Key::Esc
+        } else {
+            // A truncated UTF-8 sequence; drop the rest of it too.
+            self.buffer.retain(|b| b & 0xC0 != 0x80);
+            Key::Char(char::REPLACEMENT_CHARACTER)
+        }
+    }
+}
+
+fn decode(bytes: &[u8]) -> Decoded {
+    let Some(&first) = bytes.first() else {
+        return Decoded::Incomplete;
+    };
+    let key = match first {
+        27 => return decode_escape(bytes),
+        10 | 13 => Key::Enter,
+        9 => Key::Tab,
+        8 | 127 => Key::Backspace,
+        1..=26 => Key::Ctrl((b'a' + first - 1) as char),
+        0..=31 => return Decoded::Skip(1),
+        _ => return decode_utf8(bytes),
+    };
+    Decoded::Key(key, 1)
+}
+
+fn decode_utf8(bytes: &[u8]) -> Decoded {
+    let len = match bytes[0] {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return Decoded::Skip(1),
+    };
+    if bytes.len() < len {
+        return Decoded::Incomplete;
+    }
+    match std::str::from_utf8(&bytes[..len]) {
+        Ok(s) => match s.chars().next() {
+            Some(c) => Decoded::Key(Key::Char(c), len),
+            None => Decoded::Skip(len),
+        },
+        Err(_) => Decoded::Skip(1),
+    }
+}
+
+fn decode_escape(bytes: &[u8]) -> Decoded {
+    if bytes.starts_with(PASTE_START) {
+        return decode_paste(bytes);
+    }
+    match bytes.get(1) {
+        None => Decoded::Incomplete,
+        Some(b'[') => decode_csi(bytes),
+        Some(b'O') => match bytes.get(2) {
+            None => Decoded::Incomplete,
+            Some(&b) => match ss3_key(b) {
+                Some(key) => Decoded::Key(key, 3),
+                None => Decoded::Skip(3),
+            },
+        },
+        // ESC immediately followed by a printable character is how legacy terminals send Alt.
+        Some(&b) if (0x21..0x7F).contains(&b) => Decoded::Key(Key::Alt(b as char), 2),
+        // ESC followed by anything else: report the ESC and decode the rest separately.
+        Some(_) => Decoded::Key(Key::Esc, 1),
+    }
+}
+
+fn decode_paste(bytes: &[u8]) -> Decoded {
+    let content = &bytes[PASTE_START.len()..];
+    match content
+        .windows(PASTE_END.len())
+        .position(|w| w == PASTE_END)
+    {
+        Some(end) => {
+            let text = String::from_utf8_lossy(&content[..end]).into_owned();
+            Decoded::Key(Key::Paste(text), PASTE_START.len() + end + PASTE_END.len())
+        }
+        None => Decoded::Incomplete,
+    }
+}
+
+/// Keys sent as `ESC O <final>` (application cursor mode and F1-F4).
+fn ss3_key(final_byte: u8) -> Option<Key> {
+    let key = match final_byte {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        b'P' => Key::F(1),
+        b'Q' => Key::F(2),
+        b'R' => Key::F(3),
+        b'S' => Key::F(4),
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Decode a CSI sequence: `ESC [`, parameter bytes, intermediate bytes, final byte.
+fn decode_csi(bytes: &[u8]) -> Decoded {
+    let body = &bytes[2..];
+    let Some(end) = body.iter().position(|b| (0x40..=0x7E).contains(b)) else {
+        // Still waiting for the final byte, unless this is clearly not a CSI sequence.
+        return if body.iter().all(|b| (0x20..=0x3F).contains(b)) {
+            Decoded::Incomplete
+        } else {
+            Decoded::Skip(2)
+        };
+    };
+    let len = 2 + end + 1;
+    let params = std::str::from_utf8(&body[..end]).unwrap_or("");
+    if params.starts_with(['?', '<', '>', '=']) {
+        // Private sequences are replies to queries (or mouse reports), never keys.
+        return Decoded::Skip(len);
+    }
+    let mut fields = params.split(';');
+    let first_param = fields.next().unwrap_or("");
+    let (modifiers, event) = parse_modifiers(fields.next().unwrap_or(""));
+    if event == EVENT_RELEASE {
+        return Decoded::Skip(len);
+    }
+    // Modifiers on navigation keys (e.g. "1;5A" for Ctrl-Up) are ignored; only the base key matters.
+    let key = match body[end] {
+        b'u' => kitty_key(first_param, modifiers),
+        b'~' => match first_param {
+            "1" | "7" => Some(Key::Home),
+            "2" => Some(Key::Insert),
+            "3" => Some(Key::Delete),
+            "4" | "8" => Some(Key::End),
+            "5" => Some(Key::PageUp),
+            "6" => Some(Key::PageDown),
+            "11" => Some(Key::F(1)),
+            "12" => Some(Key::F(2)),
+            "13" => Some(Key::F(3)),
+            "14" => Some(Key::F(4)),
+            "15" => Some(Key::F(5)),
+            "17" => Some(Key::F(6)),
+            "18" => Some(Key::F(7)),
+            "19" => Some(Key::F(8)),
+            "20" => Some(Key::F(9)),
+            "21" => Some(Key::F(10)),
+            "23" => Some(Key::F(11)),
+            "24" => Some(Key::F(12)),
+            _ => None,
+        },
+        b'Z' => Some(Key::BackTab),
+        other => ss3_key(other),
+    };
+    match key {
+        Some(key) => Decoded::Key(key, len),
+        None => Decoded::Skip(len),
+    }
+}
+
+/// Split a kitty `modifiers[:event]` field into the modifier bits and the event type.
+fn parse_modifiers(field: &str) -> (u32, &str) {
+    let mut parts = field.split(':');
+    let modifiers = parts
+        .next()
+        .and_then(|m| m.parse::<u32>().ok())
+        .map_or(0, |m| m.saturating_sub(1));
+    (modifiers, parts.next().unwrap_or("1"))
+}
+
+/// Decode a kitty `CSI <code>[:alternates] ; <modifiers> u` key event.
+fn kitty_key(code_field: &str, modifiers: u32) -> Option<Key> {
+    let code: u32 = code_field.split(':').next()?.parse().ok()?;
+    let key = match code {
+        27 => Key::Esc,
+        13 | 57414 => Key::Enter,
+        9 => Key::Tab,
+        127 | 8 => Key::Backspace,
+        _ => {
+            let c = char::from_u32(code)?;
+            if c.is_control() {
+                return None;
+            }
+            if modifiers & MOD_CTRL != 0 {
+                Key::Ctrl(c.to_ascii_lowercase())
+            } else if modifiers & MOD_ALT != 0 {
+                Key::Alt(c)
+            } else {
+                Key::Char(c)
+            }
+        }
+    };
+    Some(key)
+}
+
+/// Find the first reply (`CSI ? ... u` or `CSI ? ... c`) to the capability queries.
+fn find_query_response(bytes: &[u8]) -> Option<(std::ops::Range<usize>, u8)> {
+    let start = bytes.windows(3).position(|w| w == b"\x1b[?")?;
+    let rest = &bytes[start + 3..];
+    let end = rest
+        .iter()
+        .position(|b| !(b.is_ascii_digit() || *b == b';'))?;
+    let final_byte = rest[end];
+    matches!(final_byte, b'u' | b'c').then(|| (start..start + 3 + end + 1, final_byte))
+}
@@ -0,0 +1,60 @@
+//! Branch listing and the interactive picker behind the `git-recent` binary.
+//!
+//! [`git::load_recent`] lists branches in the order the picker shows them, and
+//! [`app::App`] is the picker itself: a state machine over that list, driven by
+//! keys from [`keys::KeyReader`] and drawn through [`screen::Screen`]. Settings come
+//! from [`config::Config`]; [`cli`] parses the binary's command line. The picker's
+//! git commands go through [`backend::GitBackend`], for which [`backend::MockGit`]
+//! provides an in-memory stand-in.
+//!
+//! Most functions run `git` in the current directory and report failures as
+//! human-readable messages; the ones callers may want to tell apart are
+//! [`error::AppError`]s, which `AppError::from` recovers from a `Box<dyn Error>`.
+
+pub mod alias;
+pub mod annotate;
+pub mod app;
+pub mod backend;
+pub mod checkout_hook;
+pub mod cli;
+pub mod clipboard;
+pub mod completions;
+pub mod config;
+pub mod confirm;
+pub mod cursor;
+pub mod doctor;
+pub mod error;
+pub mod export;
+pub mod filter;
+pub mod format;
+pub mod fzf;
+pub mod git;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod init;
+pub mod jj;
+pub mod json;
+pub mod keymap;
+pub mod keys;
+pub mod loader;
+pub mod man;
+pub mod menu;
+pub mod notify;
+pub mod pager;
+pub mod pulls;
+pub mod queries;
+pub mod refname;
+#[cfg(feature = "native-refs")]
+pub mod refs;
+pub mod repositories;
+pub mod screen;
+pub mod serve;
+pub mod spinner;
+pub mod terminal;
+pub mod text_input;
+pub mod theme;
+pub mod tmux;
+pub mod top;
+pub mod trace;
+pub mod unicode;
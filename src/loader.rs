@@ -0,0 +1,102 @@
+//! Branch status loaded on a worker thread, so the picker can draw the list right
+//! away and fill in the status columns as they arrive.
+
+use std::error::Error;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use crate::git::{BranchStatus, Scope};
+
+struct Request {
+    generation: u64,
+    branches: Vec<String>,
+    scope: Scope,
+    counts_against: Option<String>,
+    merged_into: Option<String>,
+}
+
+/// The statuses for one request, tagged with the generation it was made in.
+pub struct Loaded {
+    pub generation: u64,
+    pub statuses: Vec<(String, BranchStatus)>,
+}
+
+/// A worker thread answering status requests in order. It exits once the loader is
+/// dropped.
+pub struct StatusLoader {
+    requests: Sender<Request>,
+    results: Receiver<Loaded>,
+}
+
+impl StatusLoader {
+    /// Start a worker that loads statuses with `fetch` (such as `git::branch_status`).
+    /// A failed fetch answers with the default status, so requests never go unanswered.
+    pub fn spawn<F>(mut fetch: F) -> StatusLoader
+    where
+        F: FnMut(
+                &[String],
+                Scope,
+                Option<&str>,
+                Option<&str>,
+            ) -> Result<Vec<BranchStatus>, Box<dyn Error>>
+            + Send
+            + 'static,
+    {
+        let (requests, worker_requests) = mpsc::channel::<Request>();
+        let (worker_results, results) = mpsc::channel();
+        thread::spawn(move || {
+            for request in worker_requests {
+                let statuses = fetch(
+                    &request.branches,
+                    request.scope,
+                    request.counts_against.as_deref(),
+                    request.merged_into.as_deref(),
+                )
+                .unwrap_or_default();
+                let mut statuses = statuses.into_iter();
+                let loaded = Loaded {
+                    generation: request.generation,
+                    statuses: request
+                        .branches
+                        .into_iter()
+                        .map(|branch| (branch, statuses.next().unwrap_or_default()))
+                        .collect(),
+                };
+                if worker_results.send(loaded).is_err() {
+                    break;
+                }
+            }
+        });
+        StatusLoader { requests, results }
+    }
+
+    /// Ask for the status of `branches`; the answer comes from `receive`.
+    pub fn request(
+        &self,
+        generation: u64,
+        branches: Vec<String>,
+        scope: Scope,
+        counts_against: Option<String>,
+        merged_into: Option<String>,
+    ) {
+        // Only fails if the worker is gone, and then there is nobody to ask.
+        let _ = self.requests.send(Request {
+            generation,
+            branches,
+            scope,
+            counts_against,
+            merged_into,
+        });
+    }
+
+    /// The answers that have arrived, waiting up to `wait` for the first one.
+    pub fn receive(&self, wait: Duration) -> Vec<Loaded> {
+        let Ok(first) = self.results.recv_timeout(wait) else {
+            return Vec::new();
+        };
+        let mut loaded = vec![first];
+        loaded.extend(self.results.try_iter());
+        loaded
+    }
+}
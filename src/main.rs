@@ -1,268 +1,830 @@
+use std::env;
 use std::error::Error;
-use std::io::{self, Read, Write};
-use std::process::{Command, Stdio};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Instant;
 
-const MAX_BRANCHES: usize = 200;
-const NO_OF_VISIBLE_BRANCHES: usize = 5;
+use git_recent::alias;
+use git_recent::annotate::{self, AnnotationLoader};
+use git_recent::app::{self, App, Mode, NO_OF_VISIBLE_BRANCHES};
+use git_recent::backend::SystemGit;
+use git_recent::config::{Config, Source, Value};
+use git_recent::cursor::Cursor;
+use git_recent::error::{AppError, EXIT_FAILURE, EXIT_USAGE};
+use git_recent::fzf::Picker;
+use git_recent::git::{self, BranchInfo, LoadOptions, Location, Scope};
+use git_recent::loader::StatusLoader;
+use git_recent::pulls::{Forge, PullLoader};
+use git_recent::queries::QueryHistory;
+use git_recent::theme::{self, Theme};
+use git_recent::{
+    checkout_hook, cli, completions, doctor, export, filter, format, fzf, i18n, init, jj, json,
+    man, notify, refname, repositories, serve, spinner, terminal, tmux, top, trace,
+};
 
-const CLEAR_SCREEN: &str = "\x1b[H\x1b[J";
-const CURSOR_TO_LEFT: &str = "\x1b[G";
-const HIDE_CURSOR: &str = "\x1b[?25l";
-const HIGHLIGHT: &str = "\x1b[44;30m";
-const RESET: &str = "\x1b[0m";
-const PRIMARY_PAGINATION: &str = "\x1b[47;30m";
-const SECONDARY_PAGINATION: &str = "\x1b[30m";
-const SHOW_CURSOR: &str = "\x1b[?25h";
+/// Whether to offer recently used repositories when started outside one: only for an
+/// interactive run, not when output is meant for a script.
+fn offers_repositories(args: &cli::Args) -> bool {
+    io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && !(args.list || args.json || args.format.is_some())
+        && args.checkout.is_none()
+        && !args.last
+}
 
-/// Load up to MAX_BRANCHES most recently committed branches.
-/// Returns an error if the git command fails.
-fn load_recent() -> Result<(String, Vec<String>), Box<dyn Error>> {
-    let output = Command::new("git")
-        .args(["branch", "--sort=-committerdate"])
-        .output()?;
-    if !output.status.success() {
-        return Err(format!("git branch failed: {}", output.status).into());
+/// List the recently used repositories with numbers and read a choice from stdin.
+/// `None` if there are none or nothing was chosen.
+fn choose_repository() -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let recent = repositories::recent();
+    if recent.is_empty() {
+        return Ok(None);
+    }
+    let mut ui = terminal::ui();
+    writeln!(ui, "{}", i18n::text("repositories.heading"))?;
+    for (i, path) in recent.iter().enumerate() {
+        writeln!(ui, "{:>3}) {}", i + 1, path.display())?;
+    }
+    write!(ui, "{}", i18n::text("prompt.repository_number"))?;
+    ui.flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    let answer = line.trim();
+    if answer.is_empty() {
+        return Ok(None);
+    }
+    let chosen = answer
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| recent.get(n.checked_sub(1)?));
+    match chosen {
+        Some(path) => Ok(Some(path.clone())),
+        None => Err(i18n::message("repositories.no_such", &[("answer", &answer)]).into()),
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let current_branch: String = stdout
-        .lines()
-        .map(|s| s.trim().to_string())
-        .filter(|s| s.starts_with('*'))
-        .collect::<Vec<_>>()[0]
-        .clone()
-        .trim_start_matches('*')
-        .trim()
-        .to_string();
+/// Resolve a `--merged`/`--no-merged` argument, defaulting to `--default-branch` or
+/// else the detected default branch.
+fn merge_target(
+    arg: &Option<Option<String>>,
+    default_branch: &Option<String>,
+) -> Result<Option<String>, Box<dyn Error>> {
+    match (arg, default_branch) {
+        (None, _) => Ok(None),
+        (Some(Some(target)), _) | (Some(None), Some(target)) => Ok(Some(target.clone())),
+        (Some(None), None) => git::default_branch().map(Some),
+    }
+}
 
-    let branches: Vec<String> = stdout
-        .lines()
-        .map(|s| {
-            // branch lines will be like "* main" or "  feature"
-            s.trim().trim_start_matches('*').trim().to_string()
-        })
-        .filter(|s| !s.is_empty())
-        .take(MAX_BRANCHES)
-        .collect();
+/// Choose a branch without raw mode: list the visible branches with numbers on the UI
+/// output and read a number (or name) from stdin when `from_stdin`, or else from the
+/// controlling terminal.
+fn prompt_numbered(app: &App, from_stdin: bool) -> Result<Option<String>, Box<dyn Error>> {
+    let branches: Vec<String> = app.visible_branches().cloned().collect();
+    let mut ui = terminal::ui();
+    for (i, branch) in branches.iter().enumerate() {
+        let current_mark = if branch == app.current_branch() {
+            "*"
+        } else if app.other_worktrees.contains_key(branch) {
+            "+"
+        } else {
+            " "
+        };
+        writeln!(
+            ui,
+            "{:>3}) {current_mark} {}",
+            i + 1,
+            refname::display(branch)
+        )?;
+    }
+    write!(ui, "{}", i18n::text("prompt.branch_number"))?;
+    ui.flush()?;
+    let answer = if from_stdin {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line)?;
+        line
+    } else {
+        terminal::read_tty_line().map_err(|e| {
+            AppError::TerminalSetupFailed(i18n::message("terminal.none", &[("error", &e)]))
+        })?
+    };
+    if answer.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(
+        resolve_checkout(&branches, answer.trim())
+            .map_err(AppError::BranchNotFound)?
+            .to_string(),
+    ))
+}
 
-    Ok((current_branch, branches))
+/// Resolve a `--checkout` argument: an exact branch name, or else a 1-based index
+/// into the recent list.
+fn resolve_checkout<'a>(branches: &'a [String], wanted: &str) -> Result<&'a str, String> {
+    if let Some(branch) = branches.iter().find(|b| *b == wanted) {
+        return Ok(branch);
+    }
+    match wanted.parse::<usize>() {
+        Ok(0) => Err(i18n::text("checkout.index_zero").to_string()),
+        Ok(n) => branches.get(n - 1).map(String::as_str).ok_or_else(|| {
+            i18n::message(
+                "checkout.index_too_large",
+                &[("count", &branches.len()), ("index", &n)],
+            )
+        }),
+        Err(_) => {
+            let quoted: Vec<String> = filter::suggestions(wanted, branches)
+                .iter()
+                .map(|b| format!("'{b}'"))
+                .collect();
+            Err(match quoted.as_slice() {
+                [] => i18n::message("checkout.no_such", &[("name", &wanted)]),
+                [one] => i18n::message(
+                    "checkout.did_you_mean",
+                    &[("name", &wanted), ("suggestion", one)],
+                ),
+                _ => i18n::message(
+                    "checkout.did_you_mean_many",
+                    &[("name", &wanted), ("suggestions", &quoted.join(", "))],
+                ),
+            })
+        }
+    }
 }
 
-/// Get the current branch name (git branch --show-current).
-fn get_current_branch() -> Result<String, Box<dyn Error>> {
-    let output = Command::new("git")
-        .args(["branch", "--show-current"])
-        .output()?;
-    if !output.status.success() {
-        return Err(format!("git show-current failed: {}", output.status).into());
-    }
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+/// Write non-interactive output to stdout.
+fn write_output(text: &str) -> Result<(), Box<dyn Error>> {
+    let mut out = io::stdout().lock();
+    match out
+        .write_all(&refname::encode(text))
+        .and_then(|()| out.flush())
+    {
+        // The reader went away (e.g. `| head`): that's not an error for a list.
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
 }
 
-/// RAII guard that enables raw mode while alive and restores terminal state on Drop.
-/// Uses `stty` on unix. On non-unix this is a no-op.
-struct RawModeGuard {
-    enabled: bool,
+/// Reading the config files or profile failed: a configuration error, whatever the cause.
+fn config_error(error: Box<dyn Error>) -> AppError {
+    AppError::Config(error.to_string())
 }
 
-impl RawModeGuard {
-    fn new() -> Self {
-        let mut enabled = false;
-        if cfg!(unix) {
-            // Enable raw mode and disable echo for cleaner key handling.
-            let _ = Command::new("stty")
-                .arg("raw")
-                .arg("-echo")
-                .stdin(Stdio::inherit())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status();
-            enabled = true;
-        }
-        RawModeGuard { enabled }
+/// Render branch metadata as a JSON array with one object per line (`--json`).
+fn json_output(infos: &[BranchInfo]) -> String {
+    let objects: Vec<String> = infos
+        .iter()
+        .map(|b| format!("  {}", json::branch(b)))
+        .collect();
+    if objects.is_empty() {
+        "[]\n".to_string()
+    } else {
+        format!("[\n{}\n]\n", objects.join(",\n"))
     }
 }
 
-impl Drop for RawModeGuard {
-    fn drop(&mut self) {
-        if self.enabled && cfg!(unix) {
-            // Restore canonical mode and re-enable echo.
-            let _ = Command::new("stty")
-                .arg("-raw")
-                .arg("echo")
-                .stdin(Stdio::inherit())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status();
+fn main() {
+    let started = Instant::now();
+    // Arguments are decoded like git's output, so branch names that are not UTF-8 match.
+    let args = env::args_os()
+        .skip(1)
+        .map(|arg| refname::decode(arg.as_encoded_bytes()));
+    let args = match cli::parse(args) {
+        Ok(cli::Parsed::Run(args)) => *args,
+        Ok(cli::Parsed::Help) => {
+            print!("{}", cli::help_text());
+            return;
+        }
+        Ok(cli::Parsed::Version) => {
+            println!("{}", cli::version_text());
+            return;
         }
+        Ok(cli::Parsed::Man) => {
+            let _ = write_output(&man::page());
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}: {e}", cli::NAME);
+            eprintln!(
+                "{}",
+                i18n::message("usage.try_help", &[("name", &cli::NAME)])
+            );
+            std::process::exit(EXIT_USAGE);
+        }
+    };
+
+    if args.timing {
+        trace::enable_timing(started);
+    }
+    terminal::install_interrupt_handler();
+    terminal::install_panic_hook();
+    let result = run_app(args);
+    if let Some(report) = trace::timing_report() {
+        eprint!("{report}");
+    }
+    // A dry run's skipped commands form a script on stdout, whatever happened after them.
+    for command in git::take_dry_run_log() {
+        let mut line = refname::encode(&command);
+        line.push(b'\n');
+        let _ = io::stdout().write_all(&line);
+    }
+    if let Err(e) = result {
+        eprintln!("{}", i18n::message("error", &[("error", &e)]));
+        std::process::exit(e.exit_code());
     }
 }
 
-/// Application state and logic.
-struct App {
-    branches: Vec<String>,
-    current_branch: String,
-    selected: usize,
-    offset: usize,
-}
+fn run_app(args: cli::Args) -> Result<(), AppError> {
+    git::set_dry_run(args.dry_run);
+    // Before any -C, so a relative log file is where the user meant.
+    if args.verbose {
+        trace::enable(args.log_file.as_deref())
+            .map_err(|e| i18n::message("log_file.failed", &[("error", &e)]))?;
+        trace::note(format_args!("{} {}", cli::NAME, cli::VERSION));
+    }
+    // The popup of --tmux-popup starts here and applies the same -C options.
+    let start_dir = env::current_dir().unwrap_or_default();
+    // Each -C is relative to the previous one, as with git.
+    for dir in &args.repo {
+        env::set_current_dir(dir).map_err(|e| {
+            i18n::message("chdir.failed", &[("dir", &dir.display()), ("error", &e)])
+        })?;
+    }
+    if args.command == Some(cli::Command::Completions)
+        && let Some(shell) = args.shell
+    {
+        return Ok(write_output(&completions::script(shell))?);
+    }
+    if args.command == Some(cli::Command::Init)
+        && let Some(script) = args.shell.and_then(init::script)
+    {
+        return Ok(write_output(&script)?);
+    }
+    // Before the repository check and the config files, which cost more than `top`.
+    if args.command == Some(cli::Command::Top) {
+        return Ok(print_top(&args)?);
+    }
+    if args.command == Some(cli::Command::RecordCheckout) {
+        return Ok(checkout_hook::record()?);
+    }
+    if args.command == Some(cli::Command::InstallHook) {
+        return Ok(install_hook(&args)?);
+    }
+    if args.command == Some(cli::Command::InstallAlias) {
+        return Ok(install_alias(&args)?);
+    }
+    if args.command == Some(cli::Command::Config) {
+        return Ok(show_config(&args)?);
+    }
+    if args.command == Some(cli::Command::Doctor) {
+        return doctor(&args);
+    }
+    let location = match git::check_repository() {
+        Err(AppError::NotARepository) if offers_repositories(&args) => {
+            let Some(dir) = choose_repository()? else {
+                return Err(AppError::NotARepository);
+            };
+            env::set_current_dir(&dir).map_err(|e| {
+                i18n::message("chdir.failed", &[("dir", &dir.display()), ("error", &e)])
+            })?;
+            git::check_repository()?
+        }
+        result => result?,
+    };
+    git::pin_repository(location)?;
+    if location == Location::WorkTree
+        && let Some(worktree) = git::top_level()
+    {
+        // Only offered again when starting outside a repository; not worth failing over.
+        let _ = repositories::record(&worktree);
+    }
+    // Command-line flags override the config file, which overrides built-in defaults.
+    let config = Config::load(args.profile.as_deref()).map_err(config_error)?;
+    for warning in &config.warnings {
+        eprintln!("{}: {warning}", cli::NAME);
+    }
+    let switch = config.switch_command();
+    let hooks = config.hooks().map_err(AppError::Config)?;
+    if args.last {
+        if location == Location::NoWorkTree {
+            return Err(AppError::NoWorkTree);
+        }
+        let previous = git::previous_branch()?.ok_or_else(|| {
+            AppError::BranchNotFound(i18n::text("checkout.no_previous").to_string())
+        })?;
+        return Ok(app::switch_to(
+            &mut SystemGit,
+            &previous,
+            args.quiet,
+            &switch,
+            &hooks,
+        )?);
+    }
 
-impl App {
-    fn new(branches: Vec<String>, current_branch: String) -> Self {
-        App {
-            branches,
-            current_branch,
-            offset: 0,
-            selected: 0,
+    if args.fetch {
+        let started = Instant::now();
+        match git::fetch(args.quiet) {
+            Ok(()) => notify::if_slow(hooks.notify_after, started, i18n::text("notify.fetched")),
+            // Being offline should not stop you from switching branches.
+            Err(e) => eprintln!("{}", i18n::message("fetch.failed", &[("error", &e)])),
         }
     }
 
-    fn render(&self) -> io::Result<()> {
-        // Clear screen and render menu
-        print!("{CLEAR_SCREEN}");
-        println!("Select recent branch:");
-        print!("{CURSOR_TO_LEFT}");
-        if self.offset > 0 {
-            println!("  {PRIMARY_PAGINATION}(less){RESET}")
-        } else {
-            println!("  {SECONDARY_PAGINATION}(less){RESET}")
+    // Validate UI settings up front, so mistakes show up even without the picker.
+    let keymap = config.keymap().map_err(AppError::Config)?;
+    let theme = config.theme().map_err(AppError::Config)?;
+    let height = match args.height {
+        Some(height) => height,
+        None => config
+            .height()
+            .map_err(AppError::Config)?
+            .unwrap_or(NO_OF_VISIBLE_BRANCHES),
+    };
+    let picker = match args.picker {
+        Some(picker) => picker,
+        None => config
+            .picker()
+            .map_err(AppError::Config)?
+            .unwrap_or_default(),
+    };
+    let accessible = args.accessible || config.boolean("accessible").unwrap_or(false);
+    if accessible {
+        // Its redrawn line would be read out over and over.
+        spinner::disable();
+    }
+    let ascii = args.ascii || config.boolean("ascii").unwrap_or(false);
+    if ascii {
+        spinner::use_ascii();
+    }
+    let default_branch = args
+        .default_branch
+        .clone()
+        .or_else(|| config.string("default_branch").map(String::from));
+    let load = LoadOptions {
+        limit: match args.limit {
+            Some(limit) => limit,
+            None => config
+                .limit()
+                .map_err(AppError::Config)?
+                .unwrap_or(Some(git::MAX_BRANCHES)),
+        },
+        sort: match args.sort {
+            Some(sort) => sort,
+            None => config.sort().map_err(AppError::Config)?.unwrap_or_default(),
+        },
+        scope: args.scope.unwrap_or_default(),
+        contains: args.contains.clone(),
+        merged: merge_target(&args.merged, &default_branch)?,
+        no_merged: merge_target(&args.no_merged, &default_branch)?,
+        stale_days: args.stale,
+        exclude: config.strings("exclude").unwrap_or_default(),
+        query: None,
+        case: config
+            .filter_case()
+            .map_err(AppError::Config)?
+            .unwrap_or_default(),
+        track: args.json || args.format.is_some(),
+        jj: config.boolean("jj").unwrap_or_else(jj::colocated),
+    };
+    if let Some(format) = args.export {
+        // The whole table unless --limit asks for less; the configured limit is for
+        // the picker.
+        let listing = LoadOptions {
+            limit: args.limit.unwrap_or(None),
+            query: args.query.clone(),
+            track: true,
+            ..load
+        };
+        let (_, infos) = git::load_branches(&listing)?;
+        let checkouts = git::checkout_counts()?;
+        return Ok(write_output(&export::render(format, &infos, &checkouts))?);
+    }
+    if args.serve {
+        // stdout carries only responses; a spinner would only clutter stderr.
+        spinner::disable();
+        let output = terminal::take_stdout()?;
+        let protected = config.strings("protected").unwrap_or_default();
+        let mut server = serve::Server::new(load, location, switch, hooks, protected);
+        return Ok(server.run(io::stdin().lock(), output)?);
+    }
+    let on_select = on_select(&args, &config).map_err(AppError::Config)?;
+    let print = matches!(on_select, OnSelect::Print);
+    // Plain-text records end in a newline, or NUL with -z for names with odd characters.
+    let terminator = if args.null { '\0' } else { '\n' };
+    if args.json || args.format.is_some() {
+        // The query goes to git-recent's own loading, so it searches every branch and
+        // the limit counts matches.
+        let listing = LoadOptions {
+            query: args.query.clone(),
+            ..load.clone()
+        };
+        // Metadata for --json and --format comes with the listing, from the same git call.
+        let (_, infos) = git::load_branches(&listing)?;
+        if args.json {
+            return Ok(write_output(&json_output(&infos))?);
         }
-        for (i, b) in self
-            .branches
-            .iter()
-            .skip(self.offset)
-            .take(NO_OF_VISIBLE_BRANCHES)
-            .enumerate()
-        {
-            print!("{CURSOR_TO_LEFT}");
-            let current_mark = if b == &self.current_branch { "*" } else { " " };
-            if i == self.selected - self.offset {
-                // Highlight selection: blue background, black text
-                println!(" {HIGHLIGHT}{current_mark} {b}{RESET}");
-            } else {
-                println!(" {current_mark} {b}");
+        if let Some(template) = &args.format {
+            let now = format::now();
+            let mut text = String::new();
+            for info in &infos {
+                text.push_str(&template.render(info, now));
+                text.push(terminator);
             }
+            return Ok(write_output(&text)?);
         }
-        print!("{CURSOR_TO_LEFT}");
-        if self.offset + NO_OF_VISIBLE_BRANCHES < self.branches.len() {
-            println!("  {PRIMARY_PAGINATION}(more){RESET}")
-        } else {
-            println!("  {SECONDARY_PAGINATION}(more){RESET}")
-        }
-        io::stdout().flush()
     }
-
-    fn handle_up(&mut self) {
-        if self.selected > 0 {
-            self.selected -= 1;
+    // Every branch, so that filters and --checkout reach past the limit.
+    let (current_branch, branches) = git::load_recent(&load)?;
+    // Piped output gets the plain list unless something other than a listing was asked for.
+    let acts = !matches!(on_select, OnSelect::Checkout) || args.delete || args.checkout.is_some();
+    let list = args.list || (!io::stdout().is_terminal() && !acts);
+    if list {
+        let mut text = String::new();
+        let query = args
+            .query
+            .as_ref()
+            .map(|q| filter::Query::new(q, load.case));
+        let matching = branches
+            .iter()
+            .filter(|b| query.as_ref().is_none_or(|q| q.matches(b)));
+        for branch in matching.take(load.limit.unwrap_or(usize::MAX)) {
+            text.push_str(branch);
+            text.push(terminator);
         }
-        if self.offset > self.selected {
-            self.offset -= 1;
+        return Ok(write_output(&text)?);
+    }
+    if let Some(wanted) = &args.checkout {
+        if location == Location::NoWorkTree {
+            return Err(AppError::NoWorkTree);
         }
+        let branch = resolve_checkout(&branches, wanted).map_err(AppError::BranchNotFound)?;
+        return Ok(app::switch_to(
+            &mut SystemGit,
+            &load.scope.checkout_name(branch),
+            args.quiet,
+            &switch,
+            &hooks,
+        )?);
+    }
+    if branches.is_empty() {
+        let empty = match load.scope {
+            Scope::Tags => "list.no_tags",
+            _ => "list.empty",
+        };
+        println!("{}", i18n::text(empty));
+        return Ok(());
+    }
+    if location == Location::NoWorkTree && matches!(on_select, OnSelect::Checkout) && !args.delete {
+        return Err(AppError::NoWorkTree);
     }
 
-    fn handle_down(&mut self) {
-        if self.selected + 1 < self.branches.len() {
-            self.selected += 1;
+    if print || !io::stdout().is_terminal() {
+        terminal::move_ui_off_stdout();
+    }
+    let detached = if current_branch.is_empty() {
+        git::detached_head()?
+    } else {
+        None
+    };
+    let mut app = App::new(branches, current_branch, load);
+    app.detached = detached;
+    // Only a marker; a repository whose worktrees cannot be listed still works.
+    app.other_worktrees = git::other_worktree_branches().unwrap_or_default();
+    app.height = height;
+    app.accessible = accessible;
+    app.ascii = ascii;
+    app.no_color = theme::no_color();
+    app.keymap = keymap;
+    app.theme = if ascii || app.no_color {
+        Theme::ascii()
+    } else {
+        theme
+    };
+    app.confirm_delete = config.boolean("confirm.delete").unwrap_or(true);
+    app.switch = switch;
+    app.hooks = hooks;
+    app.protected = config.strings("protected").unwrap_or_default();
+    app.confirm_checkout = matches!(on_select, OnSelect::Checkout)
+        && config.boolean("confirm.checkout").unwrap_or(false);
+    app.osc52 = config.boolean("clipboard.osc52").unwrap_or(true);
+    if let Some(query) = &args.query {
+        app.set_filter(query.clone());
+    }
+    // Raw mode needs a terminal on stdin (and one that understands escape sequences);
+    // otherwise fall back to a numbered prompt.
+    let simple = args.simple
+        || config.boolean("simple").unwrap_or(false)
+        || env::var("TERM").is_ok_and(|term| term == "dumb");
+    let interactive = io::stdin().is_terminal() && !simple;
+    // Only the builtin picker in this process shows status; fzf has its preview.
+    if interactive && picker == Picker::Builtin && !args.tmux_popup {
+        // Nearly every tag is on the main line; saying so would only be noise.
+        if app.load_options().scope != Scope::Tags {
+            app.counts_against = default_branch.clone();
+            app.merged_into = default_branch.or_else(|| git::default_branch().ok());
         }
-        if self.offset + NO_OF_VISIBLE_BRANCHES - 1 < self.selected {
-            self.offset += 1;
+        app.status_loader = Some(StatusLoader::spawn(git::branch_status));
+        if config.boolean("pull_requests").unwrap_or(false) {
+            app.pull_loader = Some(PullLoader::spawn(Forge::detect()));
+        }
+        if let Some(template) = config.string("annotate").map(String::from) {
+            app.annotation_loader = Some(AnnotationLoader::spawn(move |branch| {
+                annotate::run(&template, branch)
+            }));
         }
     }
-
-    /// Read a single key (or escape sequence) and update selected index accordingly.
-    /// Returns true when user confirms selection (Enter/Space).
-    fn handle_input(&mut self) -> io::Result<Option<bool>> {
-        // Buffer to accommodate escape sequences (e.g. "\x1b[<A>")
-        let mut buffer = [0u8; 3];
-        let n = io::stdin().read(&mut buffer)?;
-        if n == 0 {
-            return Ok(None);
+    if args.delete {
+        if !interactive {
+            return Err(AppError::TerminalSetupFailed(
+                i18n::text("delete.needs_picker").to_string(),
+            ));
         }
-
-        match &buffer[..n] {
-            // Up Arrow | k | w
-            [27, 91, 65] | [107] | [119] => self.handle_up(),
-            // Down Arrow | j | s
-            [27, 91, 66] | [106] | [115] => self.handle_down(),
-            // Enter (\n or \r) or Space
-            [10] | [13] | [32] => return Ok(Some(true)),
-            // Ctrl-C | q | Q | ESC
-            [3] | [81] | [113] | [27] => return Ok(Some(false)),
-            _ => {}
+        app.mode = Mode::Delete;
+        app.pick()?;
+        write!(terminal::ui(), "{}", app.clear_screen())?;
+        return Ok(terminal::ui().flush()?);
+    }
+    let chosen = if args.select_1 && app.visible_branches().len() == 1 {
+        app.selected_branch().cloned()
+    } else if args.tmux_popup {
+        tmux::pick(&start_dir)?
+    } else if picker == Picker::Fzf && !simple {
+        // fzf filters by itself, so it gets every branch, past the limit.
+        fzf::pick(app.branches(), args.query.as_deref())?
+    } else if !interactive {
+        prompt_numbered(&app, simple)?
+    } else {
+        if let Some(cursor) = Cursor::load() {
+            app.restore(&cursor);
+        }
+        // Off any branch, the likely next step is going back to the one left.
+        if app.detached.is_some()
+            && let Some(previous) = git::previous_branch()?
+            && app.select_branch(&previous)
+        {
+            app.set_message(i18n::message("picker.return_to", &[("branch", &previous)]));
+        }
+        app.queries = QueryHistory::load();
+        let chosen = app.pick()?;
+        if let Some(cursor) = app.cursor() {
+            // Only a convenience for the next run; not worth failing over.
+            let _ = cursor.save();
+        }
+        if !matches!(on_select, OnSelect::Checkout) {
+            write!(terminal::ui(), "{}", app.clear_screen())?;
+            terminal::ui().flush()?;
+        }
+        chosen
+    };
+    let Some(chosen) = chosen else {
+        if print {
+            // Nothing was picked: make `$(git-recent --print)` callers notice.
+            std::process::exit(EXIT_FAILURE);
+        }
+        return Ok(());
+    };
+    match on_select {
+        OnSelect::Checkout => Ok(app.checkout(&chosen, args.quiet)?),
+        OnSelect::Print => Ok(write_output(&format!("{chosen}{terminator}"))?),
+        OnSelect::Worktree => {
+            let branch = app.load_options().scope.checkout_name(&chosen);
+            let path = git::worktree_for(&branch, config.string("worktree_dir"))?;
+            let path = refname::decode(path.as_os_str().as_encoded_bytes());
+            let Some(file) = &args.cd_file else {
+                return Ok(write_output(&format!("{path}{terminator}"))?);
+            };
+            // The shell function runs this file once git-recent exits.
+            let directive = init::cd_directive(&path);
+            if git::skip_for_dry_run(directive.trim_end().to_string()) {
+                return Ok(());
+            }
+            fs::write(file, refname::encode(&directive)).map_err(|e| {
+                let path = file.display();
+                i18n::message("cd.cannot_write", &[("path", &path), ("error", &e)]).into()
+            })
+        }
+        OnSelect::Exec(command) => {
+            let command = git::branch_command(&command, &chosen);
+            if git::skip_for_dry_run(command.clone()) {
+                return Ok(());
+            }
+            let status = trace::status(Command::new("sh").arg("-c").arg(refname::to_os(&command)))
+                .map_err(|e| {
+                    i18n::message("exec.failed", &[("command", &command), ("error", &e)])
+                })?;
+            // Hand the command's outcome to our caller; a signal death counts as failure.
+            std::process::exit(status.code().unwrap_or(1));
         }
-        return Ok(None);
     }
+}
 
-    fn checkout_selected(&mut self) -> Result<bool, Box<dyn Error>> {
-        let chosen = &self.branches[self.selected];
-        println!("{CLEAR_SCREEN}");
-        println!("\nChecking out branch: {chosen}");
-        print!("{CURSOR_TO_LEFT}");
+/// `git-recent top`: the most recent branches with their ages, the current one marked
+/// with `*`, or with `--plain` just their names on one line.
+fn print_top(args: &cli::Args) -> Result<(), Box<dyn Error>> {
+    let branches = top::recent(args.limit.unwrap_or(Some(top::DEFAULT_COUNT)))?;
+    if args.plain {
+        let names: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+        return write_output(&format!("{}\n", names.join(" ")));
+    }
+    let width = branches
+        .iter()
+        .map(|b| b.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let now = format::now();
+    let mut text = String::new();
+    for branch in &branches {
+        let mark = if branch.current { '*' } else { ' ' };
+        let padding = " ".repeat(width - branch.name.chars().count());
+        let age = format::relative_time(now - branch.time);
+        text.push_str(&format!("{mark} {}{padding}  {age}\n", branch.name));
+    }
+    write_output(&text)
+}
 
-        let status = Command::new("git").args(["checkout", chosen]).status()?;
-        if status.success() {
-            // Move chosen branch to the front of the list
-            let chosen_clone = chosen.clone();
-            self.branches.retain(|b| b != &chosen_clone);
-            self.branches.insert(0, chosen_clone);
-            Ok(true)
-        } else {
-            Err(format!("git checkout failed: {}", status).into())
+/// `git-recent install-alias`: add `git recent` (and a shortcut) to the global git
+/// config, or remove them again with `--uninstall`.
+fn install_alias(args: &cli::Args) -> Result<(), Box<dyn Error>> {
+    // A dry run prints the git commands instead, which say it all.
+    let quiet = args.quiet || git::is_dry_run();
+    if args.uninstall {
+        let removed = alias::uninstall()?;
+        if quiet {
+            return Ok(());
+        }
+        if removed.is_empty() {
+            eprintln!("{}", i18n::text("alias.none"));
+        }
+        for name in removed {
+            eprintln!("{}", i18n::message("alias.removed", &[("name", &name)]));
         }
+        return Ok(());
     }
+    let shortcut = args
+        .shortcut
+        .as_ref()
+        .map(|name| name.as_deref().unwrap_or(alias::DEFAULT_SHORTCUT));
+    let added = alias::install(shortcut)?;
+    if quiet {
+        return Ok(());
+    }
+    for name in added {
+        eprintln!("{}", i18n::message("alias.added", &[("name", &name)]));
+    }
+    Ok(())
+}
 
-    fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        // Create RAII guard to restore terminal state on panic/exit.
-        let _raw_guard = RawModeGuard::new();
-
-        print!("{HIDE_CURSOR}");
-        io::stdout().flush()?;
-
-        let mut confirmed = false;
-        loop {
-            self.render()?;
-            match self.handle_input()? {
-                None => continue,
-                Some(true) => {
-                    confirmed = true;
-                    break;
-                }
-                Some(false) => {
-                    confirmed = false;
-                    break;
-                }
-            }
+/// `git-recent install-hook`: add the post-checkout hook that records checkouts made
+/// outside git-recent, or remove it again with `--uninstall`.
+fn install_hook(args: &cli::Args) -> Result<(), Box<dyn Error>> {
+    let quiet = args.quiet || git::is_dry_run();
+    if args.uninstall {
+        let removed = checkout_hook::uninstall()?;
+        if quiet {
+            return Ok(());
         }
-
-        drop(_raw_guard);
-        print!("{SHOW_CURSOR}");
-        io::stdout().flush()?;
-
-        if confirmed {
-            match self.checkout_selected() {
-                Ok(_) => Ok(()),
-                Err(e) => Err(e),
-            }
-        } else {
-            Ok(())
+        match removed {
+            Some(path) => eprintln!(
+                "{}",
+                i18n::message("hook.removed", &[("path", &path.display())])
+            ),
+            None => eprintln!("{}", i18n::text("hook.none")),
         }
+        return Ok(());
     }
+    let path = checkout_hook::install()?;
+    if !quiet {
+        eprintln!(
+            "{}",
+            i18n::message("hook.installed", &[("path", &path.display())])
+        );
+    }
+    Ok(())
 }
 
-fn main() {
-    if let Err(e) = run_app() {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+/// `git-recent config`: every setting with a value, as TOML, annotated with the layer
+/// it comes from. Options given along with the command are shown as the top layer.
+fn show_config(args: &cli::Args) -> Result<(), Box<dyn Error>> {
+    let mut config = Config::defaults();
+    config.load_layers(args.profile.as_deref())?;
+    for warning in &config.warnings {
+        eprintln!("{}: {warning}", cli::NAME);
+    }
+    let flag = |option: &str| Source::Flag(format!("--{option}"));
+    if let Some(profile) = &args.profile {
+        let profile = Value::String(profile.clone());
+        config.set("profile", profile, flag("profile"))?;
+    }
+    if let Some(sort) = args.sort {
+        config.set("sort", Value::String(sort.name().into()), flag("sort"))?;
+    }
+    if let Some(limit) = args.limit {
+        let limit = Value::Integer(limit.map_or(0, |n| n as i64));
+        config.set("limit", limit, flag("limit"))?;
+    }
+    if let Some(height) = args.height {
+        config.set("height", Value::Integer(height as i64), flag("height"))?;
+    }
+    if let Some(branch) = &args.default_branch {
+        let branch = Value::String(branch.clone());
+        config.set("default_branch", branch, flag("default-branch"))?;
+    }
+    if args.simple {
+        config.set("simple", Value::Boolean(true), flag("simple"))?;
+    }
+    if args.accessible {
+        config.set("accessible", Value::Boolean(true), flag("accessible"))?;
+    }
+    if args.ascii {
+        config.set("ascii", Value::Boolean(true), flag("ascii"))?;
     }
+    if let Some(picker) = args.picker {
+        let picker = Value::String(picker.name().into());
+        config.set("picker", picker, flag("picker"))?;
+    }
+    if args.print {
+        config.set("action", Value::String("print".into()), flag("print"))?;
+    } else if let Some(command) = &args.exec {
+        config.set("action", Value::String("exec".into()), flag("exec"))?;
+        config.set("exec", Value::String(command.clone()), flag("exec"))?;
+    }
+
+    let lines: Vec<(String, String)> = config
+        .entries()
+        .into_iter()
+        .map(|(key, entry)| (format!("{key} = {}", entry.value), entry.source.to_string()))
+        .collect();
+    let width = lines
+        .iter()
+        .map(|(setting, _)| setting.len())
+        .max()
+        .unwrap_or(0);
+    let mut out = String::new();
+    for (setting, source) in lines {
+        out.push_str(&format!("{setting:width$}  # {source}\n"));
+    }
+    write_output(&out)
 }
 
-fn run_app() -> Result<(), Box<dyn Error>> {
-    let (current_branch, branches) = load_recent()?;
-    if branches.is_empty() {
-        println!("No branches found");
-        return Ok(());
+/// Print the doctor's checks; fails if any found a problem.
+fn doctor(args: &cli::Args) -> Result<(), AppError> {
+    if !io::stdout().is_terminal() {
+        // Trying raw mode writes to the UI; keep that out of the report.
+        terminal::move_ui_off_stdout();
+    }
+    let checks = doctor::run(args.profile.as_deref());
+    let mut out = String::new();
+    for check in &checks {
+        out.push_str(&format!("{check}\n"));
     }
+    write_output(&out)?;
+    match checks
+        .iter()
+        .filter(|c| c.status == doctor::Status::Problem)
+        .count()
+    {
+        0 => Ok(()),
+        1 => Err(AppError::Other(
+            i18n::text("doctor.one_problem").to_string(),
+        )),
+        n => Err(AppError::Other(i18n::message(
+            "doctor.problems",
+            &[("count", &n)],
+        ))),
+    }
+}
+
+/// What happens to the branch picked in the list.
+enum OnSelect {
+    Checkout,
+    /// Print its name to stdout (`--print`).
+    Print,
+    /// Open it in a worktree, creating one if needed, and print the path.
+    Worktree,
+    /// Run a shell command on it (`--exec`).
+    Exec(String),
+}
 
-    let mut app = App::new(branches, current_branch);
-    app.run()
+/// `--print` or `--exec` if given, or else the `action` setting (default: checkout).
+fn on_select(args: &cli::Args, config: &Config) -> Result<OnSelect, String> {
+    if args.print {
+        return Ok(OnSelect::Print);
+    }
+    if let Some(command) = &args.exec {
+        return Ok(OnSelect::Exec(command.clone()));
+    }
+    match config.string("action") {
+        None | Some("checkout") => Ok(OnSelect::Checkout),
+        Some("print") => Ok(OnSelect::Print),
+        Some("worktree") => Ok(OnSelect::Worktree),
+        Some("exec") => match config.string("exec") {
+            Some(command) => Ok(OnSelect::Exec(command.to_string())),
+            None => Err(config.invalid("action", i18n::text("config.exec_missing"))),
+        },
+        Some(other) => Err(config.invalid(
+            "action",
+            i18n::message("config.unknown_action", &[("action", &other)]),
+        )),
+    }
 }
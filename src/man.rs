@@ -0,0 +1,149 @@
+//! The `git-recent(1)` man page, in roff, built from the same tables as `--help` so
+//! packagers can ship it with `git-recent --man > git-recent.1`.
+
+use crate::cli::{ABOUT, COMMANDS, NAME, OPTIONS, VERSION};
+use crate::error::{AppError, EXIT_FAILURE, EXIT_USAGE};
+
+/// The whole man page.
+pub fn page() -> String {
+    let name = escape(NAME);
+    let mut out = format!(
+        ".TH {} 1 \"\" \"{name} {VERSION}\" \"Git Manual\"\n",
+        escape(&NAME.to_uppercase())
+    );
+    out.push_str(&format!(
+        ".SH NAME\n{name} \\- {}\n",
+        escape(ABOUT.trim_end_matches('.').to_lowercase().as_str())
+    ));
+    out.push_str(&format!(
+        ".SH SYNOPSIS\n.B {name}\n[\\fIOPTIONS\\fR] [\\fICOMMAND\\fR]\n.br\n\
+         .B git recent\n[\\fIOPTIONS\\fR] [\\fICOMMAND\\fR]\n"
+    ));
+    out.push_str(&format!(
+        ".SH DESCRIPTION\n{}\n.PP\n{}\n",
+        escape(ABOUT),
+        escape(
+            "Without options, git-recent lists local branches by the date of their last \
+             commit in a picker drawn below the prompt. Move with the arrow keys, type / to \
+             filter, and press Enter to check out the highlighted branch. When stdout is not \
+             a terminal, it prints the list instead."
+        )
+    ));
+
+    out.push_str(".SH COMMANDS\n");
+    for (name, _, help) in COMMANDS {
+        let argument = if matches!(*name, "completions" | "init") {
+            " \\fISHELL\\fR"
+        } else {
+            ""
+        };
+        out.push_str(&format!(".TP\n.B {name}{argument}\n{}\n", escape(help)));
+    }
+
+    out.push_str(".SH OPTIONS\n");
+    for option in OPTIONS {
+        let mut names = Vec::new();
+        if let Some(c) = option.short {
+            names.push(format!("\\fB\\-{}\\fR", escape(&c.to_string())));
+        }
+        let value = match option.value {
+            None => String::new(),
+            Some(v) if option.optional_value() => {
+                format!("[=\\fI{}\\fR]", escape(v.trim_matches(['[', ']'])))
+            }
+            Some(v) => format!(" \\fI{}\\fR", escape(v)),
+        };
+        names.push(format!("\\fB\\-\\-{}\\fR{value}", escape(option.long)));
+        out.push_str(&format!(
+            ".TP\n{}\n{}\n",
+            names.join(", "),
+            escape(option.help)
+        ));
+    }
+
+    out.push_str(&format!(
+        ".SH FILES\n\
+         .TP\n.I $XDG_CONFIG_HOME/git\\-recent/config.toml\n\
+         User settings (\\fI~/.config/git\\-recent/config.toml\\fR when XDG_CONFIG_HOME is unset).\n\
+         .TP\n.I .git\\-recent.toml\n\
+         Settings shared by a repository, at the root of its worktree; they override the \
+         user's. Run \\fB{name} config\\fR to see the effective settings and where each \
+         comes from.\n\
+         .TP\n.I $XDG_STATE_HOME/git\\-recent/\n\
+         Checkout history, filter history and the picker's last position.\n"
+    ));
+
+    out.push_str(
+        ".SH ENVIRONMENT\n\
+         .TP\n.B GIT_RECENT_\\fINAME\\fR\n\
+         Overrides the setting \\fIname\\fR, upper\\-cased with dots turned into \
+         underscores, e.g. GIT_RECENT_SORT=name.\n\
+         .TP\n.B GIT_RECENT_NO_TUI\n\
+         Use the numbered prompt instead of the full\\-screen picker.\n\
+         .TP\n.BR LC_ALL \", \" LC_MESSAGES \", \" LANG\n\
+         The language of messages.\n\
+         .TP\n.BR COLORTERM \", \" TERM\n\
+         What the terminal can show; TERM=dumb selects the numbered prompt.\n",
+    );
+
+    let statuses = [
+        (0, "Success."),
+        (
+            EXIT_FAILURE,
+            "Nothing was chosen with \\-\\-print, or another error.",
+        ),
+        (EXIT_USAGE, "Invalid command line."),
+        (
+            AppError::Config(String::new()).exit_code(),
+            "Invalid setting.",
+        ),
+        (
+            AppError::NotARepository.exit_code(),
+            "Not in a git repository.",
+        ),
+        (AppError::GitNotFound.exit_code(), "git is not installed."),
+        (
+            AppError::BranchNotFound(String::new()).exit_code(),
+            "No branch matched \\-\\-checkout or \\-\\-last.",
+        ),
+        (
+            AppError::CheckoutConflict(String::new()).exit_code(),
+            "Local changes would be overwritten by the checkout.",
+        ),
+        (
+            AppError::HookFailed(String::new()).exit_code(),
+            "A hook failed.",
+        ),
+        (
+            AppError::TerminalSetupFailed(String::new()).exit_code(),
+            "The picker needs a terminal and has none.",
+        ),
+        (
+            AppError::NoWorkTree.exit_code(),
+            "A checkout was asked for in a bare repository.",
+        ),
+        (
+            AppError::RepositoryLocked(String::new()).exit_code(),
+            "Another git process holds the repository's lock.",
+        ),
+        (130, "Interrupted with Ctrl\\-C."),
+    ];
+    out.push_str(".SH EXIT STATUS\n");
+    for (code, meaning) in statuses {
+        out.push_str(&format!(".TP\n.B {code}\n{meaning}\n"));
+    }
+
+    out.push_str(".SH SEE ALSO\n.BR git\\-checkout (1),\n.BR git\\-switch (1),\n.BR git\\-for\\-each\\-ref (1)\n");
+    out
+}
+
+/// Escape text for roff: backslashes and hyphens, and a leading dot or quote that
+/// would otherwise start a request.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with(['.', '\'']) {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
@@ -0,0 +1,64 @@
+use crate::keys::Key;
+
+const RESET: &str = "\x1b[0m";
+
+/// Vertical list of choices drawn below the branch list, e.g. the actions available
+/// for the highlighted branch. Entries can also be picked by their number.
+pub struct Menu {
+    title: String,
+    items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(title: impl Into<String>, items: Vec<String>) -> Self {
+        Menu {
+            title: title.into(),
+            items,
+            selected: 0,
+        }
+    }
+
+    /// Render the title and one numbered line per item, the focused one drawn with
+    /// `highlight`.
+    pub fn render(&self, highlight: &str) -> Vec<String> {
+        let mut lines = vec![self.title.clone()];
+        for (i, item) in self.items.iter().enumerate() {
+            if i == self.selected {
+                lines.push(format!(" {highlight}{}) {item}{RESET}", i + 1));
+            } else {
+                lines.push(format!(" {}) {item}", i + 1));
+            }
+        }
+        lines
+    }
+
+    /// The title and the focused item as one plain line, for accessible mode.
+    pub fn announcement(&self) -> String {
+        let item = self.items.get(self.selected).map_or("", String::as_str);
+        format!("{} {}) {item}", self.title, self.selected + 1)
+    }
+
+    /// Feed a key to the menu. Returns `Some(Some(index))` once an item is chosen and
+    /// `Some(None)` when the menu is dismissed.
+    pub fn handle_key(&mut self, key: Key) -> Option<Option<usize>> {
+        match key {
+            Key::Enter => return Some(Some(self.selected)),
+            Key::Esc | Key::Ctrl('g') | Key::Char('q') => return Some(None),
+            Key::Up | Key::Char('k') | Key::Ctrl('p') => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            Key::Down | Key::Char('j') | Key::Ctrl('n') => {
+                self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+            }
+            Key::Char(c) => {
+                let index = c.to_digit(10).and_then(|n| (n as usize).checked_sub(1));
+                if let Some(index) = index.filter(|&i| i < self.items.len()) {
+                    return Some(Some(index));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,65 @@
+//! `notify.after`: a desktop notification once a fetch or checkout that took at least
+//! that many seconds is done, so one can look at something else meanwhile. Nothing is
+//! sent while the terminal is known to have the focus. Notifications go through
+//! `osascript` on macOS and `notify-send` elsewhere; without them there are none.
+
+use std::env;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::cli::NAME;
+use crate::refname;
+use crate::trace;
+
+/// Send `message` if the operation that began at `started` took at least `after`.
+pub fn if_slow(after: Option<Duration>, started: Instant, message: &str) {
+    if after.is_none_or(|after| started.elapsed() < after) || terminal_focused() {
+        return;
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        // The message is an argument, so it needs no AppleScript quoting.
+        command.args([
+            "-e",
+            "on run argv",
+            "-e",
+            &format!("display notification (item 1 of argv) with title \"{NAME}\""),
+            "-e",
+            "end run",
+        ]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(format!("--app-name={NAME}")).arg(NAME);
+        command
+    };
+    match trace::output(command.arg(refname::to_os(message))) {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => trace::note(format_args!(
+            "no notification: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => trace::note(format_args!("no notification: {e}")),
+    }
+}
+
+/// Whether the terminal git-recent runs in is the focused window. Only known where
+/// the terminal says which window it is: `__CFBundleIdentifier` on macOS, `WINDOWID`
+/// under X11 (with `xdotool`). Anywhere else it counts as not focused.
+fn terminal_focused() -> bool {
+    let (own, mut command) = if let Some(app) = env::var_os("__CFBundleIdentifier") {
+        let mut command = Command::new("osascript");
+        command.args(["-e", "id of app (path to frontmost application as text)"]);
+        (app, command)
+    } else if let Some(window) = env::var_os("WINDOWID") {
+        let mut command = Command::new("xdotool");
+        command.arg("getactivewindow");
+        (window, command)
+    } else {
+        return false;
+    };
+    trace::output(&mut command)
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| output.stdout.trim_ascii() == own.as_encoded_bytes())
+}
@@ -0,0 +1,85 @@
+//! The pager for the picker's diff and log views, chosen the way git chooses it:
+//! `pager.<command>`, then `GIT_PAGER`, `core.pager` and `PAGER`, then `less` with
+//! `LESS=FRX`. git only pages when its stdout is a terminal, which with `--print`
+//! it is not, so git-recent runs the pager itself, on the terminal the UI is on.
+
+use std::env;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::process::{Command, Stdio};
+
+use crate::git;
+use crate::refname;
+use crate::terminal;
+use crate::trace;
+
+/// The pager command for `git <subcommand>`, or `None` when it is not paged:
+/// `pager.<subcommand>` set to false, or a pager of `cat` or nothing.
+pub fn resolve(subcommand: &str) -> Option<String> {
+    let config = trace::output(
+        git::command()
+            .args(["config", "--get"])
+            .arg(format!("pager.{subcommand}")),
+    )
+    .ok()
+    .filter(|output| output.status.success())
+    .map(|output| refname::decode(&output.stdout).trim_end().to_string());
+    let pager = match config.as_deref() {
+        Some("false" | "no" | "off" | "0") => return None,
+        None | Some("true" | "yes" | "on" | "1") => {
+            // `git var` goes through GIT_PAGER, core.pager, PAGER and git's default.
+            let output = trace::output(git::command().args(["var", "GIT_PAGER"])).ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            refname::decode(&output.stdout).trim_end().to_string()
+        }
+        Some(pager) => pager.to_string(),
+    };
+    (!pager.is_empty() && pager != "cat").then_some(pager)
+}
+
+/// Run `git <args>` with its output in the pager `resolve` picks for `args[0]`,
+/// both writing to the UI's terminal.
+pub fn page_git(args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let subcommand = args.first().copied().unwrap_or_default();
+    let mut git = git::command();
+    git.arg("--no-pager")
+        .args(args.iter().map(|arg| refname::to_os(arg)))
+        // As under git's own pager: `color.ui = auto` colors, `color.pager` may say no.
+        .env("GIT_PAGER_IN_USE", "true");
+    let status = match resolve(subcommand) {
+        None => trace::status(git.stdout(terminal::ui_stdio()?))?,
+        Some(pager) => {
+            let mut pager_command = Command::new("sh");
+            pager_command
+                .arg("-c")
+                .arg(refname::to_os(&pager))
+                .stdin(Stdio::piped())
+                .stdout(terminal::ui_stdio()?);
+            // git sets these for the pager unless the user has.
+            for (name, value) in [("LESS", "FRX"), ("LV", "-c")] {
+                if env::var_os(name).is_none() {
+                    pager_command.env(name, OsStr::new(value));
+                }
+            }
+            let mut pager_process = pager_command.spawn()?;
+            let input = pager_process.stdin.take().expect("stdin is piped");
+            // A pager quit early ends git with SIGPIPE, which is no failure.
+            git.stdout(input);
+            let status = trace::status(&mut git);
+            // The pager reads to the end only once this last copy of the pipe is closed.
+            drop(git);
+            pager_process.wait()?;
+            let status = status?;
+            if status.code().is_none() {
+                return Ok(());
+            }
+            status
+        }
+    };
+    if !status.success() {
+        return Err(format!("git {subcommand} failed: {status}").into());
+    }
+    Ok(())
+}
@@ -0,0 +1,243 @@
+//! Pull requests (GitHub, through `gh`) and merge requests (GitLab, through `glab`)
+//! for the picker's branches, with `pull_requests = true`. They are loaded once on a
+//! worker thread, so the picker never waits for the network; the numbers show up
+//! next to the branches when they arrive.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::process::Command;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use crate::git;
+use crate::i18n;
+use crate::json::{self, Value};
+use crate::refname;
+use crate::trace;
+
+/// Where the repository's pull requests live, and the command that lists them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+}
+
+impl Forge {
+    /// The forge of the `origin` remote: GitLab if its URL says so, GitHub otherwise.
+    pub fn detect() -> Forge {
+        let url = trace::output(git::command().args(["remote", "get-url", "origin"]))
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| refname::decode(&output.stdout).to_lowercase())
+            .unwrap_or_default();
+        if url.contains("gitlab") {
+            Forge::GitLab
+        } else {
+            Forge::GitHub
+        }
+    }
+
+    /// The command-line tool that talks to the forge.
+    pub fn tool(self) -> &'static str {
+        match self {
+            Forge::GitHub => "gh",
+            Forge::GitLab => "glab",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Open,
+    Draft,
+    Approved,
+    Merged,
+    Closed,
+}
+
+impl State {
+    fn is_open(self) -> bool {
+        matches!(self, State::Open | State::Draft | State::Approved)
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let id = match self {
+            State::Open => "pull.open",
+            State::Draft => "pull.draft",
+            State::Approved => "pull.approved",
+            State::Merged => "pull.merged",
+            State::Closed => "pull.closed",
+        };
+        f.write_str(i18n::text(id))
+    }
+}
+
+/// The pull request of a branch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub state: State,
+    pub forge: Forge,
+}
+
+impl fmt::Display for PullRequest {
+    /// `#12 draft` for GitHub, `!12 draft` for GitLab, as each writes references.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sigil = match self.forge {
+            Forge::GitHub => '#',
+            Forge::GitLab => '!',
+        };
+        write!(f, "{sigil}{} {}", self.number, self.state)
+    }
+}
+
+/// How many of the most recent pull requests are asked for.
+const LIMIT: &str = "200";
+
+/// The pull requests of `forge` by branch name. A branch with several keeps its open
+/// one, or else the most recent.
+pub fn load(forge: Forge) -> Result<HashMap<String, PullRequest>, Box<dyn Error>> {
+    let mut command = Command::new(forge.tool());
+    match forge {
+        Forge::GitHub => command.args([
+            "pr",
+            "list",
+            "--state",
+            "all",
+            "--limit",
+            LIMIT,
+            "--json",
+            "number,headRefName,state,isDraft,reviewDecision",
+        ]),
+        Forge::GitLab => command.args([
+            "mr",
+            "list",
+            "--all",
+            "--per-page",
+            LIMIT,
+            "--output",
+            "json",
+        ]),
+    };
+    let output = trace::output(&mut command)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed: {}", forge.tool(), stderr.trim()).into());
+    }
+    let list = json::parse(&String::from_utf8_lossy(&output.stdout))?;
+    let Value::Array(items) = list else {
+        return Err(format!("{} printed no list", forge.tool()).into());
+    };
+    let mut pulls: HashMap<String, PullRequest> = HashMap::new();
+    // Both tools list the most recent first.
+    for item in &items {
+        let Some((branch, pull)) = parse_item(forge, item) else {
+            continue;
+        };
+        let keep = pulls
+            .get(&branch)
+            .is_some_and(|kept| kept.state.is_open() || !pull.state.is_open());
+        if !keep {
+            pulls.insert(branch, pull);
+        }
+    }
+    Ok(pulls)
+}
+
+/// One entry of `gh pr list --json` or `glab mr list --output json`.
+fn parse_item(forge: Forge, item: &Value) -> Option<(String, PullRequest)> {
+    let text = |key: &str| item.get(key).and_then(Value::as_str).unwrap_or_default();
+    let flag = |key: &str| item.get(key).and_then(Value::as_bool).unwrap_or(false);
+    let (number, branch, state) = match forge {
+        Forge::GitHub => {
+            let state = match text("state") {
+                "MERGED" => State::Merged,
+                "CLOSED" => State::Closed,
+                _ if flag("isDraft") => State::Draft,
+                _ if text("reviewDecision") == "APPROVED" => State::Approved,
+                _ => State::Open,
+            };
+            (item.get("number")?, text("headRefName"), state)
+        }
+        Forge::GitLab => {
+            let state = match text("state") {
+                "merged" => State::Merged,
+                "closed" | "locked" => State::Closed,
+                _ if flag("draft") || flag("work_in_progress") => State::Draft,
+                _ => State::Open,
+            };
+            (item.get("iid")?, text("source_branch"), state)
+        }
+    };
+    if branch.is_empty() {
+        return None;
+    }
+    let pull = PullRequest {
+        number: number.as_usize()? as u64,
+        state,
+        forge,
+    };
+    Some((branch.to_string(), pull))
+}
+
+/// Open the pull request of `branch` in the browser, or, if it has none, start the
+/// tool's flow for creating one, which may ask questions on the terminal. Returns
+/// whether a pull request existed.
+pub fn open(forge: Forge, branch: &str) -> Result<bool, Box<dyn Error>> {
+    let tool = forge.tool();
+    let (view, create): ([&str; 3], [&str; 4]) = match forge {
+        Forge::GitHub => (["pr", "view", "--web"], ["pr", "create", "--web", "--head"]),
+        Forge::GitLab => (
+            ["mr", "view", "--web"],
+            ["mr", "create", "--web", "--source-branch"],
+        ),
+    };
+    let branch = refname::to_os(branch);
+    let output = match trace::output(Command::new(tool).args(view).arg(&branch)) {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(i18n::message("pull.no_tool", &[("tool", &tool)]).into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if output.status.success() {
+        return Ok(true);
+    }
+    let status = trace::status(Command::new(tool).args(create).arg(&branch))?;
+    if !status.success() {
+        return Err(format!("{tool} {} {} failed: {status}", create[0], create[1]).into());
+    }
+    Ok(false)
+}
+
+/// Pull requests loading on a worker thread.
+pub struct PullLoader {
+    result: Receiver<HashMap<String, PullRequest>>,
+}
+
+impl PullLoader {
+    /// Start loading the pull requests of `forge`. Failures (no tool, not logged in,
+    /// offline) leave the list without pull requests.
+    pub fn spawn(forge: Forge) -> PullLoader {
+        let (sender, result) = mpsc::channel();
+        thread::spawn(move || {
+            let pulls = load(forge).unwrap_or_default();
+            let _ = sender.send(pulls);
+        });
+        PullLoader { result }
+    }
+
+    /// The pull requests once loaded; `None` while still loading.
+    pub fn receive(&self) -> Option<HashMap<String, PullRequest>> {
+        match self.result.try_recv() {
+            Ok(pulls) => Some(pulls),
+            Err(TryRecvError::Empty) => None,
+            // The worker died without an answer; there will be none.
+            Err(TryRecvError::Disconnected) => Some(HashMap::new()),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::fs;
+use std::io;
+
+use crate::history;
+
+/// At most this many queries are kept per repository.
+const MAX_QUERIES: usize = 100;
+
+/// Filter queries submitted in the picker, oldest first, one per line in a state file
+/// next to the checkout history. Browsed with Up/Down like a shell's history.
+#[derive(Default)]
+pub struct QueryHistory {
+    queries: Vec<String>,
+    /// The query being shown while browsing; `queries.len()` when not browsing.
+    position: usize,
+    /// What was typed before browsing started, shown again past the newest query.
+    draft: String,
+}
+
+impl QueryHistory {
+    /// The queries saved for the current repository; empty if there are none.
+    pub fn load() -> QueryHistory {
+        let queries: Vec<String> = history::state_file("queries")
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(String::from).collect())
+            .unwrap_or_default();
+        QueryHistory {
+            position: queries.len(),
+            queries,
+            draft: String::new(),
+        }
+    }
+
+    /// Stop browsing, so the next `older` starts again from the newest query.
+    pub fn reset(&mut self) {
+        self.position = self.queries.len();
+    }
+
+    /// The query before the one shown, remembering `current` when browsing starts.
+    pub fn older(&mut self, current: &str) -> Option<&str> {
+        if self.position == 0 {
+            return None;
+        }
+        if self.position == self.queries.len() {
+            self.draft = current.to_string();
+        }
+        self.position -= 1;
+        Some(&self.queries[self.position])
+    }
+
+    /// The query after the one shown, or the original draft past the newest one.
+    pub fn newer(&mut self) -> Option<&str> {
+        if self.position >= self.queries.len() {
+            return None;
+        }
+        self.position += 1;
+        Some(self.queries.get(self.position).unwrap_or(&self.draft))
+    }
+
+    /// Append `query`, dropping an earlier copy of it, and rewrite the file.
+    pub fn add(&mut self, query: &str) -> io::Result<()> {
+        self.queries.retain(|q| q != query);
+        self.queries.push(query.to_string());
+        let excess = self.queries.len().saturating_sub(MAX_QUERIES);
+        self.queries.drain(..excess);
+        self.reset();
+
+        let Some(path) = history::state_file("queries") else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut text = String::new();
+        for query in &self.queries {
+            text.push_str(query);
+            text.push('\n');
+        }
+        fs::write(path, text)
+    }
+}
@@ -0,0 +1,86 @@
+//! Branch names that are not valid UTF-8.
+//!
+//! Git allows almost any bytes in a ref name, while the picker holds names as
+//! `String`s. Git's output is decoded with `decode`, which keeps every byte that is
+//! not part of valid UTF-8 as a private-use character (U+10FF80 to U+10FFFF), so no
+//! name is lost. Those characters themselves are valid UTF-8 too, so where one
+//! really occurs, each of its four bytes is kept that way instead. `to_os` turns such
+//! characters back into the original bytes when a name goes to git or the shell, and
+//! `display` shows them as `\xNN`.
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+
+/// Stand-ins for raw bytes are `RAW_BYTE_BASE + byte`; only bytes from 0x80 up are
+/// ever invalid, so they land at the very end of the last private-use plane.
+const RAW_BYTE_BASE: u32 = 0x10FF00;
+
+/// The stand-in for `byte`, which is 0x80 or above.
+fn stand_in(byte: u8) -> char {
+    char::from_u32(RAW_BYTE_BASE + u32::from(byte)).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn raw_byte(c: char) -> Option<u8> {
+    let code = u32::from(c).checked_sub(RAW_BYTE_BASE)?;
+    (code >= 0x80).then_some(code as u8)
+}
+
+/// Decode git's output, keeping bytes that are not UTF-8 (see the module docs).
+pub fn decode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match raw_byte(c) {
+                // Taken for a stand-in, it would come back as a single byte.
+                Some(_) => {
+                    for &byte in c.encode_utf8(&mut [0; 4]).as_bytes() {
+                        text.push(stand_in(byte));
+                    }
+                }
+                None => text.push(c),
+            }
+        }
+        text.extend(chunk.invalid().iter().map(|&byte| stand_in(byte)));
+    }
+    text
+}
+
+/// The bytes `text` was decoded from.
+pub fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match raw_byte(c) {
+            Some(byte) => bytes.push(byte),
+            None => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    bytes
+}
+
+/// `text` as a command argument or environment value, with its original bytes.
+pub fn to_os(text: &str) -> OsString {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(encode(text))
+    }
+    #[cfg(not(unix))]
+    {
+        OsString::from(text)
+    }
+}
+
+/// `text` for showing on screen or in UTF-8 output, with raw bytes escaped as `\xNN`.
+pub fn display(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| raw_byte(c).is_some()) {
+        return Cow::Borrowed(text);
+    }
+    let mut shown = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match raw_byte(c) {
+            Some(byte) => shown.push_str(&format!("\\x{byte:02x}")),
+            None => shown.push(c),
+        }
+    }
+    Cow::Owned(shown)
+}
@@ -0,0 +1,148 @@
+//! Reading HEAD, branch refs and the HEAD reflog straight from the repository's files,
+//! instead of spawning `git` for each lookup (the `native-refs` feature).
+//!
+//! Only the plain-text parts of the repository are read. Anything unusual (a missing
+//! `.git`, an unreadable file, a reftable repository) yields `None`, and the caller
+//! falls back to running `git`.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::refname;
+
+/// The directories of the repository the current directory is in.
+struct Repository {
+    /// The worktree's git directory, holding its `HEAD` and `logs/HEAD`.
+    git_dir: PathBuf,
+    /// The directory shared by all worktrees, holding `refs/` and `packed-refs`.
+    common_dir: PathBuf,
+}
+
+impl Repository {
+    /// Find the repository like git does: the one `git::pin_repository` resolved,
+    /// `$GIT_DIR`, or else a `.git` directory (or `gitdir:` file, for linked
+    /// worktrees) in the current directory or a parent.
+    fn discover() -> Option<Repository> {
+        let git_dir = match git::pinned_git_dir() {
+            Some(dir) => dir.to_path_buf(),
+            None => match env::var_os("GIT_DIR") {
+                Some(dir) => PathBuf::from(dir),
+                None => {
+                    let cwd = env::current_dir().ok()?;
+                    cwd.ancestors().find_map(git_dir_in)?
+                }
+            },
+        };
+        // Reftable repositories keep their refs in a binary format not read here.
+        if git_dir.join("reftable").exists() {
+            return None;
+        }
+        let common_dir = match fs::read_to_string(git_dir.join("commondir")) {
+            Ok(relative) => git_dir.join(relative.trim()),
+            Err(_) => git_dir.clone(),
+        };
+        Some(Repository {
+            git_dir,
+            common_dir,
+        })
+    }
+}
+
+fn git_dir_in(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let text = fs::read_to_string(&dot_git).ok()?;
+    let target = text.trim().strip_prefix("gitdir: ")?;
+    Some(dir.join(target))
+}
+
+/// The checked-out branch, or an empty string when HEAD is detached.
+pub fn current_branch() -> Option<String> {
+    let repo = Repository::discover()?;
+    let head = refname::decode(&fs::read(repo.git_dir.join("HEAD")).ok()?);
+    Some(match head.trim().strip_prefix("ref: ") {
+        Some(target) => target
+            .strip_prefix("refs/heads/")
+            .unwrap_or(target)
+            .to_string(),
+        None => String::new(),
+    })
+}
+
+/// Whether the local branch `name` exists, as a loose ref or in `packed-refs`.
+pub fn branch_exists(name: &str) -> Option<bool> {
+    let repo = Repository::discover()?;
+    let full_name = format!("refs/heads/{name}");
+    if repo.common_dir.join(refname::to_os(&full_name)).is_file() {
+        return Some(true);
+    }
+    let packed = match fs::read(repo.common_dir.join("packed-refs")) {
+        Ok(bytes) => refname::decode(&bytes),
+        Err(_) => return Some(false),
+    };
+    Some(packed.lines().any(|line| {
+        line.split_once(' ')
+            .is_some_and(|(_, r)| !line.starts_with('#') && r == full_name)
+    }))
+}
+
+/// The messages of the HEAD reflog, newest first, like `git reflog --format=%gs`.
+pub fn head_reflog() -> Option<Vec<String>> {
+    let repo = Repository::discover()?;
+    let text = match fs::read(repo.git_dir.join("logs/HEAD")) {
+        Ok(bytes) => refname::decode(&bytes),
+        // A fresh repository has no reflog yet.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Some(Vec::new()),
+        Err(_) => return None,
+    };
+    // Each line is "<old> <new> <name> <email> <time> <zone>\t<message>".
+    Some(
+        text.lines()
+            .rev()
+            .filter_map(|line| {
+                line.split_once('\t')
+                    .map(|(_, message)| message.to_string())
+            })
+            .collect(),
+    )
+}
+
+/// The worktree's git directory and the contents of every file the branch listing
+/// depends on: HEAD, `packed-refs` and the loose branch refs, with their names. Two
+/// equal snapshots mean the same branches pointing at the same commits.
+pub fn snapshot() -> Option<(PathBuf, Vec<u8>)> {
+    let repo = Repository::discover()?;
+    let mut bytes = fs::read(repo.git_dir.join("HEAD")).ok()?;
+    match fs::read(repo.common_dir.join("packed-refs")) {
+        Ok(packed) => bytes.extend(packed),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(_) => return None,
+    }
+    let mut dirs = vec![repo.common_dir.join("refs/heads")];
+    while let Some(dir) = dirs.pop() {
+        let mut entries: Vec<PathBuf> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .map(|e| e.map(|e| e.path()))
+                .collect::<Result<_, _>>()
+                .ok()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(_) => return None,
+        };
+        entries.sort();
+        for path in entries {
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                bytes.push(0);
+                bytes.extend(path.as_os_str().as_encoded_bytes());
+                bytes.push(0);
+                bytes.extend(fs::read(&path).ok()?);
+            }
+        }
+    }
+    Some((repo.git_dir, bytes))
+}
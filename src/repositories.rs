@@ -0,0 +1,56 @@
+//! The worktrees git-recent was used in, most recent first, one path per line in
+//! `$XDG_STATE_HOME/git-recent/repositories`. Offered when it is started outside a
+//! repository.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::history;
+use crate::refname;
+
+/// At most this many repositories are remembered.
+const MAX_REPOSITORIES: usize = 20;
+
+fn state_path() -> Option<PathBuf> {
+    Some(history::state_dir()?.join("repositories"))
+}
+
+fn read() -> Vec<PathBuf> {
+    let Some(bytes) = state_path().and_then(|path| fs::read(path).ok()) else {
+        return Vec::new();
+    };
+    refname::decode(&bytes)
+        .lines()
+        .map(|line| PathBuf::from(refname::to_os(line)))
+        .collect()
+}
+
+/// Remembered worktrees that still exist, most recent first.
+pub fn recent() -> Vec<PathBuf> {
+    read().into_iter().filter(|path| path.is_dir()).collect()
+}
+
+/// Move `worktree` to the front of the list, rewriting the file only if it changed.
+pub fn record(worktree: &Path) -> io::Result<()> {
+    let mut paths = read();
+    if paths.first().map(PathBuf::as_path) == Some(worktree) {
+        return Ok(());
+    }
+    paths.retain(|path| path != worktree);
+    paths.insert(0, worktree.to_path_buf());
+    paths.truncate(MAX_REPOSITORIES);
+
+    let Some(path) = state_path() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut text = String::new();
+    for path in &paths {
+        text.push_str(&refname::decode(path.as_os_str().as_encoded_bytes()));
+        text.push('\n');
+    }
+    fs::write(path, refname::encode(&text))
+}
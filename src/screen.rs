@@ -0,0 +1,71 @@
+use std::io::{self, Write};
+
+use crate::terminal;
+
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[J";
+const CLEAR_TO_END_OF_LINE: &str = "\x1b[K";
+const CLEAR_TO_END_OF_SCREEN: &str = "\x1b[J";
+/// Synchronized output: terminals that support it show the whole update at once.
+const BEGIN_SYNC: &str = "\x1b[?2026h";
+const END_SYNC: &str = "\x1b[?2026l";
+/// Lines longer than the terminal is wide are cut off while the frame is drawn
+/// instead of wrapping onto (and shifting) the rows below.
+const DISABLE_WRAP: &str = "\x1b[?7l";
+const ENABLE_WRAP: &str = "\x1b[?7h";
+
+/// Frame-diffing renderer. Remembers the last frame drawn and only rewrites the rows
+/// that changed, so moving the selection repaints two lines instead of the whole screen.
+#[derive(Default)]
+pub struct Screen {
+    previous: Option<Vec<String>>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Screen { previous: None }
+    }
+
+    /// Forget the last frame so the next draw clears and repaints everything.
+    pub fn invalidate(&mut self) {
+        self.previous = None;
+    }
+
+    /// Draw `lines` starting at the top-left corner of the screen.
+    pub fn draw(&mut self, lines: &[String]) -> io::Result<()> {
+        let buf = self.update(lines);
+        let mut out = terminal::ui();
+        out.write_all(buf.as_bytes())?;
+        out.flush()
+    }
+
+    /// The output that turns the last frame into `lines`, which then becomes the
+    /// last frame. `draw` writes it to the terminal.
+    pub fn update(&mut self, lines: &[String]) -> String {
+        let mut buf = format!("{BEGIN_SYNC}{DISABLE_WRAP}");
+        let previous = match &self.previous {
+            Some(previous) => previous.as_slice(),
+            None => {
+                buf.push_str(CLEAR_SCREEN);
+                &[]
+            }
+        };
+
+        for (row, line) in lines.iter().enumerate() {
+            if previous.get(row) != Some(line) {
+                buf.push_str(&format!("\x1b[{};1H{line}{CLEAR_TO_END_OF_LINE}", row + 1));
+            }
+        }
+        if lines.len() < previous.len() {
+            buf.push_str(&format!(
+                "\x1b[{};1H{CLEAR_TO_END_OF_SCREEN}",
+                lines.len() + 1
+            ));
+        }
+        // Park the cursor below the frame so other output (e.g. a spinner) cannot clobber it.
+        buf.push_str(&format!("\x1b[{};1H", lines.len() + 1));
+        buf.push_str(ENABLE_WRAP);
+        buf.push_str(END_SYNC);
+        self.previous = Some(lines.to_vec());
+        buf
+    }
+}
@@ -0,0 +1,258 @@
+//! `git-recent --serve`: JSON-RPC 2.0 over stdin and stdout, one message per line,
+//! so editor plugins can list, check out and delete branches through git-recent
+//! while drawing their own picker.
+//!
+//! Methods:
+//! - `list` `{"query"?, "limit"?}`: the current branch and the branches, most recent
+//!   first, each as `--json` prints it. Loaded on first use and then kept.
+//! - `refresh`: like `list`, but reads the branches from git again first.
+//! - `checkout` `{"branch"}`: check the branch out, running the checkout hooks.
+//! - `delete` `{"branch", "force"?}`: delete the branch; without `force` only if it
+//!   is merged. Protected branches are refused.
+//!
+//! Requests without an `id` are notifications and get no response. The server stops
+//! when stdin is closed.
+
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use crate::app;
+use crate::backend::{GitBackend, SystemGit};
+use crate::error::AppError;
+use crate::filter::{self, Query};
+use crate::git::{self, BranchInfo, LoadOptions, Location, SwitchCommand};
+use crate::hooks::Hooks;
+use crate::i18n;
+use crate::json::{self, Value};
+use crate::refname;
+
+/// JSON-RPC's own error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// A request that was understood but failed, e.g. a checkout git refused.
+const FAILED: i64 = -32000;
+
+struct RpcError {
+    code: i64,
+    message: String,
+    /// git-recent's exit status for the same failure, when it has a specific one.
+    exit_code: Option<i32>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            exit_code: None,
+        }
+    }
+}
+
+impl From<Box<dyn Error>> for RpcError {
+    fn from(e: Box<dyn Error>) -> Self {
+        RpcError {
+            code: FAILED,
+            message: e.to_string(),
+            exit_code: e.downcast_ref::<AppError>().map(AppError::exit_code),
+        }
+    }
+}
+
+impl From<String> for RpcError {
+    fn from(message: String) -> Self {
+        RpcError::new(FAILED, message)
+    }
+}
+
+/// The settings requests are carried out with, and the branches last loaded.
+pub struct Server {
+    load: LoadOptions,
+    location: Location,
+    switch: SwitchCommand,
+    hooks: Hooks,
+    /// Glob patterns of branches `delete` refuses (`protected`).
+    protected: Vec<String>,
+    branches: Option<Vec<BranchInfo>>,
+}
+
+impl Server {
+    pub fn new(
+        load: LoadOptions,
+        location: Location,
+        switch: SwitchCommand,
+        hooks: Hooks,
+        protected: Vec<String>,
+    ) -> Self {
+        Server {
+            load,
+            location,
+            switch,
+            hooks,
+            protected,
+            branches: None,
+        }
+    }
+
+    /// Answer requests from `input` on `output` until `input` ends.
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.split(b'\n') {
+            let line = line?;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            if let Some(response) = self.handle(&line) {
+                output.write_all(response.as_bytes())?;
+                output.write_all(b"\n")?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The response to one line, or `None` for a notification.
+    fn handle(&mut self, line: &[u8]) -> Option<String> {
+        let request = match std::str::from_utf8(line)
+            .map_err(|e| e.to_string())
+            .and_then(json::parse)
+        {
+            Ok(request) => request,
+            Err(e) => {
+                let error = RpcError::new(PARSE_ERROR, format!("invalid JSON: {e}"));
+                return Some(respond(&Value::Null, Err(error)));
+            }
+        };
+        let id = request.get("id").cloned();
+        let method = match (&request, request.get("method").and_then(Value::as_str)) {
+            (Value::Object(_), Some(method)) => method,
+            _ => {
+                let error = RpcError::new(INVALID_REQUEST, "expected a request object");
+                return Some(respond(&id.unwrap_or(Value::Null), Err(error)));
+            }
+        };
+        let result = self.call(method, request.get("params"));
+        id.map(|id| respond(&id, result))
+    }
+
+    fn call(&mut self, method: &str, params: Option<&Value>) -> Result<String, RpcError> {
+        let param = |name: &str| params.and_then(|p| p.get(name));
+        match method {
+            "list" | "refresh" => {
+                if method == "refresh" {
+                    self.branches = None;
+                }
+                let query = match param("query") {
+                    None | Some(Value::Null) => None,
+                    Some(query) => Some(query.as_str().ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, "'query' must be a string")
+                    })?),
+                };
+                let limit = match param("limit") {
+                    None | Some(Value::Null) => self.load.limit,
+                    Some(limit) => Some(limit.as_usize().ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, "'limit' must be a whole number")
+                    })?),
+                };
+                self.list(query, limit)
+            }
+            "checkout" => {
+                let branch = branch_param(params)?;
+                if self.location == Location::NoWorkTree {
+                    return Err(Box::<dyn Error>::from(AppError::NoWorkTree).into());
+                }
+                app::switch_to(
+                    &mut SystemGit,
+                    &self.load.scope.checkout_name(branch),
+                    true,
+                    &self.switch,
+                    &self.hooks,
+                )?;
+                self.branches = None;
+                Ok("null".to_string())
+            }
+            "delete" => {
+                let branch = branch_param(params)?;
+                let force = match param("force") {
+                    None | Some(Value::Null) => false,
+                    Some(force) => force.as_bool().ok_or_else(|| {
+                        RpcError::new(INVALID_PARAMS, "'force' must be true or false")
+                    })?,
+                };
+                if self.protected.iter().any(|p| filter::glob_match(p, branch)) {
+                    return Err(RpcError::new(
+                        FAILED,
+                        i18n::message("delete.protected", &[("branch", &branch)]),
+                    ));
+                }
+                SystemGit.delete_branch(branch, force)?;
+                self.branches = None;
+                Ok("null".to_string())
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{method}'"),
+            )),
+        }
+    }
+
+    /// `{"current": ..., "branches": [...]}` for the branches matching `query`.
+    fn list(&mut self, query: Option<&str>, limit: Option<usize>) -> Result<String, RpcError> {
+        let branches = match &mut self.branches {
+            Some(branches) => branches,
+            None => {
+                // Every branch, so queries reach past the limit; it applies per request.
+                let everything = LoadOptions {
+                    limit: None,
+                    track: true,
+                    ..self.load.clone()
+                };
+                self.branches.insert(git::load_branches(&everything)?.1)
+            }
+        };
+        let query = query.map(|q| Query::new(q, self.load.case));
+        let matching: Vec<String> = branches
+            .iter()
+            .filter(|b| query.as_ref().is_none_or(|q| q.matches(&b.name)))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(json::branch)
+            .collect();
+        let current = branches.iter().find(|b| b.current);
+        Ok(format!(
+            "{{\"current\": {}, \"branches\": [{}]}}",
+            json::optional(current, |b| json::string(&refname::display(&b.name))),
+            matching.join(", ")
+        ))
+    }
+}
+
+/// The required `branch` parameter.
+fn branch_param(params: Option<&Value>) -> Result<&str, RpcError> {
+    params
+        .and_then(|p| p.get("branch"))
+        .and_then(Value::as_str)
+        .filter(|b| !b.is_empty())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "'branch' must be a branch name"))
+}
+
+/// A response to the request with `id`, carrying `result` (already JSON) or an error.
+fn respond(id: &Value, result: Result<String, RpcError>) -> String {
+    let outcome = match result {
+        Ok(result) => format!("\"result\": {result}"),
+        Err(e) => {
+            let data = e.exit_code.map_or(String::new(), |code| {
+                format!(", \"data\": {{\"exit_code\": {code}}}")
+            });
+            format!(
+                "\"error\": {{\"code\": {}, \"message\": {}{data}}}",
+                e.code,
+                json::string(&e.message)
+            )
+        }
+    };
+    format!(
+        "{{\"jsonrpc\": \"2.0\", \"id\": {}, {outcome}}}",
+        id.encode()
+    )
+}
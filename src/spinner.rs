@@ -0,0 +1,77 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::terminal;
+
+const FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+/// The frames for terminals that only show ASCII (`--ascii`).
+const ASCII_FRAMES: &[&str] = &["|", "/", "-", "\\"];
+const FRAME_INTERVAL: Duration = Duration::from_millis(80);
+/// Operations that finish faster than this never show the spinner.
+const SPINNER_DELAY: Duration = Duration::from_millis(150);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+const CLEAR_LINE: &str = "\r\x1b[K";
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Never draw the spinner from now on, e.g. in accessible mode.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Draw the spinner with ASCII characters from now on.
+pub fn use_ascii() {
+    ASCII.store(true, Ordering::Relaxed);
+}
+
+/// Run `work` on a worker thread and draw a spinner with `label` until it returns.
+/// The spinner line is erased again before the result is handed back. Nothing is
+/// drawn when stdout is not a terminal, so piped output stays clean.
+pub fn with_spinner<T, F>(label: &str, work: F) -> T
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    if DISABLED.load(Ordering::Relaxed) || !terminal::ui_is_terminal() {
+        return work();
+    }
+    thread::scope(|scope| {
+        let handle = scope.spawn(work);
+        let started = Instant::now();
+        let mut next_frame = started + SPINNER_DELAY;
+        let mut frame = 0;
+
+        while !handle.is_finished() {
+            let now = Instant::now();
+            if now >= next_frame {
+                let mut out = terminal::ui();
+                let frames = if ASCII.load(Ordering::Relaxed) {
+                    ASCII_FRAMES
+                } else {
+                    FRAMES
+                };
+                let glyph = frames[frame % frames.len()];
+                let _ = write!(out, "{CLEAR_LINE}{glyph} {label}");
+                let _ = out.flush();
+                frame += 1;
+                next_frame = now + FRAME_INTERVAL;
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        if frame > 0 {
+            let mut out = terminal::ui();
+            let _ = write!(out, "{CLEAR_LINE}");
+            let _ = out.flush();
+        }
+
+        match handle.join() {
+            Ok(value) => value,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    })
+}
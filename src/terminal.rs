@@ -0,0 +1,415 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[J";
+/// Push kitty keyboard flags: disambiguate escape codes (1) and report event types (2).
+const PUSH_KEYBOARD_FLAGS: &str = "\x1b[>3u";
+const POP_KEYBOARD_FLAGS: &str = "\x1b[<u";
+const ENABLE_BRACKETED_PASTE: &str = "\x1b[?2004h";
+const DISABLE_BRACKETED_PASTE: &str = "\x1b[?2004l";
+/// Conventional exit status for a process terminated by SIGINT (128 + 2).
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Whether raw mode is currently enabled, so an interrupt knows what to undo.
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+/// Whether kitty keyboard flags were pushed and must be popped again on exit.
+static KITTY_KEYBOARD: AtomicBool = AtomicBool::new(false);
+/// Whether `suspend` is running a child (such as a pager) that has the terminal and
+/// takes Ctrl-C as its own.
+static SUSPENDED: AtomicBool = AtomicBool::new(false);
+/// The writing end of the pipe the SIGINT handler wakes the watcher thread through;
+/// -1 until `install_interrupt_handler` made it.
+#[cfg(unix)]
+static INTERRUPT_PIPE: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(-1);
+
+/// The terminal settings from before raw mode was entered, restored on the way out.
+#[cfg(unix)]
+static SAVED_MODE: std::sync::Mutex<Option<sys::Termios>> = std::sync::Mutex::new(None);
+
+/// Where the interactive UI is drawn once moved off stdout (see `move_ui_off_stdout`).
+static UI_TTY: OnceLock<Option<File>> = OnceLock::new();
+
+/// Writer for everything the interactive UI draws: the menu, spinner and escape sequences.
+pub struct Ui;
+
+/// The UI output stream: stdout unless it was moved off stdout for `--print`.
+pub fn ui() -> Ui {
+    Ui
+}
+
+impl Write for Ui {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match UI_TTY.get() {
+            None => io::stdout().write(buf),
+            Some(Some(tty)) => (&*tty).write(buf),
+            Some(None) => io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match UI_TTY.get() {
+            None => io::stdout().flush(),
+            Some(Some(tty)) => (&*tty).flush(),
+            Some(None) => io::stderr().flush(),
+        }
+    }
+}
+
+/// The UI output stream for a child process to write to, such as a pager.
+pub fn ui_stdio() -> io::Result<Stdio> {
+    Ok(match UI_TTY.get() {
+        None => Stdio::inherit(),
+        Some(Some(tty)) => Stdio::from(tty.try_clone()?),
+        Some(None) => Stdio::from(io::stderr()),
+    })
+}
+
+/// Draw the UI on the controlling terminal (or stderr if there is none), leaving
+/// stdout free for machine-readable output such as the selected branch.
+pub fn move_ui_off_stdout() {
+    UI_TTY.get_or_init(|| OpenOptions::new().write(true).open("/dev/tty").ok());
+}
+
+/// Keep stdout for the caller alone: returns a writer on it and points file
+/// descriptor 1 at stderr, so git, hooks and stray prints cannot end up in what the
+/// caller writes there (`--serve`'s messages).
+pub fn take_stdout() -> io::Result<Box<dyn Write>> {
+    #[cfg(unix)]
+    {
+        use std::os::fd::AsFd;
+        io::stdout().flush()?;
+        let stdout = io::stdout().as_fd().try_clone_to_owned()?;
+        // SAFETY: both descriptors are open; dup2 only changes what descriptor 1 refers to.
+        if unsafe { sys::dup2(2, 1) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Box::new(io::BufWriter::new(File::from(stdout))))
+    }
+    #[cfg(not(unix))]
+    Ok(Box::new(io::stdout()))
+}
+
+/// Read one line from the controlling terminal, for prompts when stdin is not a terminal.
+pub fn read_tty_line() -> io::Result<String> {
+    let tty = File::open("/dev/tty")?;
+    let mut line = String::new();
+    io::BufReader::new(tty).read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Whether the UI output stream is a terminal (and so can show spinners and menus).
+pub fn ui_is_terminal() -> bool {
+    match UI_TTY.get() {
+        None => io::stdout().is_terminal(),
+        Some(Some(tty)) => tty.is_terminal(),
+        Some(None) => io::stderr().is_terminal(),
+    }
+}
+
+/// Put the terminal on stdin into raw mode without echo (like `stty raw -echo`),
+/// saving its settings for `restore_mode`. Returns false if stdin is not a terminal.
+fn enter_raw_mode() -> bool {
+    #[cfg(unix)]
+    {
+        let mut termios = sys::Termios::zeroed();
+        // SAFETY: `termios` is large and aligned enough for the platform's struct termios.
+        if unsafe { sys::tcgetattr(0, &mut termios) } != 0 {
+            return false;
+        }
+        let saved = termios;
+        // SAFETY: as above; `termios` was filled in by tcgetattr.
+        unsafe { sys::cfmakeraw(&mut termios) };
+        if unsafe { sys::tcsetattr(0, sys::TCSADRAIN, &termios) } != 0 {
+            return false;
+        }
+        *SAVED_MODE.lock().unwrap_or_else(|e| e.into_inner()) = Some(saved);
+        true
+    }
+    #[cfg(not(unix))]
+    false
+}
+
+/// Put back the terminal settings saved by `enter_raw_mode`, if any.
+fn restore_mode() {
+    #[cfg(unix)]
+    if let Some(saved) = SAVED_MODE.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        // SAFETY: `saved` came from tcgetattr on the same descriptor.
+        unsafe { sys::tcsetattr(0, sys::TCSADRAIN, &saved) };
+    }
+}
+
+/// RAII guard that enables raw mode while alive and restores terminal state on Drop.
+/// Uses termios on unix. On non-unix this is a no-op.
+pub struct RawModeGuard {
+    enabled: bool,
+}
+
+impl RawModeGuard {
+    // Not `Default`: creating a guard changes the terminal's state.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        // Enable raw mode and disable echo for cleaner key handling.
+        let enabled = enter_raw_mode();
+        if enabled {
+            RAW_MODE.store(true, Ordering::SeqCst);
+            // Pasted text arrives wrapped in markers instead of as a burst of keystrokes.
+            let mut out = ui();
+            let _ = write!(out, "{ENABLE_BRACKETED_PASTE}");
+            let _ = out.flush();
+        }
+        RawModeGuard { enabled }
+    }
+
+    /// Whether raw mode was entered (always false off unix).
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Opt into the kitty keyboard protocol; undone automatically when the terminal is restored.
+pub fn enable_kitty_keyboard() -> io::Result<()> {
+    let mut out = ui();
+    write!(out, "{PUSH_KEYBOARD_FLAGS}")?;
+    out.flush()?;
+    KITTY_KEYBOARD.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+fn disable_kitty_keyboard() {
+    if KITTY_KEYBOARD.swap(false, Ordering::SeqCst) {
+        let mut out = ui();
+        let _ = write!(out, "{POP_KEYBOARD_FLAGS}");
+        let _ = out.flush();
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        disable_kitty_keyboard();
+        if self.enabled && RAW_MODE.swap(false, Ordering::SeqCst) {
+            // Restore canonical mode and re-enable echo.
+            restore_mode();
+            let mut out = ui();
+            let _ = write!(out, "{DISABLE_BRACKETED_PASTE}");
+            let _ = out.flush();
+        }
+    }
+}
+
+/// Run `f` (e.g. a pager) with the terminal in its normal state, then go back to raw
+/// mode. The caller has to repaint the whole screen afterwards. Ctrl-C meanwhile is
+/// left to the child: it reaches the whole process group, and exiting under a pager
+/// that carries on would leave the terminal to both.
+pub fn suspend<T>(f: impl FnOnce() -> T) -> T {
+    let raw = RAW_MODE.load(Ordering::SeqCst);
+    let kitty = KITTY_KEYBOARD.load(Ordering::SeqCst);
+    let mut out = ui();
+    if kitty {
+        let _ = write!(out, "{POP_KEYBOARD_FLAGS}");
+    }
+    if raw {
+        restore_mode();
+        let _ = write!(out, "{DISABLE_BRACKETED_PASTE}");
+    }
+    let _ = write!(out, "{CLEAR_SCREEN}{SHOW_CURSOR}");
+    let _ = out.flush();
+
+    let suspended = SUSPENDED.swap(true, Ordering::SeqCst);
+    let result = f();
+    SUSPENDED.store(suspended, Ordering::SeqCst);
+
+    if raw {
+        enter_raw_mode();
+        let _ = write!(out, "{ENABLE_BRACKETED_PASTE}");
+    }
+    if kitty {
+        let _ = write!(out, "{PUSH_KEYBOARD_FLAGS}");
+    }
+    let _ = write!(out, "{HIDE_CURSOR}");
+    let _ = out.flush();
+    result
+}
+
+/// Leave raw mode (if active) and make the cursor visible again.
+fn restore_terminal() {
+    disable_kitty_keyboard();
+    let mut out = ui();
+    if RAW_MODE.swap(false, Ordering::SeqCst) {
+        restore_mode();
+        let _ = write!(out, "{DISABLE_BRACKETED_PASTE}");
+    }
+    let _ = write!(out, "{SHOW_CURSOR}\r\n");
+    let _ = out.flush();
+}
+
+/// Restore the terminal before a panic message is printed, so the message is readable
+/// and the shell is left in a usable state, then report the panic as usual. Guards
+/// dropped while unwinding find nothing left to undo.
+pub fn install_panic_hook() {
+    let report = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if RAW_MODE.load(Ordering::SeqCst) || KITTY_KEYBOARD.load(Ordering::SeqCst) {
+            restore_terminal();
+        }
+        report(info);
+    }));
+}
+
+/// Restore the terminal and exit with the conventional SIGINT status.
+/// Used both for the SIGINT signal and for Ctrl-C read as a key while in raw mode.
+pub fn interrupt() -> ! {
+    restore_terminal();
+    std::process::exit(EXIT_INTERRUPTED);
+}
+
+/// Wait until stdin has input, or until `timeout` passes (`None` waits forever).
+/// Returns whether input is ready.
+pub fn poll_input(timeout: Option<Duration>) -> io::Result<bool> {
+    #[cfg(unix)]
+    {
+        let mut fd = sys::PollFd {
+            fd: 0,
+            events: sys::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.map_or(-1, |t| t.as_millis().min(i32::MAX as u128) as i32);
+        loop {
+            // SAFETY: `fd` is a valid pollfd and we pass a count of exactly one.
+            let ready = unsafe { sys::poll(&mut fd, 1, timeout_ms) };
+            if ready >= 0 {
+                return Ok(ready > 0);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = timeout;
+        Ok(true)
+    }
+}
+
+/// Read whatever bytes are available from stdin, bypassing std's buffering
+/// so that `poll_input` sees exactly what has not been consumed yet.
+pub fn read_input(buf: &mut [u8]) -> io::Result<usize> {
+    #[cfg(unix)]
+    {
+        use std::fs::File;
+        use std::io::Read;
+        use std::mem::ManuallyDrop;
+        use std::os::fd::FromRawFd;
+
+        // SAFETY: fd 0 stays open for the life of the process; ManuallyDrop keeps it open.
+        let mut stdin = ManuallyDrop::new(unsafe { File::from_raw_fd(0) });
+        stdin.read(buf)
+    }
+    #[cfg(not(unix))]
+    {
+        use std::io::Read;
+        io::stdin().read(buf)
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::ffi::{c_int, c_short};
+
+    pub const SIGINT: c_int = 2;
+    pub const POLLIN: c_short = 1;
+    pub const TCSADRAIN: c_int = 1;
+
+    #[cfg(target_os = "macos")]
+    pub type NFds = std::ffi::c_uint;
+    #[cfg(not(target_os = "macos"))]
+    pub type NFds = std::ffi::c_ulong;
+
+    #[repr(C)]
+    pub struct PollFd {
+        pub fd: c_int,
+        pub events: c_short,
+        pub revents: c_short,
+    }
+
+    /// `struct termios`, whose layout differs between platforms. It is only filled in
+    /// by `tcgetattr` and handed back to libc, so an opaque buffer that is larger and
+    /// at least as aligned as any platform's struct will do.
+    #[repr(C, align(8))]
+    #[derive(Clone, Copy)]
+    pub struct Termios([u8; 256]);
+
+    impl Termios {
+        pub fn zeroed() -> Self {
+            Termios([0; 256])
+        }
+    }
+
+    unsafe extern "C" {
+        pub fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+        pub fn tcsetattr(fd: c_int, actions: c_int, termios: *const Termios) -> c_int;
+        pub fn cfmakeraw(termios: *mut Termios);
+        pub fn signal(signum: c_int, handler: usize) -> usize;
+        pub fn poll(fds: *mut PollFd, nfds: NFds, timeout: c_int) -> c_int;
+        pub fn dup2(old: c_int, new: c_int) -> c_int;
+        pub fn write(fd: c_int, buf: *const u8, count: usize) -> isize;
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: std::ffi::c_int) {
+    // Only async-signal-safe work here; the watcher thread does the cleanup.
+    if SUSPENDED.load(Ordering::SeqCst) {
+        return;
+    }
+    let fd = INTERRUPT_PIPE.load(Ordering::SeqCst);
+    if fd >= 0 {
+        // SAFETY: write(2) is async-signal-safe and the byte outlives the call.
+        unsafe {
+            sys::write(fd, &1, 1);
+        }
+    }
+}
+
+/// Catch SIGINT (e.g. Ctrl-C while git is running outside raw mode) so the terminal
+/// is restored before exiting, instead of being left in raw mode with a hidden cursor.
+/// Ctrl-C while `suspend` runs a child is left to the child.
+pub fn install_interrupt_handler() {
+    #[cfg(unix)]
+    {
+        use std::io::Read;
+        use std::os::fd::IntoRawFd;
+
+        // Without the pipe, SIGINT keeps its default of ending the process.
+        let Ok((mut wake, waker)) = io::pipe() else {
+            return;
+        };
+        // The writing end stays open for the life of the process.
+        INTERRUPT_PIPE.store(waker.into_raw_fd(), Ordering::SeqCst);
+        let handler = on_sigint as extern "C" fn(std::ffi::c_int);
+        // SAFETY: the handler only reads atomics and calls write(2), both
+        // async-signal-safe.
+        unsafe {
+            sys::signal(sys::SIGINT, handler as usize);
+        }
+        // Sleeps in read(2) until the handler writes a byte.
+        thread::spawn(move || {
+            let mut byte = [0];
+            loop {
+                match wake.read(&mut byte) {
+                    Ok(1) => interrupt(),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    _ => return,
+                }
+            }
+        });
+    }
+}
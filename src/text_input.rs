@@ -0,0 +1,99 @@
+use crate::keys::Key;
+
+const CURSOR: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+/// Single-line text input with readline-style editing.
+/// Shared by the filter, create-branch and rename prompts.
+pub struct TextInput {
+    prompt: String,
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self::with_value(prompt, "")
+    }
+
+    /// Create an input pre-filled with `value`, cursor at the end.
+    pub fn with_value(prompt: impl Into<String>, value: &str) -> Self {
+        let chars: Vec<char> = value.chars().collect();
+        TextInput {
+            prompt: prompt.into(),
+            cursor: chars.len(),
+            chars,
+        }
+    }
+
+    pub fn value(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    /// Render the prompt and text, drawing the cursor as a reverse-video cell.
+    pub fn render(&self) -> String {
+        let before: String = self.chars[..self.cursor].iter().collect();
+        let (at, after) = match self.chars.get(self.cursor) {
+            Some(c) => (
+                c.to_string(),
+                self.chars[self.cursor + 1..].iter().collect(),
+            ),
+            None => (" ".to_string(), String::new()),
+        };
+        format!("{}{before}{CURSOR}{at}{RESET}{after}", self.prompt)
+    }
+
+    /// The prompt and text without a drawn cursor, for accessible mode.
+    pub fn announcement(&self) -> String {
+        format!("{}{}", self.prompt, self.value())
+    }
+
+    /// Feed a key to the input. Returns `Some(true)` on Enter, `Some(false)` on cancel.
+    pub fn handle_key(&mut self, key: Key) -> Option<bool> {
+        match key {
+            Key::Enter => return Some(true),
+            Key::Esc | Key::Ctrl('g') => return Some(false),
+            Key::Char(c) => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Paste(text) => {
+                // Single-line input: drop newlines and other control characters.
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    self.chars.insert(self.cursor, c);
+                    self.cursor += 1;
+                }
+            }
+            Key::Left | Key::Ctrl('b') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right | Key::Ctrl('f') => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.chars.len(),
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            Key::Delete | Key::Ctrl('d') if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            Key::Ctrl('u') => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::Ctrl('k') => self.chars.truncate(self.cursor),
+            Key::Ctrl('w') => {
+                // Delete the word before the cursor, along with any whitespace after it.
+                let mut start = self.cursor;
+                while start > 0 && self.chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                while start > 0 && !self.chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                self.chars.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            _ => {}
+        }
+        None
+    }
+}
@@ -0,0 +1,303 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::i18n;
+
+/// Whether the user asked for no colors by setting `NO_COLOR` to anything but the
+/// empty string (see no-color.org).
+pub fn no_color() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+}
+
+/// Colors configurable as `colors.<slot>`.
+pub const SLOTS: &[&str] = &["highlight", "highlight_text", "pagination"];
+
+/// The color of each slot in the default theme.
+pub const DEFAULT_COLORS: &[(&str, &str)] = &[
+    ("highlight", "blue"),
+    ("highlight_text", "black"),
+    ("pagination", "white"),
+];
+
+const COLOR_NAMES: &[&str] = &[
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The usual RGB values of the 16 named colors (xterm's defaults), for picking the
+/// closest one to a color the terminal cannot show.
+const NAMED_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// The 8 basic colors and their bright variants.
+    Basic,
+    /// The xterm 256-color palette.
+    Indexed,
+    /// Any RGB color.
+    TrueColor,
+}
+
+impl ColorDepth {
+    /// The terminal's color depth: 24-bit when `COLORTERM` says so, otherwise the
+    /// `colors` capability of `TERM`'s terminfo entry, or a guess from the name of
+    /// `TERM` when there is no entry to read.
+    pub fn detect() -> ColorDepth {
+        if matches!(env::var("COLORTERM").as_deref(), Ok("truecolor" | "24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        match terminfo_colors(&term) {
+            Some(colors) if colors >= 1 << 24 => ColorDepth::TrueColor,
+            Some(colors) if colors >= 256 => ColorDepth::Indexed,
+            Some(_) => ColorDepth::Basic,
+            None if term.ends_with("-direct") => ColorDepth::TrueColor,
+            None if term.contains("256color") => ColorDepth::Indexed,
+            None => ColorDepth::Basic,
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        i18n::text(match self {
+            ColorDepth::Basic => "colors.basic",
+            ColorDepth::Indexed => "colors.indexed",
+            ColorDepth::TrueColor => "colors.true_color",
+        })
+    }
+}
+
+/// A color as written in the config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Color {
+    /// One of the 16 named colors: 0-7 basic, 8-15 bright.
+    Named(u8),
+    /// An entry of the 256-color palette.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Parse a color name, optionally prefixed with `bright-`, a palette number from
+    /// 0 to 255, or `#rrggbb`.
+    fn parse(text: &str) -> Option<Color> {
+        if let Some(hex) = text.strip_prefix('#') {
+            if hex.len() != 6 || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+        if let Ok(index) = text.parse::<u8>() {
+            return Some(Color::Indexed(index));
+        }
+        let (name, bright) = match text.strip_prefix("bright-") {
+            Some(rest) => (rest, 8),
+            None => (text, 0),
+        };
+        let position = COLOR_NAMES.iter().position(|&n| n == name)?;
+        Some(Color::Named(bright + position as u8))
+    }
+
+    /// The SGR parameters selecting this color on a terminal with `depth`, where
+    /// `base` is 30 for foreground or 40 for background. Colors beyond the depth
+    /// become the closest one it has.
+    fn sgr(self, base: u8, depth: ColorDepth) -> String {
+        let extended = base + 8;
+        match (self, depth) {
+            (Color::Named(n), _) if n < 8 => format!("{}", base + n),
+            (Color::Named(n), _) => format!("{}", base + 60 + n - 8),
+            (Color::Rgb(r, g, b), ColorDepth::TrueColor) => format!("{extended};2;{r};{g};{b}"),
+            (Color::Indexed(n), ColorDepth::Indexed | ColorDepth::TrueColor) => {
+                format!("{extended};5;{n}")
+            }
+            (Color::Rgb(r, g, b), ColorDepth::Indexed) => {
+                format!("{extended};5;{}", nearest_indexed((r, g, b)))
+            }
+            (Color::Indexed(n), ColorDepth::Basic) => {
+                Color::Named(nearest_named(indexed_rgb(n))).sgr(base, depth)
+            }
+            (Color::Rgb(r, g, b), ColorDepth::Basic) => {
+                Color::Named(nearest_named((r, g, b))).sgr(base, depth)
+            }
+        }
+    }
+}
+
+/// Escape sequences used to draw the branch list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// The highlighted (selected) row.
+    pub highlight: String,
+    /// `(less)`/`(more)` when there is something to scroll to.
+    pub pagination: String,
+    /// `(less)`/`(more)` at either end of the list.
+    pub pagination_inactive: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            highlight: "\x1b[44;30m".to_string(),
+            pagination: "\x1b[47;30m".to_string(),
+            pagination_inactive: "\x1b[30m".to_string(),
+        }
+    }
+}
+
+impl Theme {
+    /// Reverse video for the highlighted row and nothing else (`--ascii`, `NO_COLOR`).
+    pub fn ascii() -> Theme {
+        Theme {
+            highlight: "\x1b[7m".to_string(),
+            pagination: String::new(),
+            pagination_inactive: String::new(),
+        }
+    }
+
+    /// Build a theme from `(slot, color)` pairs over the defaults, for a terminal
+    /// showing `depth` colors.
+    pub fn from_colors<'a>(
+        colors: impl IntoIterator<Item = (&'a str, &'a str)>,
+        depth: ColorDepth,
+    ) -> Result<Theme, String> {
+        let mut highlight_bg = Color::Named(4);
+        let mut highlight_fg = Color::Named(0);
+        let mut pagination_bg = Color::Named(7);
+        for (slot, name) in colors {
+            let color = Color::parse(name).ok_or_else(|| {
+                i18n::message(
+                    "config.unknown_color",
+                    &[
+                        ("name", &name),
+                        ("slot", &slot),
+                        ("names", &COLOR_NAMES.join(", ")),
+                    ],
+                )
+            })?;
+            match slot {
+                "highlight" => highlight_bg = color,
+                "highlight_text" => highlight_fg = color,
+                "pagination" => pagination_bg = color,
+                _ => return Err(i18n::message("config.unknown_slot", &[("slot", &slot)])),
+            }
+        }
+        Ok(Theme {
+            highlight: format!(
+                "\x1b[{};{}m",
+                highlight_bg.sgr(40, depth),
+                highlight_fg.sgr(30, depth)
+            ),
+            pagination: format!("\x1b[{};30m", pagination_bg.sgr(40, depth)),
+            ..Theme::default()
+        })
+    }
+}
+
+/// The RGB value of an entry of the 256-color palette: the 16 named colors, a
+/// 6x6x6 color cube and a ramp of 24 grays.
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+    match index {
+        0..16 => NAMED_RGB[index as usize],
+        16..232 => {
+            let n = index - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// The palette entry closest to `rgb`, from the color cube or the gray ramp.
+fn nearest_indexed(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255)
+        .min_by_key(|&i| distance(indexed_rgb(i), rgb))
+        .unwrap_or(16)
+}
+
+/// The named color (0-15) closest to `rgb`.
+fn nearest_named(rgb: (u8, u8, u8)) -> u8 {
+    (0..16)
+        .min_by_key(|&i| distance(NAMED_RGB[i as usize], rgb))
+        .unwrap_or(0)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).unsigned_abs().pow(2);
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// The `colors` number capability of the terminfo entry for `term`, looked up where
+/// ncurses does: `$TERMINFO`, `~/.terminfo`, `$TERMINFO_DIRS` and the system
+/// directories.
+fn terminfo_colors(term: &str) -> Option<u32> {
+    let first = term.chars().next()?;
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    dirs.extend(env::var_os("TERMINFO").map(PathBuf::from));
+    dirs.extend(env::var_os("HOME").map(|home| PathBuf::from(home).join(".terminfo")));
+    if let Some(list) = env::var_os("TERMINFO_DIRS") {
+        dirs.extend(env::split_paths(&list).filter(|dir| !dir.as_os_str().is_empty()));
+    }
+    dirs.extend(
+        ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"]
+            .into_iter()
+            .map(PathBuf::from),
+    );
+    // Entries live in a directory named after their first letter, or its hex code
+    // on case-insensitive file systems (macOS).
+    let subdirs = [first.to_string(), format!("{:x}", first as u32)];
+    dirs.iter()
+        .flat_map(|dir| subdirs.iter().map(move |sub| dir.join(sub).join(term)))
+        .find_map(|path| fs::read(path).ok())
+        .and_then(|entry| parse_colors(&entry))
+}
+
+/// Read the `colors` number (index 13) from a compiled terminfo entry, in either the
+/// legacy format with 16-bit numbers or the extended one with 32-bit numbers.
+fn parse_colors(entry: &[u8]) -> Option<u32> {
+    const COLORS: usize = 13;
+    let short = |i: usize| -> Option<usize> {
+        let bytes = entry.get(i * 2..i * 2 + 2)?;
+        Some(usize::from(u16::from_le_bytes([bytes[0], bytes[1]])))
+    };
+    let width = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let (names, booleans, numbers) = (short(1)?, short(2)?, short(3)?);
+    if COLORS >= numbers {
+        return None;
+    }
+    // Numbers start on an even offset after the header, names and booleans.
+    let mut start = 12 + names + booleans;
+    start += start % 2;
+    let at = start + COLORS * width;
+    let bytes = entry.get(at..at + width)?;
+    let value = if width == 2 {
+        i32::from(i16::from_le_bytes([bytes[0], bytes[1]]))
+    } else {
+        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    };
+    u32::try_from(value).ok()
+}
@@ -0,0 +1,91 @@
+//! `--tmux-popup`: choosing the branch in a tmux popup over the current pane, then
+//! acting on the choice in the process that asked, so a key binding can switch
+//! branches without disturbing the pane layout.
+
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{self, Command};
+
+use crate::cli::NAME;
+use crate::git::shell_quote;
+use crate::i18n;
+use crate::refname;
+use crate::trace;
+
+/// Whether git-recent runs inside a tmux session.
+pub fn inside_tmux() -> bool {
+    env::var_os("TMUX").is_some_and(|v| !v.is_empty())
+}
+
+/// Run git-recent again in a popup started in `dir`, with this run's arguments and
+/// `--print`, and return the branch chosen there. `None` means the popup was left
+/// without a choice.
+pub fn pick(dir: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    if !inside_tmux() {
+        return Err(i18n::text("tmux.not_running").into());
+    }
+    let temp = env::temp_dir();
+    let chosen_file = temp.join(format!("{NAME}-popup-{}.out", process::id()));
+    let errors_file = temp.join(format!("{NAME}-popup-{}.err", process::id()));
+    let _ = fs::remove_file(&chosen_file);
+
+    // The popup's shell starts from the tmux server's environment, so git's and
+    // git-recent's own variables are passed on explicitly.
+    let mut words = vec!["exec".to_string(), "env".to_string()];
+    for (name, value) in env::vars_os() {
+        let name = refname::decode(name.as_encoded_bytes());
+        if name.starts_with("GIT_") {
+            let value = refname::decode(value.as_encoded_bytes());
+            words.push(shell_quote(&format!("{name}={value}")));
+        }
+    }
+    let exe = env::current_exe()?;
+    words.push(shell_quote(&refname::decode(
+        exe.as_os_str().as_encoded_bytes(),
+    )));
+    words.push("--print".to_string());
+    for arg in env::args_os().skip(1) {
+        let arg = refname::decode(arg.as_encoded_bytes());
+        if arg != "--tmux-popup" {
+            words.push(shell_quote(&arg));
+        }
+    }
+    let path = |p: &Path| shell_quote(&refname::decode(p.as_os_str().as_encoded_bytes()));
+    words.push(format!(
+        "> {} 2> {}",
+        path(&chosen_file),
+        path(&errors_file)
+    ));
+
+    let status = trace::status(
+        Command::new("tmux")
+            .args(["display-popup", "-E", "-d"])
+            .arg(dir)
+            .args(["-w", "80%", "-h", "80%"])
+            .arg(refname::to_os(&words.join(" "))),
+    );
+    // The shell in the popup creates the file first thing, so without it the popup
+    // never opened; once it has, tmux's own status says nothing about the choice.
+    let chosen = match fs::read(&chosen_file) {
+        Ok(chosen) => chosen,
+        Err(_) => {
+            let status: &dyn fmt::Display = match &status {
+                Ok(status) => status,
+                Err(e) => e,
+            };
+            return Err(i18n::message("tmux.failed", &[("status", status)]).into());
+        }
+    };
+    let errors = fs::read(&errors_file).unwrap_or_default();
+    let _ = fs::remove_file(&chosen_file);
+    let _ = fs::remove_file(&errors_file);
+    // The popup closes as soon as git-recent exits there, so its errors are shown here.
+    io::stderr().write_all(&errors)?;
+    let chosen = refname::decode(&chosen);
+    let chosen = chosen.trim_end_matches(['\n', '\0']);
+    Ok((!chosen.is_empty()).then(|| chosen.to_string()))
+}
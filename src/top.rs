@@ -0,0 +1,94 @@
+//! `git-recent top`: the few most recently committed branches, quickly enough for a
+//! shell prompt. It skips the config files and repository checks the picker needs and
+//! asks git once. With the `native-refs` feature the answer is also kept in a state
+//! file and reused while no branch ref has changed, so that most prompts run no
+//! process at all.
+
+use std::error::Error;
+#[cfg(feature = "native-refs")]
+use std::fs;
+#[cfg(feature = "native-refs")]
+use std::process;
+
+use crate::git;
+#[cfg(feature = "native-refs")]
+use crate::history;
+use crate::refname;
+use crate::trace;
+
+/// How many branches `top` prints without `--limit`.
+pub const DEFAULT_COUNT: usize = 3;
+
+pub struct RecentBranch {
+    pub name: String,
+    pub current: bool,
+    /// Committer date of the branch's commit, in Unix seconds.
+    pub time: i64,
+}
+
+/// The `count` most recently committed local branches, newest first; all of them for
+/// `None`.
+pub fn recent(count: Option<usize>) -> Result<Vec<RecentBranch>, Box<dyn Error>> {
+    #[cfg(feature = "native-refs")]
+    let cache = crate::refs::snapshot().and_then(|(git_dir, snapshot)| {
+        let path = history::state_file_of(&git_dir, "top")?;
+        let key = format!("{:016x} {count:?}\n", history::fnv1a(&snapshot));
+        Some((path, key))
+    });
+    #[cfg(feature = "native-refs")]
+    if let Some((path, key)) = &cache
+        && let Ok(cached) = fs::read(path)
+        && let Some(listing) = cached.strip_prefix(key.as_bytes())
+    {
+        trace::note(format_args!("top: cached in {}", path.display()));
+        return Ok(parse(&refname::decode(listing)));
+    }
+
+    let mut command = git::command();
+    command.args([
+        "for-each-ref",
+        "--sort=-committerdate",
+        "--format=%(HEAD)%(committerdate:unix) %(refname)",
+    ]);
+    if let Some(count) = count {
+        command.arg(format!("--count={count}"));
+    }
+    let output = trace::output(command.arg("refs/heads/"))?;
+    if !output.status.success() {
+        // Most likely not a repository, which the check reports as such.
+        git::check_repository()?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git for-each-ref failed: {}", stderr.trim()).into());
+    }
+
+    // Written beside the file and renamed over it, as prompts of several shells may
+    // refresh it at once. A cache that cannot be written is just not used.
+    #[cfg(feature = "native-refs")]
+    if let Some((path, key)) = &cache
+        && let Some(dir) = path.parent()
+        && fs::create_dir_all(dir).is_ok()
+    {
+        let temp = path.with_extension(format!("top.{}", process::id()));
+        let contents = [key.as_bytes(), &output.stdout].concat();
+        if fs::write(&temp, contents).is_err() || fs::rename(&temp, path).is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+    }
+    Ok(parse(&refname::decode(&output.stdout)))
+}
+
+/// `for-each-ref` lines of the form `<HEAD mark><unix time> refs/heads/<name>`.
+fn parse(listing: &str) -> Vec<RecentBranch> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let current = line.starts_with('*');
+            let (time, name) = line.get(1..)?.split_once(' ')?;
+            Some(RecentBranch {
+                name: name.strip_prefix("refs/heads/")?.to_string(),
+                current,
+                time: time.parse().ok()?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,357 @@
+//! `--verbose`: every command git-recent runs, with how long it took and how it
+//! ended, plus the odd note on why a branch was chosen, written to stderr or to the
+//! file given with `--log-file`. And `--timing`: where startup time went, summed up
+//! once git-recent exits.
+
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::git::{self, shell_quote};
+use crate::refname;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Where the trace goes; stderr when `None`.
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+/// What `--timing` has collected so far.
+struct Timing {
+    started: Instant,
+    /// When startup ended (the picker's first frame was drawn); nothing later counts.
+    ready: Option<Instant>,
+    /// Commands by program and subcommand (`git rev-parse`), with their count and total
+    /// time, in the order they first ran.
+    commands: Vec<(String, u32, Duration)>,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+static TIMING: Mutex<Option<Timing>> = Mutex::new(None);
+
+/// Start collecting startup timings, counting from `started`.
+pub fn enable_timing(started: Instant) {
+    *TIMING.lock().unwrap_or_else(|e| e.into_inner()) = Some(Timing {
+        started,
+        ready: None,
+        commands: Vec::new(),
+        phases: Vec::new(),
+    });
+}
+
+/// Record a startup phase named `name` that began at `started` and ends now.
+pub fn phase(name: &'static str, started: Instant) {
+    if let Some(timing) = &mut *TIMING.lock().unwrap_or_else(|e| e.into_inner())
+        && timing.ready.is_none()
+    {
+        timing.phases.push((name, started.elapsed()));
+    }
+}
+
+/// Mark the end of startup: the picker is on screen and waits for keys.
+pub fn startup_done() {
+    if let Some(timing) = &mut *TIMING.lock().unwrap_or_else(|e| e.into_inner()) {
+        timing.ready.get_or_insert_with(Instant::now);
+    }
+}
+
+/// The `--timing` breakdown, if it was asked for: commands, then phases, then what
+/// is left, up to the end of startup (or now, when there was no picker).
+pub fn timing_report() -> Option<String> {
+    let timing = TIMING.lock().unwrap_or_else(|e| e.into_inner());
+    let timing = timing.as_ref()?;
+    let total = timing.ready.unwrap_or_else(Instant::now) - timing.started;
+    let ms = |d: Duration| format!("{:.1}ms", d.as_secs_f64() * 1000.0);
+    let mut report = String::from("startup timing:\n");
+    let mut accounted = Duration::ZERO;
+    for (command, count, elapsed) in &timing.commands {
+        report.push_str(&format!("  {command:<26}{count:>4} {:>9}\n", ms(*elapsed)));
+        accounted += *elapsed;
+    }
+    for (name, elapsed) in &timing.phases {
+        report.push_str(&format!("  {name:<31}{:>9}\n", ms(*elapsed)));
+        accounted += *elapsed;
+    }
+    // Background commands may overlap the rest, so this can come out at zero.
+    let other = total.saturating_sub(accounted);
+    report.push_str(&format!("  {:<31}{:>9}\n", "other", ms(other)));
+    report.push_str(&format!("  {:<31}{:>9}\n", "total", ms(total)));
+    Some(report)
+}
+
+fn record_command(command: &Command, elapsed: Duration) {
+    let mut timing = TIMING.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(timing) = timing.as_mut().filter(|t| t.ready.is_none()) else {
+        return;
+    };
+    let mut name = command.get_program().to_string_lossy().into_owned();
+    // The subcommand, if any; `sh -c` hooks and commands are all just `sh`.
+    let mut args = command.get_args().filter(|a| !git::is_repository_option(a));
+    if let Some(sub) = args.next().map(|a| a.to_string_lossy())
+        && !sub.starts_with('-')
+    {
+        name = format!("{name} {sub}");
+    }
+    match timing.commands.iter_mut().find(|(n, _, _)| *n == name) {
+        Some((_, count, total)) => {
+            *count += 1;
+            *total += elapsed;
+        }
+        None => timing.commands.push((name, 1, elapsed)),
+    }
+}
+
+/// Start tracing, to the end of `log_file` if given and to stderr otherwise.
+pub fn enable(log_file: Option<&Path>) -> io::Result<()> {
+    if let Some(path) = log_file {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    }
+    ENABLED.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Write one line to the trace.
+pub fn note(message: impl Display) {
+    if !is_enabled() {
+        return;
+    }
+    let line = format!("git-recent: {message}\n");
+    match &mut *LOG_FILE.lock().unwrap_or_else(|e| e.into_inner()) {
+        // Tracing is a debugging aid; a failed write is not worth failing over.
+        Some(file) => {
+            let _ = file.write_all(line.as_bytes());
+        }
+        None => {
+            let _ = io::stderr().write_all(line.as_bytes());
+        }
+    }
+}
+
+/// `command.output()`, traced.
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    let started = Instant::now();
+    let output = command.output();
+    finished(command, started, output.as_ref().map(|o| &o.status));
+    output
+}
+
+/// `command.output()`, traced, with each line of stdout (without its newline) handed
+/// to `line` as it arrives instead of collected; the returned stdout is empty.
+pub fn output_lines(command: &mut Command, mut line: impl FnMut(&[u8])) -> io::Result<Output> {
+    let started = Instant::now();
+    let output = (|| {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        // Read stderr alongside, so a chatty command cannot fill the pipe and stall.
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr = thread::spawn(move || {
+            let mut text = Vec::new();
+            let _ = stderr.read_to_end(&mut text);
+            text
+        });
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut buffer = Vec::new();
+        while stdout.read_until(b'\n', &mut buffer)? > 0 {
+            if buffer.last() == Some(&b'\n') {
+                buffer.pop();
+            }
+            line(&buffer);
+            buffer.clear();
+        }
+        Ok(Output {
+            status: child.wait()?,
+            stdout: Vec::new(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    })();
+    finished(command, started, output.as_ref().map(|o| &o.status));
+    output
+}
+
+/// `command.output()`, traced, keeping only the first `max_lines` lines of stdout.
+/// Once it has them the command is killed, so a huge output costs no more than a
+/// short one; the status then says so.
+pub fn output_head(command: &mut Command, max_lines: usize) -> io::Result<Output> {
+    let started = Instant::now();
+    let output = (|| {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let stderr = thread::spawn(move || {
+            let mut text = Vec::new();
+            let _ = stderr.read_to_end(&mut text);
+            text
+        });
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let mut text = Vec::new();
+        let mut lines = 0;
+        while lines < max_lines && stdout.read_until(b'\n', &mut text)? > 0 {
+            lines += 1;
+        }
+        if lines == max_lines {
+            // It may have finished on its own already.
+            let _ = child.kill();
+        }
+        drop(stdout);
+        Ok(Output {
+            status: child.wait()?,
+            stdout: text,
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    })();
+    finished(command, started, output.as_ref().map(|o| &o.status));
+    output
+}
+
+/// `command.output()`, traced, with the command killed if it has not finished within
+/// `timeout`; the status then says so. Its output is read on threads of their own,
+/// which a child it left behind holding the pipes cannot keep the caller waiting on.
+pub fn output_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let started = Instant::now();
+    let output = (|| {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let stderr = thread::spawn(move || {
+                let mut text = Vec::new();
+                let _ = stderr.read_to_end(&mut text);
+                text
+            });
+            let mut text = Vec::new();
+            let _ = stdout.read_to_end(&mut text);
+            let _ = sender.send((text, stderr.join().unwrap_or_default()));
+        });
+        let deadline = started + timeout;
+        let (stdout, stderr) = receiver
+            .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+        // Closing its output is not the same as exiting; allow what is left of the time.
+        loop {
+            if let Some(status) = child.try_wait()? {
+                return Ok(Output {
+                    status,
+                    stdout,
+                    stderr,
+                });
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                return Ok(Output {
+                    status: child.wait()?,
+                    stdout,
+                    stderr,
+                });
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    })();
+    finished(command, started, output.as_ref().map(|o| &o.status));
+    output
+}
+
+/// `command.output()`, traced, with `input` written to its stdin and stderr left on
+/// ours, for interactive programs that take a list and print the choice.
+pub fn output_with_input(command: &mut Command, input: &[u8]) -> io::Result<Output> {
+    let started = Instant::now();
+    let output = (|| {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        thread::scope(|scope| {
+            // Written alongside, so a program printing before it read everything cannot
+            // stall; it may also quit early, which is not an error here.
+            scope.spawn(move || {
+                let _ = stdin.write_all(input);
+            });
+            child.wait_with_output()
+        })
+    })();
+    finished(command, started, output.as_ref().map(|o| &o.status));
+    output
+}
+
+/// `command.status()`, traced, with `input` written to its stdin and its output
+/// discarded, for tools that only take input. The command's own children (xclip
+/// stays behind to serve the selection) cannot hold a pipe open and stall the wait.
+pub fn status_with_input(command: &mut Command, input: &[u8]) -> io::Result<ExitStatus> {
+    let started = Instant::now();
+    let status = (|| {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Closing stdin afterwards tells the tool the input is complete.
+        let written = stdin.write_all(input);
+        drop(stdin);
+        let status = child.wait()?;
+        written.map(|()| status)
+    })();
+    finished(command, started, status.as_ref());
+    status
+}
+
+/// `command.status()`, traced.
+pub fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    let started = Instant::now();
+    let status = command.status();
+    finished(command, started, status.as_ref());
+    status
+}
+
+fn finished(command: &Command, started: Instant, status: Result<&ExitStatus, &io::Error>) {
+    let elapsed = started.elapsed();
+    record_command(command, elapsed);
+    if !is_enabled() {
+        return;
+    }
+    let elapsed = elapsed.as_secs_f64() * 1000.0;
+    let outcome = match status {
+        Ok(status) => match status.code() {
+            Some(code) => format!("exit {code}"),
+            None => status.to_string(),
+        },
+        Err(e) => format!("failed to start: {e}"),
+    };
+    note(format_args!(
+        "{} ({elapsed:.1}ms, {outcome})",
+        command_line(command)
+    ));
+}
+
+/// `command` as a shell command line, with bytes that are not UTF-8 escaped. The
+/// repository options every git command gets are left out; they are noted once.
+fn command_line(command: &Command) -> String {
+    let args = command.get_args().filter(|a| !git::is_repository_option(a));
+    let words = std::iter::once(command.get_program()).chain(args);
+    let words: Vec<String> = words
+        .map(|word| {
+            let word = refname::decode(word.as_encoded_bytes());
+            shell_quote(&refname::display(&word))
+        })
+        .collect();
+    words.join(" ")
+}
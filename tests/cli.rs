@@ -0,0 +1,1219 @@
+//! End-to-end tests: throwaway repositories in temporary directories, driven through
+//! the `git-recent` binary.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A repository in a fresh temporary directory, removed again on drop. Next to it are
+/// a home and a state directory, so neither the user's git config nor their
+/// git-recent config or history leaks into a test.
+struct TestRepo {
+    root: PathBuf,
+}
+
+impl TestRepo {
+    /// A repository with one commit on `main`.
+    fn new() -> TestRepo {
+        let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+        let root = env::temp_dir().join(format!("git-recent-test-{}-{id}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("repo")).unwrap();
+        fs::create_dir_all(root.join("home")).unwrap();
+        let repo = TestRepo { root };
+        repo.git(&["init", "--quiet", "--initial-branch=main"]);
+        repo.commit("initial", 1_000_000_000);
+        repo
+    }
+
+    fn path(&self) -> PathBuf {
+        self.root.join("repo")
+    }
+
+    /// Environment for both git and git-recent: no user or system config, no
+    /// `GIT_RECENT_*` overrides, English messages and a fixed identity.
+    fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> Command {
+        let mut command = Command::new(program);
+        command
+            .current_dir(self.path())
+            .env("HOME", self.root.join("home"))
+            .env("XDG_CONFIG_HOME", self.root.join("home/.config"))
+            .env("XDG_STATE_HOME", self.root.join("state"))
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("LC_ALL", "C")
+            .env("GIT_AUTHOR_NAME", "Test")
+            .env("GIT_AUTHOR_EMAIL", "test@example.com")
+            .env("GIT_COMMITTER_NAME", "Test")
+            .env("GIT_COMMITTER_EMAIL", "test@example.com")
+            .env_remove("GIT_DIR")
+            .env_remove("GIT_WORK_TREE");
+        for (var, _) in env::vars_os() {
+            if var.to_string_lossy().starts_with("GIT_RECENT_") {
+                command.env_remove(var);
+            }
+        }
+        command
+    }
+
+    /// Run git in the repository, panicking if it fails; returns its trimmed stdout.
+    fn git(&self, args: &[&str]) -> String {
+        let output = self.command("git").args(args).output().unwrap();
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    /// Commit a change to a file named after `message`, dated `time` (Unix seconds).
+    fn commit(&self, message: &str, time: i64) {
+        fs::write(self.path().join(message), message).unwrap();
+        self.git(&["add", message]);
+        let date = format!("@{time} +0000");
+        let output = self
+            .command("git")
+            .args(["commit", "--quiet", "-m", message])
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git commit failed");
+    }
+
+    /// Create `branch` from `main` with one commit dated `time`, then go back to `main`.
+    fn branch(&self, branch: &str, time: i64) {
+        self.git(&["checkout", "--quiet", "-b", branch, "main"]);
+        self.commit(branch, time);
+        self.git(&["checkout", "--quiet", "main"]);
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.command(env!("CARGO_BIN_EXE_git-recent"))
+            .args(args)
+            .output()
+            .unwrap()
+    }
+
+    /// Run git-recent, expecting success, and return its stdout lines.
+    fn lines(&self, args: &[&str]) -> Vec<String> {
+        let output = self.run(args);
+        assert!(
+            output.status.success(),
+            "git-recent {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect()
+    }
+
+    fn current_branch(&self) -> String {
+        self.git(&["branch", "--show-current"])
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// A repository with `old`, `middle` and `new` branches committed in that order.
+fn three_branches() -> TestRepo {
+    let repo = TestRepo::new();
+    repo.branch("old", 1_100_000_000);
+    repo.branch("middle", 1_200_000_000);
+    repo.branch("new", 1_300_000_000);
+    repo
+}
+
+#[test]
+fn list_orders_by_most_recent_commit() {
+    let repo = three_branches();
+    assert_eq!(repo.lines(&["--list"]), ["new", "middle", "old", "main"]);
+}
+
+#[test]
+fn piped_output_is_a_plain_list() {
+    let repo = three_branches();
+    assert_eq!(repo.lines(&[]), repo.lines(&["--list"]));
+}
+
+#[test]
+fn list_respects_limit_and_sort() {
+    let repo = three_branches();
+    assert_eq!(repo.lines(&["--list", "--limit", "2"]), ["new", "middle"]);
+    assert_eq!(
+        repo.lines(&["--list", "--sort", "name"]),
+        ["main", "middle", "new", "old"]
+    );
+}
+
+#[test]
+fn query_and_checkout_reach_past_the_limit() {
+    let repo = three_branches();
+    assert_eq!(
+        repo.lines(&["--list", "--limit", "1", "--query", "ld"]),
+        ["old"]
+    );
+    assert!(
+        repo.run(&["--limit", "1", "--checkout", "old"])
+            .status
+            .success()
+    );
+    assert_eq!(repo.git(&["branch", "--show-current"]), "old");
+}
+
+#[test]
+fn query_ignores_case_and_unicode_composition() {
+    let repo = TestRepo::new();
+    // "Café" with a precomposed é, and "resume" with e followed by a combining accent.
+    repo.branch("Caf\u{e9}", 1_100_000_000);
+    repo.branch("re\u{301}sume\u{301}", 1_200_000_000);
+    assert_eq!(
+        repo.lines(&["--list", "--query", "cafe\u{301}"]),
+        ["Caf\u{e9}"]
+    );
+    assert_eq!(
+        repo.lines(&["--list", "--query", "R\u{e9}SUM"]),
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        repo.lines(&["--list", "--query", "r\u{e9}sum"]),
+        ["re\u{301}sume\u{301}"]
+    );
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "filter_case = \"match\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        repo.lines(&["--list", "--query", "caf"]),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn list_skips_excluded_branches() {
+    let repo = three_branches();
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "exclude = [\"mid*\"]\n",
+    )
+    .unwrap();
+    assert_eq!(repo.lines(&["--list"]), ["new", "old", "main"]);
+}
+
+#[test]
+fn stale_lists_branches_without_recent_commits() {
+    let repo = three_branches();
+    repo.branch("fresh", unix_now());
+    assert_eq!(
+        repo.lines(&["--list", "--stale", "1"]),
+        ["new", "middle", "old", "main"]
+    );
+    // More days than seconds fit in 64 bits: nothing is that old.
+    assert!(
+        repo.lines(&["--list", "--stale", "999999999999999999"])
+            .is_empty()
+    );
+}
+
+/// The current time in Unix seconds.
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+#[test]
+fn repository_config_only_sets_inert_settings_unless_trusted() {
+    let repo = three_branches();
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "sort = \"name\"\nswitch_args = [\"--force\"]\n\n[profile.ci]\nexec = \"touch pwned\"\n",
+    )
+    .unwrap();
+    assert_eq!(repo.lines(&["--list"]), ["main", "middle", "new", "old"]);
+    let output = repo.run(&["config"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stdout.contains("--force"), "{stdout}");
+    assert!(stderr.contains(":2: ignoring 'switch_args'"), "{stderr}");
+    assert!(
+        stderr.contains(":5: ignoring 'profile.ci.exec'"),
+        "{stderr}"
+    );
+
+    repo.git(&["config", "recent.trustRepoConfig", "true"]);
+    let output = repo.run(&["config"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("switch_args = [\"--force\"]"));
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn profile_is_chosen_by_flag_git_config_user_file_or_repository_file() {
+    let repo = three_branches();
+    let user_config = repo.root.join("home/.config/git-recent/config.toml");
+    fs::create_dir_all(user_config.parent().unwrap()).unwrap();
+    let profiles = "[profile.short]\nlimit = 1\n\n[profile.named]\nsort = \"name\"\n";
+    fs::write(&user_config, profiles).unwrap();
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "profile = \"short\"\n",
+    )
+    .unwrap();
+    assert_eq!(repo.lines(&["--list"]), ["new"]);
+
+    fs::write(&user_config, format!("profile = \"named\"\n\n{profiles}")).unwrap();
+    assert_eq!(repo.lines(&["--list"]), ["main", "middle", "new", "old"]);
+
+    repo.git(&["config", "recent.profile", "short"]);
+    assert_eq!(repo.lines(&["--list"]), ["new"]);
+    assert_eq!(
+        repo.lines(&["--list", "--profile", "named"]),
+        ["main", "middle", "new", "old"]
+    );
+}
+
+#[test]
+fn list_with_null_separates_with_nul() {
+    let repo = three_branches();
+    let output = repo.run(&["--list", "--null", "--limit", "2"]);
+    assert_eq!(output.stdout, b"new\0middle\0");
+}
+
+#[test]
+fn top_prints_the_most_recent_branches() {
+    let repo = three_branches();
+    assert_eq!(repo.lines(&["top", "--plain"]), ["new middle old"]);
+    assert_eq!(repo.lines(&["top", "--plain", "-n", "1"]), ["new"]);
+    let lines = repo.lines(&["top", "--limit", "all"]);
+    let names: Vec<&str> = lines.iter().map(|line| &line[..8]).collect();
+    assert_eq!(names, ["  new   ", "  middle", "  old   ", "* main  "]);
+    assert!(lines[0].ends_with(" years ago"), "{lines:?}");
+    // Twice, so that with `native-refs` the second answer comes from the cache, and
+    // then after a commit, which the cache must notice.
+    assert_eq!(repo.lines(&["top", "--plain"]), ["new middle old"]);
+    repo.commit("latest", 1_400_000_000);
+    assert_eq!(repo.lines(&["top", "--plain"]), ["main new middle"]);
+}
+
+#[test]
+fn export_writes_every_branch_as_csv_or_json() {
+    let repo = three_branches();
+    repo.git(&["checkout", "--quiet", "old"]);
+    repo.git(&["checkout", "--quiet", "main"]);
+    repo.git(&["checkout", "--quiet", "old"]);
+    let csv = repo.lines(&["export", "--format", "csv"]);
+    assert_eq!(
+        csv[0],
+        "name,sha,committer_date,subject,upstream,ahead,behind,current,checkouts"
+    );
+    let rows: Vec<Vec<&str>> = csv[1..].iter().map(|l| l.split(',').collect()).collect();
+    let column = |i: usize| rows.iter().map(|r| r[i]).collect::<Vec<_>>();
+    assert_eq!(column(0), ["new", "middle", "old", "main"]);
+    assert_eq!(column(2)[0], "2011-03-13T07:06:40+00:00");
+    assert_eq!(column(7), ["false", "false", "true", "false"]);
+    // `TestRepo::branch` checks each new branch out and goes back to main.
+    assert_eq!(column(8), ["1", "1", "3", "4"]);
+
+    let json = repo.lines(&["export", "-n", "1"]);
+    assert_eq!(json.len(), 3);
+    assert!(json[1].starts_with("  {\"name\": \"new\", \"sha\": \""));
+    assert!(json[1].ends_with("\"subject\": \"new\", \"upstream\": null, \"ahead\": null, \"behind\": null, \"current\": false, \"checkouts\": 1}"));
+
+    let output = repo.run(&["export", "--format", "{name}"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn checkout_by_name_switches_branch() {
+    let repo = three_branches();
+    let output = repo.run(&["--checkout", "middle", "--quiet"]);
+    assert!(output.status.success());
+    assert_eq!(repo.current_branch(), "middle");
+}
+
+#[test]
+fn checkout_by_number_uses_list_position() {
+    let repo = three_branches();
+    assert!(repo.run(&["--checkout", "3", "--quiet"]).status.success());
+    assert_eq!(repo.current_branch(), "old");
+}
+
+#[test]
+fn checkout_hooks_run_from_user_config_but_not_the_repository_file() {
+    let repo = three_branches();
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "[hooks]\npre_checkout = \"touch repo-hook\"\n",
+    )
+    .unwrap();
+    assert!(repo.run(&["--checkout", "old", "--quiet"]).status.success());
+    assert_eq!(repo.current_branch(), "old");
+    assert!(!repo.path().join("repo-hook").exists());
+
+    let config = repo.root.join("home/.config/git-recent");
+    fs::create_dir_all(&config).unwrap();
+    fs::write(
+        config.join("config.toml"),
+        "[hooks]\npost_checkout = \"touch user-hook\"\n",
+    )
+    .unwrap();
+    assert!(repo.run(&["--checkout", "new", "--quiet"]).status.success());
+    assert!(repo.path().join("user-hook").exists());
+    assert!(!repo.path().join("repo-hook").exists());
+}
+
+#[test]
+fn checkout_records_history_for_checkout_order() {
+    let repo = three_branches();
+    assert!(repo.run(&["--checkout", "old", "--quiet"]).status.success());
+    assert!(
+        repo.run(&["--checkout", "main", "--quiet"])
+            .status
+            .success()
+    );
+    assert!(repo.root.join("state/git-recent").is_dir());
+    let order = repo.lines(&["--list", "--sort", "checkout"]);
+    assert_eq!(order[..2], ["main", "old"]);
+}
+
+#[test]
+fn tags_are_listed_by_tag_date_and_checked_out_detached() {
+    let repo = three_branches();
+    repo.git(&["tag", "light", "old"]);
+    // An annotated tag on the oldest commit, tagged after everything else.
+    let output = repo
+        .command("git")
+        .args(["tag", "--annotate", "-m", "release", "v1", "main"])
+        .env("GIT_COMMITTER_DATE", "@1400000000 +0000")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git tag failed");
+    assert_eq!(repo.lines(&["tags", "--list"]), ["v1", "light"]);
+
+    assert!(
+        repo.run(&["tags", "--checkout", "light", "--quiet"])
+            .status
+            .success()
+    );
+    assert_eq!(repo.current_branch(), "");
+    assert_eq!(
+        repo.git(&["rev-parse", "HEAD"]),
+        repo.git(&["rev-parse", "old"])
+    );
+
+    let output = repo.run(&["tags", "--delete"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn installed_hook_records_checkouts_made_with_git() {
+    let repo = three_branches();
+    assert!(repo.run(&["install-hook"]).status.success());
+    let hook = repo.path().join(".git/hooks/post-checkout");
+    assert!(hook.is_file());
+
+    assert!(repo.run(&["--checkout", "new", "--quiet"]).status.success());
+    repo.git(&["checkout", "--quiet", "old"]);
+    // Checking out files is not a branch checkout.
+    repo.git(&["checkout", "--quiet", "new", "--", "new"]);
+    let order = repo.lines(&["--list", "--sort", "checkout"]);
+    assert_eq!(order[..2], ["old", "new"]);
+    // git-recent's own checkout is recorded once, not again by the hook.
+    let state = repo.root.join("state/git-recent");
+    let history = fs::read_dir(&state)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| path.extension().is_some_and(|e| e == "history"))
+        .unwrap();
+    let entries = fs::read_to_string(history).unwrap();
+    let branches: Vec<&str> = entries
+        .lines()
+        .map(|line| line.split_once('\t').unwrap().1)
+        .collect();
+    assert_eq!(branches, ["new", "old"]);
+
+    assert!(repo.run(&["install-hook", "--uninstall"]).status.success());
+    assert!(!hook.exists());
+
+    // Someone else's hook is left alone.
+    fs::write(&hook, "#!/bin/sh\necho mine\n").unwrap();
+    let output = repo.run(&["install-hook"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("record-checkout"));
+    assert_eq!(fs::read_to_string(&hook).unwrap(), "#!/bin/sh\necho mine\n");
+}
+
+#[test]
+fn last_returns_to_previous_branch() {
+    let repo = three_branches();
+    assert!(repo.run(&["--checkout", "new", "--quiet"]).status.success());
+    assert!(repo.run(&["--last", "--quiet"]).status.success());
+    assert_eq!(repo.current_branch(), "main");
+}
+
+#[test]
+fn unknown_branch_fails_without_switching() {
+    let repo = three_branches();
+    let output = repo.run(&["--checkout", "missing"]);
+    assert_eq!(output.status.code(), Some(6));
+    assert_eq!(repo.current_branch(), "main");
+}
+
+#[test]
+fn messages_follow_the_locale() {
+    let repo = three_branches();
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .args(["--checkout", "nwe"])
+        .env_remove("LC_ALL")
+        .env("LANG", "de_DE.UTF-8")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Fehler: kein letzter Branch namens 'nwe'; meinten Sie 'new'?\n"
+    );
+    let output = repo.run(&["--checkout", "nwe"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Error: no recent branch named 'nwe'; did you mean 'new'?\n"
+    );
+    // Errors about config files are translated too.
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .args(["--list"])
+        .env_remove("LC_ALL")
+        .env("LANG", "de_DE.UTF-8")
+        .env("GIT_RECENT_LIMIT", "many")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "Fehler: $GIT_RECENT_LIMIT muss eine ganze Zahl sein, nicht 'many'\n"
+    );
+}
+
+#[test]
+fn checkout_over_local_changes_is_a_conflict() {
+    let repo = three_branches();
+    // `new` has a file that main does not track; an untracked copy would be overwritten.
+    fs::write(repo.path().join("new"), "local").unwrap();
+    let output = repo.run(&["--checkout", "new"]);
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(repo.current_branch(), "main");
+}
+
+#[test]
+fn dry_run_prints_checkout_without_running_it() {
+    let repo = three_branches();
+    let lines = repo.lines(&["--checkout", "old", "--dry-run"]);
+    assert_eq!(lines, ["git checkout old"]);
+    assert_eq!(repo.current_branch(), "main");
+}
+
+#[test]
+fn outside_a_repository_fails() {
+    let repo = TestRepo::new();
+    let outside = repo.root.join("home");
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .current_dir(&outside)
+        .env("GIT_CEILING_DIRECTORIES", &repo.root)
+        .arg("--list")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn unknown_option_is_a_usage_error() {
+    let repo = TestRepo::new();
+    assert_eq!(repo.run(&["--no-such-option"]).status.code(), Some(2));
+}
+
+#[test]
+fn height_must_be_positive() {
+    let repo = three_branches();
+    assert_eq!(repo.run(&["--height", "0"]).status.code(), Some(2));
+    fs::write(repo.path().join(".git-recent.toml"), "height = 0\n").unwrap();
+    assert_eq!(repo.run(&["--list"]).status.code(), Some(3));
+    assert!(repo.run(&["--height", "3", "--list"]).status.success());
+}
+
+#[test]
+fn unknown_git_config_settings_are_skipped_but_bad_values_are_errors() {
+    let repo = three_branches();
+    repo.git(&["config", "recent.someFutureSetting", "on"]);
+    let output = repo.run(&["--list", "--limit", "1"]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"new\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("ignoring unknown setting 'recent.somefuturesetting'"),
+        "{stderr}"
+    );
+
+    repo.git(&["config", "recent.limit", "many"]);
+    assert_eq!(repo.run(&["--list"]).status.code(), Some(3));
+}
+
+#[test]
+fn completions_need_a_known_shell() {
+    let repo = TestRepo::new();
+    assert_eq!(repo.run(&["completions"]).status.code(), Some(2));
+    assert_eq!(repo.run(&["completions", "tcsh"]).status.code(), Some(2));
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let output = repo.run(&["completions", shell]);
+        assert!(output.status.success(), "completions {shell} failed");
+        let script = String::from_utf8(output.stdout).unwrap();
+        // Branch names are completed by listing them.
+        assert!(
+            script.contains("git-recent --list --limit 0"),
+            "{shell} script does not complete branches"
+        );
+    }
+}
+
+#[test]
+fn init_function_follows_worktree_choices() {
+    let repo = three_branches();
+    assert_eq!(repo.run(&["init"]).status.code(), Some(2));
+    assert_eq!(repo.run(&["init", "powershell"]).status.code(), Some(2));
+    for shell in ["bash", "zsh", "fish"] {
+        let output = repo.run(&["init", shell]);
+        assert!(output.status.success(), "init {shell} failed");
+        let script = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            script.contains("--cd-file"),
+            "{shell} function ignores worktrees"
+        );
+    }
+
+    // The worktree's path goes into the file as a cd command instead of to stdout.
+    let cd_file = repo.root.join("cd");
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .env("GIT_RECENT_ACTION", "worktree")
+        .args(["--query", "mid", "--select-1", "--cd-file"])
+        .arg(&cd_file)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.stdout.is_empty());
+    let worktree = repo.root.join("repo-middle");
+    assert!(worktree.is_dir());
+    let directive = fs::read_to_string(&cd_file).unwrap();
+    assert_eq!(directive.trim_end(), format!("cd {}", worktree.display()));
+}
+
+#[test]
+fn exec_quotes_the_branch_for_where_the_placeholder_stands() {
+    let repo = three_branches();
+    repo.branch("it's-$HOME", 1_400_000_000);
+    let output = repo.run(&[
+        "--query",
+        "it's",
+        "--select-1",
+        "--exec",
+        r#"printf '%s|%s|%s\n' {} "{}" 'x{}x'"#,
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "it's-$HOME|it's-$HOME|xit's-$HOMEx\n"
+    );
+
+    // Annotations run as soon as the picker opens, so a repository cannot set them.
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "annotate = \"touch pwned\"\n",
+    )
+    .unwrap();
+    let output = repo.run(&["config"]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("pwned"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("ignoring 'annotate'"));
+}
+
+#[test]
+fn repository_file_cannot_make_enter_run_a_command_unless_trusted() {
+    let repo = three_branches();
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "action = \"exec\"\nexec = \"touch pwned\"\n",
+    )
+    .unwrap();
+    let output = repo.run(&["--query", "mid", "--select-1"]);
+    assert!(output.status.success());
+    assert!(!repo.path().join("pwned").exists());
+
+    repo.git(&["config", "recent.trustRepoConfig", "true"]);
+    let output = repo.run(&["--query", "mid", "--select-1"]);
+    assert!(output.status.success());
+    assert!(repo.path().join("pwned").exists());
+}
+
+#[test]
+fn init_widget_inserts_the_chosen_branch() {
+    let repo = three_branches();
+    use std::os::unix::fs::PermissionsExt;
+
+    // The widget opens the picker; a stand-in on the PATH chooses for it.
+    let bin = repo.root.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let stand_in = bin.join("git-recent");
+    fs::write(
+        &stand_in,
+        format!(
+            "#!/bin/sh\nexec '{}' --query mid --select-1 \"$@\"\n",
+            env!("CARGO_BIN_EXE_git-recent")
+        ),
+    )
+    .unwrap();
+    fs::set_permissions(&stand_in, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!("{}:{}", bin.display(), env::var("PATH").unwrap());
+    let output = repo
+        .command("bash")
+        .env("PATH", path)
+        .args([
+            "-c",
+            r#"eval "$(git-recent init bash)"
+            READLINE_LINE="git log  --oneline" READLINE_POINT=8
+            __git_recent_widget
+            printf '%s|%s' "$READLINE_LINE" "$READLINE_POINT""#,
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "git log middle --oneline|14",
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn install_alias_adds_and_removes_git_recent() {
+    let repo = three_branches();
+    let global = repo.path().join("global.gitconfig");
+    let run = |args: &[&str]| {
+        repo.command(env!("CARGO_BIN_EXE_git-recent"))
+            .env("GIT_CONFIG_GLOBAL", &global)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let git_config = |args: &[&str]| {
+        let output = repo
+            .command("git")
+            .env("GIT_CONFIG_GLOBAL", &global)
+            .args(["config", "--global"])
+            .args(args)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+    // A dry run only prints the commands.
+    let output = run(&["install-alias", "--dry-run"]);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).starts_with("git config --global alias.recent")
+    );
+    assert!(!global.exists());
+
+    assert!(run(&["install-alias", "--shortcut"]).status.success());
+    assert!(git_config(&["alias.recent"]).starts_with('!'));
+    assert!(git_config(&["alias.rb"]).starts_with('!'));
+
+    // Someone else's alias is never overwritten.
+    git_config(&["alias.co", "checkout"]);
+    assert_eq!(
+        run(&["install-alias", "--shortcut=co"]).status.code(),
+        Some(1)
+    );
+    assert_eq!(git_config(&["alias.co"]).trim(), "checkout");
+
+    assert!(run(&["install-alias", "--uninstall"]).status.success());
+    assert_eq!(git_config(&["alias.recent"]), "");
+    assert_eq!(git_config(&["alias.rb"]), "");
+    assert_eq!(git_config(&["alias.co"]).trim(), "checkout");
+}
+
+#[cfg(unix)]
+#[test]
+fn fzf_picker_chooses_from_every_branch() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = three_branches();
+    // A stand-in for fzf that notes its arguments and input and picks the second line.
+    let bin = repo.root.join("bin");
+    fs::create_dir(&bin).unwrap();
+    let fzf = bin.join("fzf");
+    let (args, input) = (repo.root.join("fzf-args"), repo.root.join("fzf-input"));
+    let script = format!(
+        "#!/bin/sh\nprintf '%s\\n' \"$@\" > '{}'\ntee '{}' | sed -n 2p\n",
+        args.display(),
+        input.display()
+    );
+    fs::write(&fzf, script).unwrap();
+    fs::set_permissions(&fzf, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = env::var_os("PATH").unwrap_or_default();
+    let path = env::join_paths(iter::once(bin).chain(env::split_paths(&path))).unwrap();
+
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .env("PATH", &path)
+        .args([
+            "--picker", "fzf", "--print", "--limit", "1", "--query", "mid",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "middle\n");
+    assert_eq!(
+        fs::read_to_string(input).unwrap(),
+        "new\nmiddle\nold\nmain\n"
+    );
+    assert!(fs::read_to_string(args).unwrap().contains("--query\nmid\n"));
+}
+
+#[test]
+fn tmux_popup_acts_on_the_choice_made_in_the_popup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = three_branches();
+    let outside = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .env_remove("TMUX")
+        .args(["--tmux-popup", "--print"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&outside.stderr).contains("only works inside tmux"));
+
+    // A stand-in for tmux that runs the popup's command where asked, answering the
+    // numbered prompt with the second branch.
+    let bin = repo.root.join("bin");
+    fs::create_dir(&bin).unwrap();
+    let tmux = bin.join("tmux");
+    let script = "#!/bin/sh\ncd \"$4\" || exit 1\nshift 8\necho 2 | sh -c \"$1\"\n";
+    fs::write(&tmux, script).unwrap();
+    fs::set_permissions(&tmux, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = env::var_os("PATH").unwrap_or_default();
+    let path = env::join_paths(iter::once(bin).chain(env::split_paths(&path))).unwrap();
+
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .env("PATH", &path)
+        .env("TMUX", "/tmp/tmux-test/default,1,0")
+        .env("GIT_RECENT_SIMPLE", "true")
+        .args(["--tmux-popup", "--exec", "echo chose {}"])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "chose middle\n");
+}
+
+#[test]
+fn slow_checkout_sends_a_desktop_notification() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = three_branches();
+    // A stand-in for notify-send that logs its arguments.
+    let bin = repo.root.join("bin");
+    fs::create_dir(&bin).unwrap();
+    let log = repo.root.join("notify.log");
+    let notify_send = bin.join("notify-send");
+    let script = format!("#!/bin/sh\necho \"$*\" >> '{}'\n", log.display());
+    fs::write(&notify_send, script).unwrap();
+    fs::set_permissions(&notify_send, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = env::var_os("PATH").unwrap_or_default();
+    let path = env::join_paths(iter::once(bin).chain(env::split_paths(&path))).unwrap();
+    let checkout = |branch: &str, after: &str| {
+        let output = repo
+            .command(env!("CARGO_BIN_EXE_git-recent"))
+            .env("PATH", &path)
+            .env("GIT_RECENT_NOTIFY_AFTER", after)
+            .env_remove("WINDOWID")
+            .env_remove("__CFBundleIdentifier")
+            .args(["--checkout", branch, "--quiet"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    };
+
+    checkout("middle", "3600");
+    assert!(!log.exists());
+    checkout("old", "0");
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        "--app-name=git-recent git-recent Now on 'old'\n"
+    );
+}
+
+#[test]
+fn colocated_jj_repository_exports_bookmarks_and_switches_with_jj() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = three_branches();
+    fs::create_dir(repo.path().join(".jj")).unwrap();
+    // A stand-in for jj that logs its arguments; its export adds a bookmark.
+    let bin = repo.root.join("bin");
+    fs::create_dir(&bin).unwrap();
+    let log = repo.root.join("jj.log");
+    let jj = bin.join("jj");
+    let script = format!(
+        "#!/bin/sh\necho \"$*\" >> '{}'\n[ \"$1\" = git ] && git branch -f bookmark main\nexit 0\n",
+        log.display()
+    );
+    fs::write(&jj, script).unwrap();
+    fs::set_permissions(&jj, fs::Permissions::from_mode(0o755)).unwrap();
+    let path = env::var_os("PATH").unwrap_or_default();
+    let path = env::join_paths(iter::once(bin).chain(env::split_paths(&path))).unwrap();
+    let run = |args: &[&str], jj: Option<&str>| {
+        let mut command = repo.command(env!("CARGO_BIN_EXE_git-recent"));
+        command.env("PATH", &path).args(args);
+        if let Some(jj) = jj {
+            command.env("GIT_RECENT_JJ", jj);
+        }
+        let output = command.output().unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(run(&["--list"], None).lines().any(|b| b == "bookmark"));
+    fs::remove_file(&log).unwrap();
+    run(&["--checkout", "middle", "--quiet"], None);
+    assert_eq!(repo.current_branch(), "main");
+    assert_eq!(
+        fs::read_to_string(&log).unwrap(),
+        "git export --ignore-working-copy\nnew \"middle\"\n"
+    );
+
+    // `jj = false` leaves jj out.
+    fs::remove_file(&log).unwrap();
+    run(&["--checkout", "middle", "--quiet"], Some("false"));
+    assert_eq!(repo.current_branch(), "middle");
+    assert!(!log.exists());
+}
+
+#[test]
+fn serve_answers_json_rpc_requests() {
+    let repo = three_branches();
+    let mut child = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .arg("--serve")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let requests = [
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "list", "params": {"query": "d", "limit": 1}}"#,
+        r#"{"jsonrpc": "2.0", "id": 2, "method": "checkout", "params": {"branch": "middle"}}"#,
+        r#"{"jsonrpc": "2.0", "id": 3, "method": "delete", "params": {"branch": "old", "force": true}}"#,
+        r#"{"jsonrpc": "2.0", "method": "refresh"}"#,
+        r#"{"jsonrpc": "2.0", "id": 4, "method": "list"}"#,
+        r#"{"jsonrpc": "2.0", "id": 5, "method": "rebase"}"#,
+        "{not json",
+    ];
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(requests.join("\n").as_bytes()).unwrap();
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+
+    let responses = String::from_utf8(output.stdout).unwrap();
+    let responses: Vec<&str> = responses.lines().collect();
+    assert_eq!(responses.len(), 6, "{responses:?}");
+    // Most recent first, filtered and cut to the limit.
+    assert!(responses[0].starts_with(r#"{"jsonrpc": "2.0", "id": 1, "result": {"current": "main", "branches": [{"name": "middle", "#));
+    assert!(!responses[0].contains("\"old\""));
+    assert_eq!(
+        responses[1],
+        r#"{"jsonrpc": "2.0", "id": 2, "result": null}"#
+    );
+    assert_eq!(
+        responses[2],
+        r#"{"jsonrpc": "2.0", "id": 3, "result": null}"#
+    );
+    assert_eq!(repo.current_branch(), "middle");
+    assert!(responses[3].contains(r#""current": "middle""#));
+    assert!(!responses[3].contains("\"old\""));
+    assert!(responses[4].contains(r#""id": 5, "error": {"code": -32601"#));
+    assert!(responses[5].contains(r#""id": null, "error": {"code": -32700"#));
+}
+
+#[test]
+fn man_page_documents_every_option() {
+    let repo = TestRepo::new();
+    let output = repo.run(&["--man"]);
+    assert!(output.status.success());
+    let page = String::from_utf8(output.stdout).unwrap();
+    assert!(page.starts_with(".TH GIT\\-RECENT 1 "));
+    for option in ["repo", "checkout", "dry\\-run", "select\\-1", "man"] {
+        assert!(
+            page.contains(&format!("\\fB\\-\\-{option}\\fR")),
+            "--{option} is missing"
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn bash_completion_offers_branches_for_checkout() {
+    let repo = three_branches();
+    let script = String::from_utf8(repo.run(&["completions", "bash"]).stdout).unwrap();
+    // The script runs git-recent itself to list branches.
+    let bin = Path::new(env!("CARGO_BIN_EXE_git-recent"))
+        .parent()
+        .unwrap();
+    let path = env::var_os("PATH").unwrap_or_default();
+    let path = env::join_paths(iter::once(bin.to_path_buf()).chain(env::split_paths(&path)));
+    let complete = |words: &str| {
+        let output = repo
+            .command("bash")
+            .env("PATH", path.as_ref().unwrap())
+            .arg("-c")
+            .arg(format!(
+                "{script}\nCOMP_WORDS=({words}); COMP_CWORD=$((${{#COMP_WORDS[@]}} - 1))\n\
+                 _git_recent; echo \"${{COMPREPLY[*]}}\""
+            ))
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+    assert_eq!(complete("git-recent --checkout mi"), "middle");
+    assert_eq!(complete("git-recent --sort = na"), "name");
+    assert_eq!(complete("git-recent --che"), "--checkout");
+}
+
+#[cfg(unix)]
+#[test]
+fn branch_names_that_are_not_utf8_round_trip() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let repo = three_branches();
+    let name = OsStr::from_bytes(b"caf\xe9");
+    let status = repo
+        .command("git")
+        .args(["branch".as_ref(), name])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = repo.run(&["--list", "--sort", "name", "--limit", "1"]);
+    assert_eq!(output.stdout, b"caf\xe9\n");
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .args(["--quiet".as_ref(), "--checkout".as_ref(), name])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let current = repo
+        .command("git")
+        .args(["branch", "--show-current"])
+        .output()
+        .unwrap();
+    assert_eq!(current.stdout, b"caf\xe9\n");
+
+    // A genuine U+10FF80, which decoding otherwise uses to stand in for the byte 0x80.
+    let name = "a\u{10FF80}";
+    repo.git(&["branch", name]);
+    assert_eq!(
+        repo.lines(&["--list", "--sort", "name", "--limit", "1"]),
+        [name]
+    );
+    assert!(repo.run(&["--quiet", "--checkout", name]).status.success());
+    assert_eq!(repo.current_branch(), name);
+}
+
+#[test]
+fn detached_head_is_not_listed_and_last_returns() {
+    let repo = three_branches();
+    repo.git(&["checkout", "--quiet", "middle"]);
+    repo.git(&["checkout", "--quiet", "--detach", "old"]);
+    assert_eq!(repo.lines(&["--list"]), ["new", "middle", "old", "main"]);
+    assert!(repo.run(&["--last", "--quiet"]).status.success());
+    assert_eq!(repo.current_branch(), "middle");
+}
+
+#[test]
+fn bare_repository_lists_but_refuses_checkout() {
+    let repo = three_branches();
+    let bare = repo.root.join("bare.git");
+    repo.git(&["clone", "--quiet", "--bare", ".", bare.to_str().unwrap()]);
+    let run = |args: &[&str]| {
+        repo.command(env!("CARGO_BIN_EXE_git-recent"))
+            .current_dir(&bare)
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let output = run(&["--list"]);
+    assert_eq!(output.stdout, b"new\nmiddle\nold\nmain\n");
+    assert_eq!(run(&["--checkout", "old"]).status.code(), Some(10));
+}
+
+#[test]
+fn linked_worktree_checks_out_its_own_head() {
+    let repo = three_branches();
+    let worktree = repo.root.join("worktree");
+    repo.git(&[
+        "worktree",
+        "add",
+        "--quiet",
+        worktree.to_str().unwrap(),
+        "old",
+    ]);
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .current_dir(&worktree)
+        .args(["--checkout", "middle", "--quiet"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(repo.current_branch(), "main");
+    // `main` is taken by the main worktree.
+    let output = repo
+        .command(env!("CARGO_BIN_EXE_git-recent"))
+        .current_dir(&worktree)
+        .args(["--checkout", "main"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("is checked out in the worktree at"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn verbose_logs_git_commands() {
+    let repo = three_branches();
+    let output = repo.run(&["--list", "--verbose"]);
+    assert_eq!(output.stdout, b"new\nmiddle\nold\nmain\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr
+            .lines()
+            .any(|line| line.starts_with("git-recent: git for-each-ref")
+                && line.ends_with(", exit 0)")),
+        "{stderr}"
+    );
+
+    let log = repo.root.join("git-recent.log");
+    let output = repo.run(&["--list", "--log-file", log.to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+    assert!(
+        fs::read_to_string(&log)
+            .unwrap()
+            .contains("git for-each-ref")
+    );
+}
+
+#[test]
+fn timing_breaks_down_startup() {
+    let repo = three_branches();
+    let output = repo.run(&["--list", "--timing"]);
+    assert_eq!(output.stdout, b"new\nmiddle\nold\nmain\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    assert_eq!(lines.first(), Some(&"startup timing:"), "{stderr}");
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.trim_start().starts_with("git for-each-ref"))
+    );
+    assert!(lines.last().unwrap().trim_start().starts_with("total"));
+}
+
+#[test]
+fn doctor_reports_checks_and_fails_on_problems() {
+    let repo = three_branches();
+    let output = repo.run(&["doctor"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let repository = stdout
+        .lines()
+        .find(|line| line.contains("repository"))
+        .unwrap();
+    assert!(repository.starts_with("ok"), "{stdout}");
+
+    fs::write(
+        repo.path().join(".git-recent.toml"),
+        "sort = \"sideways\"\n",
+    )
+    .unwrap();
+    let output = repo.run(&["doctor"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.starts_with("PROBLEM") && line.contains("sideways")),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn index_lock_is_reported_before_checkout() {
+    let repo = three_branches();
+    fs::write(repo.path().join(".git/index.lock"), "").unwrap();
+    let output = repo.run(&["--checkout", "old"]);
+    assert_eq!(output.status.code(), Some(11));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("index.lock' exists"), "{stderr}");
+    fs::remove_file(repo.path().join(".git/index.lock")).unwrap();
+    assert_eq!(repo.current_branch(), "main");
+}
+
+#[test]
+fn git_dir_and_work_tree_from_the_environment() {
+    let repo = three_branches();
+    let outside = repo.root.join("home");
+    let run = |args: &[&str]| {
+        repo.command(env!("CARGO_BIN_EXE_git-recent"))
+            .current_dir(&outside)
+            .env("GIT_DIR", repo.path().join(".git"))
+            .env("GIT_WORK_TREE", repo.path())
+            .args(args)
+            .output()
+            .unwrap()
+    };
+    let output = run(&["--list"]);
+    assert_eq!(output.stdout, b"new\nmiddle\nold\nmain\n");
+    assert!(run(&["--checkout", "old", "--quiet"]).status.success());
+    assert_eq!(repo.current_branch(), "old");
+    // Checking out wrote the work tree, not the directory git-recent ran in.
+    assert!(repo.path().join("old").is_file());
+    assert!(!outside.join("old").exists());
+}
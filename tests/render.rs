@@ -0,0 +1,501 @@
+//! Snapshot tests for the picker's frames. Each frame is compared with
+//! `tests/snapshots/<name>.txt`, with escape characters written as `\e`.
+//! Run with `UPDATE_SNAPSHOTS=1` to rewrite the snapshots after an intended change.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use git_recent::annotate::AnnotationLoader;
+use git_recent::app::{App, NO_OF_VISIBLE_BRANCHES};
+use git_recent::backend::{GitBackend, MockGit};
+use git_recent::git::{BranchStatus, LoadOptions};
+use git_recent::i18n;
+use git_recent::keys::Key;
+use git_recent::loader::StatusLoader;
+use git_recent::pulls::{Forge, PullRequest, State};
+use git_recent::screen::Screen;
+use git_recent::theme::{ColorDepth, Theme};
+
+/// A picker over `count` branches named `branch-1`, `branch-2`, …, with `current`
+/// checked out.
+fn picker(count: usize, current: &str) -> App {
+    picker_with(mock(count), current)
+}
+
+/// A repository with `count` branches named `branch-1`, `branch-2`, …
+fn mock(count: usize) -> MockGit {
+    let branches: Vec<String> = (1..=count).map(|i| format!("branch-{i}")).collect();
+    let names: Vec<&str> = branches.iter().map(String::as_str).collect();
+    MockGit::new(&names)
+}
+
+/// A picker over the branches of `git`, which then serves its git commands.
+fn picker_with(git: MockGit, current: &str) -> App {
+    let mut app = new_app(git.branches.clone(), current, LoadOptions::default());
+    app.git = Box::new(git);
+    app
+}
+
+/// `App::new`, with English text whatever the locale of the test run.
+fn new_app(branches: Vec<String>, current: &str, load: LoadOptions) -> App {
+    i18n::set_language("en");
+    App::new(branches, current.to_string(), load)
+}
+
+fn press(app: &mut App, key: Key, times: usize) {
+    for _ in 0..times {
+        assert_eq!(
+            app.handle_key(key.clone()),
+            None,
+            "{key:?} closed the picker"
+        );
+    }
+}
+
+fn assert_snapshot(name: &str, app: &App) {
+    assert_text_snapshot(name, &app.frame().join("\n"));
+}
+
+fn assert_text_snapshot(name: &str, text: &str) {
+    let actual = text.replace('\x1b', "\\e") + "\n";
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read {} ({e}); run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert_eq!(
+        actual,
+        expected,
+        "frame differs from {}; run with UPDATE_SNAPSHOTS=1 if the change is intended",
+        path.display()
+    );
+}
+
+#[test]
+fn first_branch_selected() {
+    assert_snapshot("first_branch_selected", &picker(3, "branch-1"));
+}
+
+#[test]
+fn current_branch_not_selected() {
+    assert_snapshot("current_branch_not_selected", &picker(3, "branch-2"));
+}
+
+#[test]
+fn selection_moves_down() {
+    let mut app = picker(3, "branch-1");
+    press(&mut app, Key::Down, 2);
+    assert_snapshot("selection_moves_down", &app);
+}
+
+#[test]
+fn single_branch() {
+    assert_snapshot("single_branch", &picker(1, "branch-1"));
+}
+
+#[test]
+fn exactly_one_page() {
+    assert_snapshot(
+        "exactly_one_page",
+        &picker(NO_OF_VISIBLE_BRANCHES, "branch-1"),
+    );
+}
+
+#[test]
+fn longer_list_is_cut_at_a_page() {
+    assert_snapshot("longer_list_is_cut_at_a_page", &picker(12, "branch-1"));
+}
+
+#[test]
+fn scrolled_to_the_middle() {
+    let mut app = picker(12, "branch-1");
+    press(&mut app, Key::Down, NO_OF_VISIBLE_BRANCHES + 1);
+    assert_snapshot("scrolled_to_the_middle", &app);
+}
+
+#[test]
+fn scrolled_to_the_end() {
+    let mut app = picker(12, "branch-1");
+    press(&mut app, Key::End, 1);
+    assert_snapshot("scrolled_to_the_end", &app);
+}
+
+#[test]
+fn taller_window() {
+    let mut app = picker(12, "branch-1");
+    app.height = 8;
+    press(&mut app, Key::PageDown, 1);
+    assert_snapshot("taller_window", &app);
+}
+
+#[test]
+fn rgb_colors_fall_back_to_the_terminal_depth() {
+    let colors = [("highlight", "#ff8700"), ("highlight_text", "236")];
+    let mut app = picker(3, "branch-1");
+    app.theme = Theme::from_colors(colors, ColorDepth::TrueColor).unwrap();
+    let mut frames = app.frame();
+    for depth in [ColorDepth::Indexed, ColorDepth::Basic] {
+        app.theme = Theme::from_colors(colors, depth).unwrap();
+        frames.extend(app.frame());
+    }
+    assert_text_snapshot(
+        "rgb_colors_fall_back_to_the_terminal_depth",
+        &frames.join("\n"),
+    );
+}
+
+#[test]
+fn filter_without_matches() {
+    let mut app = picker(3, "branch-1");
+    app.set_filter("nothing".to_string());
+    assert_snapshot("filter_without_matches", &app);
+}
+
+#[test]
+fn protected_branch_is_locked() {
+    let mut app = picker(3, "branch-1");
+    app.protected = vec!["branch-3".to_string()];
+    assert_snapshot("protected_branch_is_locked", &app);
+}
+
+#[test]
+fn ascii_mode_draws_without_glyphs_or_colors() {
+    let mut git = mock(3);
+    git.status.insert(
+        "branch-2".to_string(),
+        BranchStatus {
+            ahead: Some(2),
+            behind: Some(5),
+            ..BranchStatus::default()
+        },
+    );
+    let diff = vec!["\u{1b}[32m+added\u{1b}[m".to_string()];
+    git.diffs.insert("branch-1...branch-2".to_string(), diff);
+    let long_diff = (1..=500).map(|i| format!("+line {i}")).collect();
+    git.diffs
+        .insert("branch-1...branch-3".to_string(), long_diff);
+    let mut app = picker_with(git, "branch-1");
+    app.ascii = true;
+    app.theme = Theme::ascii();
+    app.protected = vec!["branch-3".to_string()];
+    app.load_status();
+    press(&mut app, Key::Down, 1);
+    press(&mut app, Key::Char('p'), 1);
+    let frame = app.frame().join("\n");
+    assert!(frame.is_ascii());
+    assert_snapshot("ascii_mode", &app);
+    // Catalog text is spelled out in ASCII too, in any language.
+    press(&mut app, Key::Down, 1);
+    press(&mut app, Key::Ctrl('d'), 100);
+    let frame = app.frame().join("\n");
+    assert!(frame.is_ascii(), "{frame}");
+    assert!(frame.contains("(lines 387-401 of 401)"), "{frame}");
+    assert!(
+        frame.contains("... only the first 400 lines are shown"),
+        "{frame}"
+    );
+    assert_eq!(i18n::to_ascii("(geschützt)"), "(geschuetzt)");
+}
+
+#[test]
+fn no_color_keeps_the_glyphs_but_only_reverse_video() {
+    let mut git = mock(3);
+    let diff = vec!["\u{1b}[32m+added\u{1b}[m".to_string()];
+    git.diffs.insert("branch-1...branch-2".to_string(), diff);
+    let mut app = picker_with(git, "branch-1");
+    app.no_color = true;
+    app.theme = Theme::ascii();
+    press(&mut app, Key::Down, 1);
+    press(&mut app, Key::Char('p'), 1);
+    press(&mut app, Key::Char('a'), 1);
+    assert_snapshot("no_color", &app);
+}
+
+#[test]
+fn redraw_rewrites_only_changed_rows() {
+    let mut app = picker(3, "branch-1");
+    let mut screen = Screen::new();
+    screen.update(&app.frame());
+    press(&mut app, Key::Down, 1);
+    assert_text_snapshot(
+        "redraw_rewrites_only_changed_rows",
+        &screen.update(&app.frame()),
+    );
+}
+
+#[test]
+fn accessible_announcement() {
+    let mut app = picker(12, "branch-1");
+    app.protected = vec!["branch-3".to_string()];
+    press(&mut app, Key::Down, 2);
+    assert_text_snapshot("accessible_announcement", &app.announcement().join("\n"));
+}
+
+#[test]
+fn accessible_mode_announces_only_changes() {
+    let mut app = picker(12, "branch-1");
+    app.accessible = true;
+    app.announcement_update();
+    press(&mut app, Key::Down, 1);
+    assert_text_snapshot(
+        "accessible_mode_announces_only_changes",
+        &app.announcement_update(),
+    );
+}
+
+#[test]
+fn detached_head_in_header() {
+    let mut app = picker(3, "");
+    app.detached = Some("1a2b3c4".to_string());
+    app.select_branch("branch-2");
+    app.set_message("Enter returns to 'branch-2'".to_string());
+    assert_snapshot("detached_head_in_header", &app);
+}
+
+#[test]
+fn branch_in_another_worktree_is_marked() {
+    let mut git = mock(3);
+    git.worktrees
+        .insert("branch-3".to_string(), "/work/repo-branch-3".into());
+    let mut app = picker_with(git, "branch-1");
+    // Reloading asks the backend which branches other worktrees hold.
+    press(&mut app, Key::F(5), 1);
+    assert_snapshot("branch_in_another_worktree_is_marked", &app);
+}
+
+#[test]
+fn delete_offers_force_only_for_unmerged_branches() {
+    let mut git = mock(3);
+    git.unmerged.push("branch-2".to_string());
+    git.worktrees
+        .insert("branch-3".to_string(), "/work/repo-branch-3".into());
+    let mut app = picker_with(git, "branch-1");
+    app.confirm_delete = false;
+    press(&mut app, Key::Down, 2);
+    press(&mut app, Key::Char('x'), 1);
+    assert_snapshot("delete_fails_in_another_worktree", &app);
+    press(&mut app, Key::Up, 1);
+    press(&mut app, Key::Char('x'), 1);
+    assert_snapshot("delete_offers_force_for_unmerged", &app);
+}
+
+#[test]
+fn created_and_renamed_branches_survive_a_reload() {
+    let mut app = picker(3, "branch-1");
+    press(&mut app, Key::Char('n'), 1);
+    press(&mut app, Key::Paste("topic".to_string()), 1);
+    press(&mut app, Key::Enter, 1);
+    press(&mut app, Key::Char('m'), 1);
+    press(&mut app, Key::Ctrl('u'), 1);
+    press(&mut app, Key::Paste("feature".to_string()), 1);
+    press(&mut app, Key::Enter, 1);
+    // Reloading lists what the backend now has, not what the picker remembers.
+    press(&mut app, Key::F(5), 1);
+    assert_snapshot("created_and_renamed_branches_survive_a_reload", &app);
+    // The backend refuses a second branch of the same name.
+    press(&mut app, Key::Char('n'), 1);
+    press(&mut app, Key::Paste("branch-2".to_string()), 1);
+    press(&mut app, Key::Enter, 1);
+    let message = app.frame().pop().unwrap();
+    assert!(
+        message.contains("fatal: a branch named 'branch-2' already exists"),
+        "{message}"
+    );
+}
+
+#[test]
+fn limit_hides_branches_until_filtered() {
+    let branches: Vec<String> = (1..=8).map(|i| format!("branch-{i}")).collect();
+    let load = LoadOptions {
+        limit: Some(3),
+        ..LoadOptions::default()
+    };
+    let mut app = new_app(branches, "branch-1", load);
+    assert_snapshot("limit_hides_branches_until_filtered", &app);
+    app.set_filter("8".to_string());
+    assert_eq!(app.selected_branch().map(String::as_str), Some("branch-8"));
+}
+
+#[test]
+fn status_of_shown_branches() {
+    let mut git = mock(3);
+    git.status.insert(
+        "branch-1".to_string(),
+        BranchStatus {
+            ahead: Some(2),
+            behind: Some(0),
+            merged: false,
+        },
+    );
+    git.status.insert(
+        "branch-3".to_string(),
+        BranchStatus {
+            ahead: Some(0),
+            behind: Some(5),
+            merged: true,
+        },
+    );
+    let mut app = picker_with(git, "branch-1");
+    app.load_status();
+    assert_snapshot("status_of_shown_branches", &app);
+}
+
+#[test]
+fn pull_requests_follow_their_branches() {
+    let mut app = picker(4, "branch-1");
+    let pull = |number, state| PullRequest {
+        number,
+        state,
+        forge: Forge::GitHub,
+    };
+    app.pulls = [
+        ("branch-1".to_string(), pull(42, State::Draft)),
+        ("branch-2".to_string(), pull(17, State::Approved)),
+        ("branch-4".to_string(), pull(9, State::Merged)),
+    ]
+    .into_iter()
+    .collect();
+    assert_snapshot("pull_requests_follow_their_branches", &app);
+}
+
+#[test]
+fn menu_opens_the_pull_request() {
+    let mut git = mock(3);
+    git.pull_requests.push("branch-2".to_string());
+    let mut app = picker_with(git, "branch-1");
+    press(&mut app, Key::Down, 1);
+    press(&mut app, Key::Char('a'), 1);
+    assert_snapshot("menu_of_a_branch", &app);
+    press(&mut app, Key::Char('8'), 1);
+    let message = app.frame().pop().unwrap();
+    assert_eq!(
+        message,
+        "Opened the pull request of 'branch-2' in the browser"
+    );
+}
+
+#[test]
+fn yank_copies_the_branch_name() {
+    let mut app = picker(3, "branch-1");
+    press(&mut app, Key::Down, 2);
+    press(&mut app, Key::Char('y'), 1);
+    let message = app.frame().pop().unwrap();
+    assert_eq!(message, "Copied 'branch-3' to the clipboard");
+}
+
+#[test]
+fn preview_shows_and_scrolls_the_diff_of_the_highlighted_branch() {
+    let mut git = mock(3);
+    let diff = (1..=20).map(|i| format!("+line {i}")).collect();
+    git.diffs.insert("branch-1...branch-2".to_string(), diff);
+    let mut app = picker_with(git, "branch-1");
+    press(&mut app, Key::Down, 1);
+    press(&mut app, Key::Char('p'), 1);
+    assert_snapshot("preview_of_a_branch", &app);
+    press(&mut app, Key::Ctrl('d'), 2);
+    let frame = app.frame();
+    assert!(frame.iter().any(|line| line.contains("(lines 6–20 of 20)")));
+    assert_eq!(frame.last().unwrap(), "+line 20\u{1b}[0m");
+    // Moving on loads the next branch's diff, which is empty.
+    press(&mut app, Key::Down, 1);
+    assert_eq!(
+        app.frame().last().unwrap(),
+        "   'branch-3' makes no changes"
+    );
+    press(&mut app, Key::Char('p'), 1);
+    assert!(!app.frame().iter().any(|line| line.contains("Changes on")));
+}
+
+#[test]
+fn status_is_loaded_only_near_the_shown_rows() {
+    let mut git = mock(12);
+    git.status.insert(
+        "branch-12".to_string(),
+        BranchStatus {
+            ahead: Some(1),
+            ..BranchStatus::default()
+        },
+    );
+    let mut app = picker_with(git, "branch-1");
+    app.load_status();
+    press(&mut app, Key::End, 1);
+    let row = |app: &App| {
+        app.frame()
+            .into_iter()
+            .find(|line| line.contains("branch-12"))
+            .unwrap()
+    };
+    assert!(!row(&app).contains('↑'));
+    app.load_status();
+    assert!(row(&app).contains("branch-12 ↑1"));
+}
+
+#[test]
+fn background_status_fills_in_when_it_arrives() {
+    let mut app = picker(3, "branch-1");
+    let mut git = mock(3);
+    git.status.insert(
+        "branch-2".to_string(),
+        BranchStatus {
+            behind: Some(3),
+            ..BranchStatus::default()
+        },
+    );
+    app.status_loader = Some(StatusLoader::spawn(
+        move |branches, scope, counts_against, merged_into| {
+            git.branch_status(branches, scope, counts_against, merged_into)
+        },
+    ));
+    app.load_status();
+    assert_snapshot("first_branch_selected", &app);
+    assert!(app.receive_status(Duration::from_secs(10)));
+    assert!(app.frame().contains(&"   branch-2 ↓3".to_string()));
+}
+
+#[test]
+fn annotations_fill_in_after_their_branches() {
+    let mut app = picker(3, "branch-1");
+    app.annotation_loader = Some(AnnotationLoader::spawn(|branch| {
+        (branch == "branch-2").then(|| "build passed".to_string())
+    }));
+    app.load_annotations();
+    while app.frame().iter().all(|row| !row.contains("build passed")) {
+        assert!(app.receive_annotations(Duration::from_secs(10)));
+    }
+    assert!(
+        app.frame()
+            .contains(&"   branch-2 build passed".to_string())
+    );
+}
+
+#[test]
+fn a_hung_annotation_holds_up_no_other_branch() {
+    let loader = AnnotationLoader::spawn(|branch| {
+        if branch == "branch-1" {
+            thread::sleep(Duration::from_secs(3600));
+        }
+        Some(format!("{branch} passed"))
+    });
+    let branches: Vec<String> = (1..=20).map(|i| format!("branch-{i}")).collect();
+    loader.request(1, branches[..10].to_vec());
+    loader.request(2, branches[10..].to_vec());
+    let mut annotated = Vec::new();
+    while annotated.len() < 19 {
+        let arrived = loader.receive(Duration::from_secs(10));
+        assert!(!arrived.is_empty(), "only {annotated:?} arrived");
+        annotated.extend(arrived.into_iter().map(|a| a.branch));
+    }
+    assert!(!annotated.contains(&"branch-1".to_string()));
+}